@@ -0,0 +1,337 @@
+//! 命令行参数定义
+
+use crate::format::Algorithm;
+use crate::integrity::DigestAlgorithm;
+use crate::kdf;
+use crate::matrix::BenchCipher;
+use crate::metrics::ReportFormat;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+pub const DEFAULT_CHUNK_SIZE: u32 = 16 * 1024; // 16KB
+pub const DEFAULT_SYNTHETIC_SIZE: usize = 64 * 1024 * 1024; // 64MB
+
+#[derive(Parser)]
+#[command(name = "faes", version, about = "分块认证加密工具")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// 加密文件
+    Encrypt(EncryptArgs),
+    /// 解密文件或其中一段明文区间，任一分块认证失败即以非零状态退出
+    Decrypt(DecryptArgs),
+    /// 认证整个密文而不输出明文，可选地与原文或已知的明文摘要比较
+    Verify(VerifyArgs),
+    /// 加密再解密一个文件，报告吞吐量、分块延迟与资源占用
+    Bench(BenchArgs),
+    /// 在内存中对比多种算法、模式和分块大小的吞吐量与资源占用
+    BenchMatrix(BenchMatrixArgs),
+    /// 生成随机原始密钥文件
+    Keygen(KeygenArgs),
+    /// 基于 recrypt 代理重加密的文件信封
+    #[command(subcommand)]
+    Pre(PreCommand),
+}
+
+#[derive(Subcommand)]
+pub enum PreCommand {
+    /// 生成 recrypt 密钥对：私钥写入输出文件，公钥写入同名 `.pub` 文件
+    Keygen(PreKeygenArgs),
+    /// 由委托方私钥生成指向被委托方公钥的变换密钥
+    TransformKey(PreTransformKeyArgs),
+    /// 加密文件给 recrypt 公钥
+    Encrypt(PreEncryptArgs),
+    /// 代理用变换密钥转交信封，只改写头部，正文原样复制
+    Delegate(PreDelegateArgs),
+    /// 用 recrypt 私钥解密信封
+    Decrypt(PreDecryptArgs),
+}
+
+/// 加密时的密钥来源，三者必选其一
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub struct EncryptKeyArgs {
+    /// 原始密钥文件（二进制或十六进制文本，长度须与算法匹配）
+    #[arg(long, value_name = "PATH")]
+    pub key_file: Option<PathBuf>,
+
+    /// 口令，也可通过环境变量 FAES_PASSPHRASE 提供
+    #[arg(long, env = "FAES_PASSPHRASE", hide_env_values = true)]
+    pub passphrase: Option<String>,
+
+    /// X25519 接收者公钥或公钥文件，可重复指定多个接收者
+    #[arg(short, long, value_name = "PUBKEY")]
+    pub recipient: Vec<String>,
+}
+
+/// 解密时的密钥来源，三者必选其一
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub struct KeyArgs {
+    /// 原始密钥文件（二进制或十六进制文本，长度须与算法匹配）
+    #[arg(long, value_name = "PATH")]
+    pub key_file: Option<PathBuf>,
+
+    /// 口令，也可通过环境变量 FAES_PASSPHRASE 提供
+    #[arg(long, env = "FAES_PASSPHRASE", hide_env_values = true)]
+    pub passphrase: Option<String>,
+
+    /// X25519 私钥文件
+    #[arg(short, long, value_name = "PATH")]
+    pub identity: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct EncryptArgs {
+    /// 输入文件，`-` 表示标准输入
+    pub input: PathBuf,
+
+    /// 输出文件，`-` 表示标准输出
+    #[arg(short, long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// aes128-gcm、aes256-gcm 或 chacha20-poly1305
+    #[arg(long, default_value_t = Algorithm::Aes256Gcm)]
+    pub cipher: Algorithm,
+
+    /// 明文分块大小（字节）
+    #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+    pub chunk_size: u32,
+
+    /// 同时计算明文摘要（sha256 或 blake3）并作为认证尾部写入密文
+    #[arg(long, value_name = "ALG")]
+    pub digest: Option<DigestAlgorithm>,
+
+    /// 工作线程数，0 表示使用全部 CPU 核心；输出与线程数无关
+    #[arg(long, default_value_t = 0)]
+    pub threads: usize,
+
+    #[command(flatten)]
+    pub key: EncryptKeyArgs,
+
+    #[command(flatten)]
+    pub kdf: KdfArgs,
+}
+
+/// 口令加密时使用的 Argon2id 代价参数，写入文件头部
+#[derive(Args)]
+pub struct KdfArgs {
+    /// 内存代价（KiB）
+    #[arg(long, default_value_t = kdf::DEFAULT_M_COST)]
+    pub kdf_memory: u32,
+
+    /// 迭代次数
+    #[arg(long, default_value_t = kdf::DEFAULT_T_COST)]
+    pub kdf_iterations: u32,
+
+    /// 并行度
+    #[arg(long, default_value_t = kdf::DEFAULT_P_COST)]
+    pub kdf_parallelism: u32,
+}
+
+#[derive(Args)]
+pub struct DecryptArgs {
+    /// 输入密文，`-` 表示标准输入
+    pub input: PathBuf,
+
+    /// 输出文件，`-` 表示标准输出
+    #[arg(short, long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// 工作线程数，0 表示使用全部 CPU 核心；输出与线程数无关
+    #[arg(long, default_value_t = 0)]
+    pub threads: usize,
+
+    /// 只解密从该明文偏移开始的区间，输入须为可定位的文件
+    #[arg(long, value_name = "BYTES")]
+    pub offset: Option<u64>,
+
+    /// 区间长度（字节），缺省时到明文末尾
+    #[arg(long, value_name = "BYTES")]
+    pub length: Option<u64>,
+
+    #[command(flatten)]
+    pub key: KeyArgs,
+}
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// 输入密文，`-` 表示标准输入
+    pub input: PathBuf,
+
+    /// 与解密结果逐字节比较的原文
+    #[arg(long, value_name = "PATH", conflicts_with = "expect_digest")]
+    pub plaintext: Option<PathBuf>,
+
+    /// 与密文中记录的明文摘要比较（十六进制），无需原文
+    #[arg(long, value_name = "HEX")]
+    pub expect_digest: Option<String>,
+
+    /// 工作线程数，0 表示使用全部 CPU 核心；输出与线程数无关
+    #[arg(long, default_value_t = 0)]
+    pub threads: usize,
+
+    #[command(flatten)]
+    pub key: KeyArgs,
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// 测试用的输入文件
+    pub input: PathBuf,
+
+    /// 存放中间密文和解密结果的目录，默认为系统临时目录
+    #[arg(long, value_name = "DIR")]
+    pub work_dir: Option<PathBuf>,
+
+    /// aes128-gcm、aes256-gcm 或 chacha20-poly1305
+    #[arg(long, default_value_t = Algorithm::Aes128Gcm)]
+    pub cipher: Algorithm,
+
+    /// 明文分块大小（字节）
+    #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+    pub chunk_size: u32,
+
+    /// 工作线程数，0 表示使用全部 CPU 核心；输出与线程数无关
+    #[arg(long, default_value_t = 0)]
+    pub threads: usize,
+
+    /// 报告格式：text、json 或 csv
+    #[arg(long, default_value_t = ReportFormat::Text)]
+    pub format: ReportFormat,
+
+    /// 报告输出文件，默认为标准输出；CSV 文件已存在时追加数据行
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct BenchMatrixArgs {
+    /// 测试用的输入文件（整个读入内存），缺省时生成随机输入
+    pub input: Option<PathBuf>,
+
+    /// 随机输入的大小（字节）
+    #[arg(long, default_value_t = DEFAULT_SYNTHETIC_SIZE, conflicts_with = "input")]
+    pub size: usize,
+
+    /// 参与对比的算法，逗号分隔，默认全部
+    #[arg(long, value_delimiter = ',', default_values_t = BenchCipher::ALL)]
+    pub ciphers: Vec<BenchCipher>,
+
+    /// 参与对比的分块大小（字节），逗号分隔
+    #[arg(long, value_delimiter = ',', default_values_t = [4 * 1024, DEFAULT_CHUNK_SIZE, 64 * 1024, 1024 * 1024])]
+    pub chunk_sizes: Vec<u32>,
+
+    /// 工作线程数，0 表示使用全部 CPU 核心
+    #[arg(long, default_value_t = 1)]
+    pub threads: usize,
+
+    /// 报告格式：text、json 或 csv
+    #[arg(long, default_value_t = ReportFormat::Text)]
+    pub format: ReportFormat,
+
+    /// 报告输出文件，默认为标准输出；CSV 文件已存在时追加数据行
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct KeygenArgs {
+    /// 输出的密钥文件，已存在时报错
+    pub output: PathBuf,
+
+    /// 密钥长度：128 或 256 位
+    #[arg(long, default_value_t = 256, conflicts_with = "x25519")]
+    pub bits: usize,
+
+    /// 改为生成 X25519 密钥对：私钥写入输出文件，公钥写入同名 `.pub` 文件
+    #[arg(long)]
+    pub x25519: bool,
+}
+
+#[derive(Args)]
+pub struct PreKeygenArgs {
+    /// 输出的私钥文件，已存在时报错
+    pub output: PathBuf,
+}
+
+#[derive(Args)]
+pub struct PreTransformKeyArgs {
+    /// 委托方私钥文件
+    #[arg(long, value_name = "PATH")]
+    pub from: PathBuf,
+
+    /// 被委托方公钥文件
+    #[arg(long, value_name = "PATH")]
+    pub to: PathBuf,
+
+    /// 输出的变换密钥文件
+    #[arg(short, long)]
+    pub output: PathBuf,
+}
+
+#[derive(Args)]
+pub struct PreEncryptArgs {
+    /// 输入文件，`-` 表示标准输入
+    pub input: PathBuf,
+
+    /// 输出文件，`-` 表示标准输出
+    #[arg(short, long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// 接收者 recrypt 公钥文件
+    #[arg(long, value_name = "PATH")]
+    pub to: PathBuf,
+
+    /// aes256-gcm 或 chacha20-poly1305
+    #[arg(long, default_value_t = Algorithm::Aes256Gcm)]
+    pub cipher: Algorithm,
+
+    /// 明文分块大小（字节）
+    #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+    pub chunk_size: u32,
+
+    /// 同时计算明文摘要（sha256 或 blake3）并作为认证尾部写入密文
+    #[arg(long, value_name = "ALG")]
+    pub digest: Option<DigestAlgorithm>,
+
+    /// 工作线程数，0 表示使用全部 CPU 核心；输出与线程数无关
+    #[arg(long, default_value_t = 0)]
+    pub threads: usize,
+}
+
+#[derive(Args)]
+pub struct PreDelegateArgs {
+    /// 输入信封，`-` 表示标准输入
+    pub input: PathBuf,
+
+    /// 输出信封，`-` 表示标准输出
+    #[arg(short, long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// 变换密钥文件
+    #[arg(long, value_name = "PATH")]
+    pub transform_key: PathBuf,
+}
+
+#[derive(Args)]
+pub struct PreDecryptArgs {
+    /// 输入信封，`-` 表示标准输入
+    pub input: PathBuf,
+
+    /// 输出文件，`-` 表示标准输出
+    #[arg(short, long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// recrypt 私钥文件
+    #[arg(short, long, value_name = "PATH")]
+    pub identity: PathBuf,
+
+    /// 工作线程数，0 表示使用全部 CPU 核心；输出与线程数无关
+    #[arg(long, default_value_t = 0)]
+    pub threads: usize,
+}
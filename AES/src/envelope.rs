@@ -0,0 +1,192 @@
+//! 基于 recrypt 代理重加密的文件信封
+//!
+//! 正文使用随机 `Plaintext` 经 `derive_symmetric_key` 得到的 256 位密钥，按 FAES 流格式
+//! （原始密钥模式）加密；`Plaintext` 本身用 recrypt 加密给接收者公钥，按 recrypt 线格式
+//! （`EncryptedValue::to_wire_bytes`）序列化后放在信封头部，`TransformKey` 文件同样使用线格式。
+//! 代理只需用 `TransformKey` 变换这个很小的头部，即可把文件转交给新的接收者，正文原样复制，
+//! 代理始终接触不到明文或对称密钥。
+//!
+//! | 字段 | 长度 |
+//! |------|------|
+//! | 魔数 `FPRE` | 4 |
+//! | 版本 | 1 |
+//! | `EncryptedValue` 长度（u32 大端） | 4 |
+//! | `EncryptedValue` | 变长 |
+//! | FAES 流 | 其余 |
+//!
+//! 信封头部不参与正文的 AAD，因此变换后正文仍能通过认证；头部被篡改时
+//! recrypt 的签名与认证哈希校验会失败，或者派生出错误的密钥使正文认证失败。
+
+use crate::format::{invalid_data, invalid_input};
+use recrypt::api::{EncryptedValue, PrivateKey, PublicKey, RecryptErr, TransformKey};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+pub const MAGIC: [u8; 4] = *b"FPRE";
+pub const VERSION: u8 = 1;
+
+const PUBLIC_KEY_LEN: usize = PublicKey::ENCODED_SIZE_BYTES;
+
+/// 头部长度上限，防止恶意长度字段导致超大内存分配
+const MAX_VALUE_LEN: u32 = 1024 * 1024;
+
+fn recrypt_err(err: RecryptErr) -> io::Error {
+    invalid_data(err.to_string())
+}
+
+/// 写出信封头部：魔数、版本和序列化后的 `EncryptedValue`
+pub fn write_header<W: Write>(writer: &mut W, value: &EncryptedValue) -> io::Result<()> {
+    let encoded = value.to_wire_bytes();
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&[VERSION])?;
+    writer.write_all(&(encoded.len() as u32).to_be_bytes())?;
+    writer.write_all(&encoded)
+}
+
+/// 读取信封头部，读取后 `reader` 停在 FAES 流的开头
+pub fn read_header<R: Read>(reader: &mut R) -> io::Result<EncryptedValue> {
+    let mut fixed = [0u8; 9];
+    reader.read_exact(&mut fixed)?;
+    if fixed[..4] != MAGIC {
+        return Err(invalid_data("不是有效的代理重加密信封（魔数不匹配）"));
+    }
+    if fixed[4] != VERSION {
+        return Err(invalid_data(format!("不支持的信封版本: {}", fixed[4])));
+    }
+    let len = u32::from_be_bytes([fixed[5], fixed[6], fixed[7], fixed[8]]);
+    if len > MAX_VALUE_LEN {
+        return Err(invalid_data(format!("信封头部过大: {} 字节", len)));
+    }
+    let mut encoded = vec![0u8; len as usize];
+    reader.read_exact(&mut encoded)?;
+    EncryptedValue::from_wire_bytes(&encoded).map_err(recrypt_err)
+}
+
+/// 公钥以 `x || y` 的十六进制文本保存
+pub fn encode_public_key(key: &PublicKey) -> String {
+    let (x, y) = key.bytes_x_y();
+    hex::encode([x.as_slice(), y.as_slice()].concat())
+}
+
+pub fn read_public_key(path: &Path) -> io::Result<PublicKey> {
+    let bytes = hex::decode(fs::read_to_string(path)?.trim()).map_err(invalid_data)?;
+    if bytes.len() != PUBLIC_KEY_LEN {
+        return Err(invalid_data(format!(
+            "公钥应为 {} 字节，实际为 {} 字节",
+            PUBLIC_KEY_LEN,
+            bytes.len()
+        )));
+    }
+    PublicKey::new_from_slice(bytes.split_at(PUBLIC_KEY_LEN / 2)).map_err(recrypt_err)
+}
+
+pub fn read_private_key(path: &Path) -> io::Result<PrivateKey> {
+    let text = Zeroizing::new(fs::read_to_string(path)?);
+    let bytes = Zeroizing::new(hex::decode(text.trim()).map_err(invalid_data)?);
+    PrivateKey::new_from_slice(&bytes).map_err(recrypt_err)
+}
+
+/// 以十六进制写入私钥，Unix 上文件权限为 0600，文件已存在时报错
+pub fn write_private_key(path: &Path, key: &PrivateKey) -> io::Result<()> {
+    let encoded = Zeroizing::new(hex::encode(key.bytes()));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    writeln!(file, "{}", &*encoded)?;
+    file.sync_all()
+}
+
+pub fn read_transform_key(path: &Path) -> io::Result<TransformKey> {
+    let bytes = hex::decode(fs::read_to_string(path)?.trim()).map_err(invalid_data)?;
+    TransformKey::from_wire_bytes(&bytes).map_err(recrypt_err)
+}
+
+pub fn write_transform_key(path: &Path, key: &TransformKey) -> io::Result<()> {
+    fs::write(path, format!("{}\n", hex::encode(key.to_wire_bytes())))
+}
+
+/// 信封正文要求 256 位密钥
+pub fn check_body_key_len(key_len: usize) -> io::Result<()> {
+    if key_len != 32 {
+        return Err(invalid_input("代理重加密信封的正文需使用 256 位密钥的算法"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use recrypt::api::{CryptoOps, Ed25519Ops, KeyGenOps, ProtocolVersion, Recrypt};
+
+    #[test]
+    fn header_and_transform_key_roundtrip_every_protocol_version() {
+        for version in [ProtocolVersion::Legacy, ProtocolVersion::V2] {
+            let recrypt = Recrypt::new().with_protocol_version(version);
+            let signing_keypair = recrypt.generate_ed25519_key_pair();
+            let (private_key, public_key) = recrypt.generate_key_pair().unwrap();
+            let (_, to_public_key) = recrypt.generate_key_pair().unwrap();
+            let plaintext = recrypt.gen_plaintext();
+            let value = recrypt.encrypt(&plaintext, &public_key, &signing_keypair).unwrap();
+            let transform_key = recrypt
+                .generate_transform_key(&private_key, &to_public_key, &signing_keypair)
+                .unwrap();
+            let transformed = recrypt
+                .transform(value.clone(), transform_key.clone(), &signing_keypair)
+                .unwrap();
+
+            for value in [value, transformed] {
+                let mut header = Vec::new();
+                write_header(&mut header, &value).unwrap();
+                header.extend_from_slice(b"body");
+                let mut reader = &header[..];
+                assert_eq!(read_header(&mut reader).unwrap(), value);
+                // 读取后 reader 停在正文开头
+                assert_eq!(reader, b"body");
+            }
+
+            let decoded_key = TransformKey::from_wire_bytes(&transform_key.to_wire_bytes()).unwrap();
+            assert_eq!(decoded_key, transform_key);
+            assert_eq!(decoded_key.protocol_version(), version);
+        }
+    }
+
+    #[test]
+    fn read_header_rejects_bad_magic_version_and_value() {
+        let recrypt = Recrypt::new();
+        let (_, public_key) = recrypt.generate_key_pair().unwrap();
+        let value = recrypt
+            .encrypt(
+                &recrypt.gen_plaintext(),
+                &public_key,
+                &recrypt.generate_ed25519_key_pair(),
+            )
+            .unwrap();
+        let mut header = Vec::new();
+        write_header(&mut header, &value).unwrap();
+
+        let mut bad_magic = header.clone();
+        bad_magic[0] ^= 1;
+        assert!(read_header(&mut &bad_magic[..]).is_err());
+
+        let mut bad_version = header.clone();
+        bad_version[4] = VERSION + 1;
+        assert!(read_header(&mut &bad_version[..]).is_err());
+
+        let mut bad_value = header.clone();
+        bad_value[9] ^= 0xff;
+        assert_eq!(
+            read_header(&mut &bad_value[..]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        let truncated = &header[..header.len() - 1];
+        assert!(read_header(&mut &truncated[..]).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+//! 加密文件容器格式
+//!
+//! 文件以头部开始（版本 2 或 3）：
+//!
+//! | 偏移 | 长度 | 字段 |
+//! |------|------|------|
+//! | 0    | 4    | 魔数 `FAES` |
+//! | 4    | 1    | 格式版本 |
+//! | 5    | 1    | 算法 id |
+//! | 6    | 4    | 明文分块大小（大端） |
+//! | 10   | 7    | 每个文件随机生成的 nonce 前缀 |
+//! | 17   | 1    | 密钥模式：0 为原始密钥，1 为 Argon2id 口令，2 为 X25519 接收者 |
+//!
+//! 密钥模式之后的内容随模式而定：
+//!
+//! - Argon2id：盐(16) 与 m/t/p 代价（各 u32 大端），共 28 字节
+//! - X25519：接收者数量(1)，随后每个接收者为临时公钥(32) 与包装后的文件密钥（密钥长度 + 16）
+//!
+//! 版本 3 在此之后再追加 1 字节摘要算法 id（1 为 SHA-256，2 为 BLAKE3），
+//! 表示最后一个密文块之后附有明文摘要尾部，见 [`crate::integrity`]。不带摘要的文件仍写为版本 2。
+//!
+//! 头部之后是按 STREAM 构造加密的密文块，详见 [`crate::stream`]。
+
+use crate::hybrid::Stanza;
+use crate::integrity::DigestAlgorithm;
+use crate::kdf::KdfParams;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+pub const MAGIC: [u8; 4] = *b"FAES";
+pub const VERSION: u8 = 2;
+/// 带明文摘要尾部的格式版本
+pub const VERSION_DIGEST: u8 = 3;
+pub const NONCE_PREFIX_LEN: usize = 7;
+pub const TAG_LEN: usize = 16;
+/// 头部中与密钥模式无关的定长部分
+const FIXED_HEADER_LEN: usize = 4 + 1 + 1 + 4 + NONCE_PREFIX_LEN + 1;
+
+const MODE_RAW: u8 = 0;
+const MODE_PASSPHRASE: u8 = 1;
+const MODE_RECIPIENTS: u8 = 2;
+
+/// 允许的最大分块，防止恶意头部导致超大内存分配
+pub const MAX_CHUNK_SIZE: u32 = 64 * 1024 * 1024;
+
+/// 头部中记录的 AEAD 算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Aes128Gcm,
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl Algorithm {
+    pub fn id(self) -> u8 {
+        match self {
+            Algorithm::Aes128Gcm => 1,
+            Algorithm::Aes256Gcm => 2,
+            Algorithm::ChaCha20Poly1305 => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> io::Result<Self> {
+        match id {
+            1 => Ok(Algorithm::Aes128Gcm),
+            2 => Ok(Algorithm::Aes256Gcm),
+            3 => Ok(Algorithm::ChaCha20Poly1305),
+            _ => Err(invalid_data(format!("未知的算法 id: {}", id))),
+        }
+    }
+
+    /// 算法要求的密钥长度（字节）
+    pub fn key_len(self) -> usize {
+        match self {
+            Algorithm::Aes128Gcm => 16,
+            Algorithm::Aes256Gcm | Algorithm::ChaCha20Poly1305 => 32,
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Algorithm::Aes128Gcm => "aes128-gcm",
+            Algorithm::Aes256Gcm => "aes256-gcm",
+            Algorithm::ChaCha20Poly1305 => "chacha20-poly1305",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "aes128-gcm" => Ok(Algorithm::Aes128Gcm),
+            "aes256-gcm" => Ok(Algorithm::Aes256Gcm),
+            "chacha20-poly1305" => Ok(Algorithm::ChaCha20Poly1305),
+            _ => Err(format!("不支持的算法: {}", s)),
+        }
+    }
+}
+
+/// 正文密钥的获取方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyMode {
+    /// 调用方直接提供原始密钥
+    Raw,
+    /// 由口令经 Argon2id 派生
+    Passphrase(KdfParams),
+    /// 随机文件密钥，分别包装给每个 X25519 接收者
+    Recipients(Vec<Stanza>),
+}
+
+/// 加密文件头部，编码后的字节同时作为每个分块的 AAD
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub algorithm: Algorithm,
+    pub chunk_size: u32,
+    pub nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    pub key_mode: KeyMode,
+    /// 明文摘要算法，`Some` 时密文末尾附有摘要尾部
+    pub digest: Option<DigestAlgorithm>,
+}
+
+impl Header {
+    /// 使用随机 nonce 前缀创建新头部
+    pub fn new(algorithm: Algorithm, chunk_size: u32, key_mode: KeyMode) -> io::Result<Self> {
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(invalid_input(format!("分块大小无效: {}", chunk_size)));
+        }
+        if let KeyMode::Recipients(stanzas) = &key_mode
+            && (stanzas.is_empty() || stanzas.len() > u8::MAX as usize)
+        {
+            return Err(invalid_input(format!("接收者数量应为 1 到 255，实际为 {}", stanzas.len())));
+        }
+        Ok(Header {
+            algorithm,
+            chunk_size,
+            nonce_prefix: rand::random(),
+            key_mode,
+            digest: None,
+        })
+    }
+
+    /// 启用明文摘要尾部
+    pub fn with_digest(mut self, digest: Option<DigestAlgorithm>) -> Self {
+        self.digest = digest;
+        self
+    }
+
+    /// 摘要尾部的长度（含认证标签），未启用时为 0
+    pub fn trailer_len(&self) -> usize {
+        self.digest.map_or(0, |d| d.output_len() + TAG_LEN)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(FIXED_HEADER_LEN);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(if self.digest.is_some() { VERSION_DIGEST } else { VERSION });
+        bytes.push(self.algorithm.id());
+        bytes.extend_from_slice(&self.chunk_size.to_be_bytes());
+        bytes.extend_from_slice(&self.nonce_prefix);
+        // 写入 Vec 不会失败
+        match &self.key_mode {
+            KeyMode::Raw => bytes.push(MODE_RAW),
+            KeyMode::Passphrase(params) => {
+                bytes.push(MODE_PASSPHRASE);
+                params.write_to(&mut bytes).expect("写入内存缓冲区失败");
+            }
+            KeyMode::Recipients(stanzas) => {
+                bytes.push(MODE_RECIPIENTS);
+                bytes.push(stanzas.len() as u8);
+                for stanza in stanzas {
+                    stanza.write_to(&mut bytes).expect("写入内存缓冲区失败");
+                }
+            }
+        }
+        if let Some(digest) = self.digest {
+            bytes.push(digest.id());
+        }
+        bytes
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = [0u8; FIXED_HEADER_LEN];
+        reader.read_exact(&mut bytes)?;
+        if bytes[..4] != MAGIC {
+            return Err(invalid_data("不是有效的加密文件（魔数不匹配）"));
+        }
+        let version = bytes[4];
+        if version != VERSION && version != VERSION_DIGEST {
+            return Err(invalid_data(format!("不支持的格式版本: {}", bytes[4])));
+        }
+        let algorithm = Algorithm::from_id(bytes[5])?;
+        let chunk_size = u32::from_be_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]);
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(invalid_data(format!("分块大小无效: {}", chunk_size)));
+        }
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        nonce_prefix.copy_from_slice(&bytes[10..10 + NONCE_PREFIX_LEN]);
+        let key_mode = match bytes[FIXED_HEADER_LEN - 1] {
+            MODE_RAW => KeyMode::Raw,
+            MODE_PASSPHRASE => KeyMode::Passphrase(KdfParams::read_from(reader)?),
+            MODE_RECIPIENTS => {
+                let mut count = [0u8; 1];
+                reader.read_exact(&mut count)?;
+                if count[0] == 0 {
+                    return Err(invalid_data("接收者列表为空"));
+                }
+                let stanzas = (0..count[0])
+                    .map(|_| Stanza::read_from(reader, algorithm.key_len()))
+                    .collect::<io::Result<Vec<_>>>()?;
+                KeyMode::Recipients(stanzas)
+            }
+            other => return Err(invalid_data(format!("未知的密钥模式: {}", other))),
+        };
+        let digest = if version == VERSION_DIGEST {
+            let mut id = [0u8; 1];
+            reader.read_exact(&mut id)?;
+            let digest = DigestAlgorithm::from_id(id[0])
+                .ok_or_else(|| invalid_data(format!("未知的摘要算法: {}", id[0])))?;
+            Some(digest)
+        } else {
+            None
+        };
+        Ok(Header {
+            algorithm,
+            chunk_size,
+            nonce_prefix,
+            key_mode,
+            digest,
+        })
+    }
+}
+
+pub fn invalid_data<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+pub fn invalid_input<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidInput, err)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn header_roundtrip() {
+        for algorithm in [Algorithm::Aes128Gcm, Algorithm::Aes256Gcm, Algorithm::ChaCha20Poly1305] {
+            let header = Header::new(algorithm, 4096, KeyMode::Raw).unwrap();
+            let bytes = header.to_bytes();
+            assert_eq!(bytes.len(), FIXED_HEADER_LEN);
+            let mut reader = &bytes[..];
+            assert_eq!(Header::read_from(&mut reader).unwrap(), header);
+            assert!(reader.is_empty());
+        }
+    }
+
+    #[test]
+    fn header_layout() {
+        let header = Header::new(Algorithm::ChaCha20Poly1305, 0x0102_0304, KeyMode::Raw).unwrap();
+        let bytes = header.to_bytes();
+        assert_eq!(bytes[..4], MAGIC);
+        assert_eq!(bytes[4], VERSION);
+        assert_eq!(bytes[5], Algorithm::ChaCha20Poly1305.id());
+        assert_eq!(bytes[6..10], [1, 2, 3, 4]);
+        assert_eq!(bytes[10..17], header.nonce_prefix);
+        assert_eq!(bytes[17], MODE_RAW);
+    }
+
+    #[test]
+    fn read_from_rejects_bad_headers() {
+        let bytes = Header::new(Algorithm::Aes256Gcm, 4096, KeyMode::Raw)
+            .unwrap()
+            .to_bytes();
+        let with = |index: usize, value: u8| {
+            let mut bytes = bytes.clone();
+            bytes[index] = value;
+            bytes
+        };
+        let chunk_size = |size: u32| {
+            let mut bytes = bytes.clone();
+            bytes[6..10].copy_from_slice(&size.to_be_bytes());
+            bytes
+        };
+        for bad in [
+            with(0, b'X'),
+            with(4, 1),
+            with(5, 9),
+            with(17, 9),
+            chunk_size(0),
+            chunk_size(MAX_CHUNK_SIZE + 1),
+        ] {
+            let err = Header::read_from(&mut &bad[..]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(Header::read_from(&mut &truncated[..]).is_err());
+    }
+
+    #[test]
+    fn new_rejects_bad_chunk_sizes() {
+        assert!(Header::new(Algorithm::Aes256Gcm, 0, KeyMode::Raw).is_err());
+        assert!(Header::new(Algorithm::Aes256Gcm, MAX_CHUNK_SIZE + 1, KeyMode::Raw).is_err());
+    }
+}
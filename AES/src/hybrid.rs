@@ -0,0 +1,249 @@
+//! X25519 + HKDF + AEAD 混合公钥加密
+//!
+//! 每个文件使用随机文件密钥加密正文。对每个接收者生成一个临时 X25519 密钥对，
+//! 以 `HKDF-SHA256(盐 = 临时公钥 || 接收者公钥, 输入 = 共享秘密)` 派生包装密钥，
+//! 再用 AES-256-GCM 包装文件密钥。包装结果（“接收者条目”）写入文件头部，
+//! 接收者用自己的私钥依次尝试解开即可，无需预先共享密钥。
+
+use crate::format::{invalid_data, invalid_input};
+use aes_gcm::Aes256Gcm;
+use aes_gcm::aead::{Aead, KeyInit};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+pub const PUBLIC_KEY_PREFIX: &str = "faes-x25519-public:";
+pub const SECRET_KEY_PREFIX: &str = "FAES-X25519-SECRET:";
+
+const WRAP_INFO: &[u8] = b"faes x25519 file key wrap v1";
+const WRAP_NONCE: [u8; 12] = [0u8; 12]; // 每个包装密钥只使用一次
+
+/// 接收者公钥
+#[derive(Clone)]
+pub struct Recipient(PublicKey);
+
+impl Recipient {
+    /// 解析 `faes-x25519-public:<base64>` 形式的公钥，或读取包含该字符串的公钥文件
+    pub fn parse(value: &str) -> io::Result<Self> {
+        let text = if value.starts_with(PUBLIC_KEY_PREFIX) {
+            value.to_string()
+        } else {
+            fs::read_to_string(value)?
+        };
+        let encoded = text
+            .trim()
+            .strip_prefix(PUBLIC_KEY_PREFIX)
+            .ok_or_else(|| invalid_input(format!("不是有效的接收者公钥: {}", value)))?;
+        let bytes: [u8; 32] = decode_key(encoded)?;
+        Ok(Recipient(PublicKey::from(bytes)))
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{}{}", PUBLIC_KEY_PREFIX, BASE64.encode(self.0.as_bytes()))
+    }
+}
+
+/// 接收者私钥
+pub struct Identity(StaticSecret);
+
+impl Identity {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = Zeroizing::new(fs::read_to_string(path)?);
+        let encoded = text
+            .trim()
+            .strip_prefix(SECRET_KEY_PREFIX)
+            .ok_or_else(|| invalid_data(format!("{} 不是有效的私钥文件", path.display())))?;
+        let bytes = Zeroizing::new(decode_key(encoded)?);
+        Ok(Identity(StaticSecret::from(*bytes)))
+    }
+
+    pub fn recipient(&self) -> Recipient {
+        Recipient(PublicKey::from(&self.0))
+    }
+}
+
+fn decode_key(encoded: &str) -> io::Result<[u8; 32]> {
+    let bytes = Zeroizing::new(BASE64.decode(encoded).map_err(invalid_data)?);
+    bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| invalid_data("X25519 密钥长度应为 32 字节"))
+}
+
+/// 生成密钥对：私钥写入新文件（Unix 上权限为 0600），返回编码后的公钥
+pub fn generate_identity(path: &Path) -> io::Result<Recipient> {
+    let identity = Identity(StaticSecret::random());
+    let encoded = Zeroizing::new(format!("{}{}", SECRET_KEY_PREFIX, BASE64.encode(identity.0.as_bytes())));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    writeln!(file, "{}", &*encoded)?;
+    file.sync_all()?;
+    Ok(identity.recipient())
+}
+
+/// 头部中的一个接收者条目：临时公钥与被包装的文件密钥
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stanza {
+    pub ephemeral: [u8; 32],
+    pub wrapped_key: Vec<u8>,
+}
+
+impl Stanza {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.ephemeral)?;
+        writer.write_all(&self.wrapped_key)
+    }
+
+    /// 包装后的长度由文件密钥长度决定，因此头部无需记录
+    pub fn read_from<R: Read>(reader: &mut R, key_len: usize) -> io::Result<Self> {
+        let mut ephemeral = [0u8; 32];
+        reader.read_exact(&mut ephemeral)?;
+        let mut wrapped_key = vec![0u8; key_len + crate::format::TAG_LEN];
+        reader.read_exact(&mut wrapped_key)?;
+        Ok(Stanza { ephemeral, wrapped_key })
+    }
+}
+
+fn wrap_cipher(shared: &[u8; 32], ephemeral: &PublicKey, recipient: &PublicKey) -> io::Result<Aes256Gcm> {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral.as_bytes());
+    salt[32..].copy_from_slice(recipient.as_bytes());
+    let hk = Hkdf::<Sha256>::new(Some(&salt), shared);
+    let mut wrap_key = Zeroizing::new([0u8; 32]);
+    hk.expand(WRAP_INFO, &mut *wrap_key)
+        .map_err(|_| invalid_data("无法派生包装密钥"))?;
+    Aes256Gcm::new_from_slice(&*wrap_key).map_err(invalid_data)
+}
+
+/// 为每个接收者包装文件密钥
+pub fn wrap_file_key(file_key: &[u8], recipients: &[Recipient]) -> io::Result<Vec<Stanza>> {
+    recipients
+        .iter()
+        .map(|recipient| {
+            let secret = EphemeralSecret::random();
+            let ephemeral = PublicKey::from(&secret);
+            let shared = secret.diffie_hellman(&recipient.0);
+            if !shared.was_contributory() {
+                return Err(invalid_input("接收者公钥无效（低阶点）"));
+            }
+            let cipher = wrap_cipher(shared.as_bytes(), &ephemeral, &recipient.0)?;
+            let wrapped_key = cipher
+                .encrypt(&WRAP_NONCE.into(), file_key)
+                .map_err(|_| invalid_data("包装文件密钥失败"))?;
+            Ok(Stanza {
+                ephemeral: ephemeral.to_bytes(),
+                wrapped_key,
+            })
+        })
+        .collect()
+}
+
+/// 用私钥依次尝试解开接收者条目，返回文件密钥
+pub fn unwrap_file_key(stanzas: &[Stanza], identity: &Identity) -> io::Result<Zeroizing<Vec<u8>>> {
+    let own = identity.recipient();
+    for stanza in stanzas {
+        let ephemeral = PublicKey::from(stanza.ephemeral);
+        let shared = identity.0.diffie_hellman(&ephemeral);
+        if !shared.was_contributory() {
+            continue;
+        }
+        let cipher = wrap_cipher(shared.as_bytes(), &ephemeral, &own.0)?;
+        if let Ok(key) = cipher.decrypt(&WRAP_NONCE.into(), stanza.wrapped_key.as_slice()) {
+            return Ok(Zeroizing::new(key));
+        }
+    }
+    Err(invalid_data("该文件没有发给此私钥的接收者条目"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::format::{Algorithm, Header, KeyMode, TAG_LEN};
+
+    fn identity() -> Identity {
+        Identity(StaticSecret::random())
+    }
+
+    #[test]
+    fn wrap_unwrap_roundtrip() {
+        let alice = identity();
+        let stanzas = wrap_file_key(&[7u8; 32], &[alice.recipient()]).unwrap();
+        assert_eq!(stanzas.len(), 1);
+        assert_eq!(stanzas[0].wrapped_key.len(), 32 + TAG_LEN);
+        assert_eq!(*unwrap_file_key(&stanzas, &alice).unwrap(), [7u8; 32]);
+    }
+
+    #[test]
+    fn every_recipient_unwraps_the_same_key() {
+        let identities: Vec<Identity> = (0..3).map(|_| identity()).collect();
+        let recipients: Vec<Recipient> = identities.iter().map(Identity::recipient).collect();
+        let stanzas = wrap_file_key(&[9u8; 16], &recipients).unwrap();
+        assert_eq!(stanzas.len(), 3);
+        // 每个接收者都有自己的临时密钥
+        assert_ne!(stanzas[0].ephemeral, stanzas[1].ephemeral);
+
+        // 接收者条目经过头部往返后仍可解开
+        let header = Header::new(Algorithm::Aes128Gcm, 4096, KeyMode::Recipients(stanzas)).unwrap();
+        let parsed = Header::read_from(&mut &header.to_bytes()[..]).unwrap();
+        assert_eq!(parsed, header);
+        let KeyMode::Recipients(stanzas) = parsed.key_mode else {
+            panic!("头部中应为接收者模式");
+        };
+        for identity in &identities {
+            assert_eq!(*unwrap_file_key(&stanzas, identity).unwrap(), [9u8; 16]);
+        }
+    }
+
+    #[test]
+    fn wrong_recipient_is_rejected() {
+        let stanzas = wrap_file_key(&[7u8; 32], &[identity().recipient(), identity().recipient()]).unwrap();
+        let err = unwrap_file_key(&stanzas, &identity()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn tampered_stanza_is_rejected() {
+        let alice = identity();
+        let stanzas = wrap_file_key(&[7u8; 32], &[alice.recipient()]).unwrap();
+        let mut wrapped = stanzas.clone();
+        wrapped[0].wrapped_key[0] ^= 1;
+        assert!(unwrap_file_key(&wrapped, &alice).is_err());
+        let mut ephemeral = stanzas;
+        ephemeral[0].ephemeral[0] ^= 1;
+        assert!(unwrap_file_key(&ephemeral, &alice).is_err());
+    }
+
+    #[test]
+    fn low_order_recipient_is_rejected() {
+        let err = wrap_file_key(&[7u8; 32], &[Recipient(PublicKey::from([0u8; 32]))]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn keys_roundtrip_through_their_text_forms() {
+        let path = std::env::temp_dir().join(format!("aes-hybrid-{}-identity", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let recipient = generate_identity(&path).unwrap();
+        let loaded = Identity::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().recipient().0, recipient.0);
+
+        let encoded = recipient.encode();
+        assert!(encoded.starts_with(PUBLIC_KEY_PREFIX));
+        assert_eq!(Recipient::parse(&encoded).unwrap().0, recipient.0);
+        assert!(Recipient::parse(&format!("{}AAAA", PUBLIC_KEY_PREFIX)).is_err());
+    }
+}
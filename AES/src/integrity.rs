@@ -0,0 +1,292 @@
+//! 明文摘要尾部
+//!
+//! 加密时可以边加密边计算明文的 SHA-256 或 BLAKE3 摘要，用同一密钥以独立的 nonce
+//! 密封后追加在最后一个密文块之后。解密时同步计算并在末尾比对，
+//! 因此 `verify` 无需把明文写到磁盘，也能确认解密结果与加密时的原文一致。
+
+use crate::format::invalid_input;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::{self, Read};
+use std::str::FromStr;
+
+/// 摘要算法，记录在头部中
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+impl DigestAlgorithm {
+    pub fn id(self) -> u8 {
+        match self {
+            DigestAlgorithm::Sha256 => 1,
+            DigestAlgorithm::Blake3 => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(DigestAlgorithm::Sha256),
+            2 => Some(DigestAlgorithm::Blake3),
+            _ => None,
+        }
+    }
+
+    /// 摘要长度（字节）
+    pub fn output_len(self) -> usize {
+        32
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DigestAlgorithm::Sha256 => "sha256",
+            DigestAlgorithm::Blake3 => "blake3",
+        })
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        match s {
+            "sha256" => Ok(DigestAlgorithm::Sha256),
+            "blake3" => Ok(DigestAlgorithm::Blake3),
+            other => Err(invalid_input(format!("不支持的摘要算法: {}（可选 sha256、blake3）", other))),
+        }
+    }
+}
+
+/// 流式摘要计算
+pub enum Hasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub fn new(algorithm: DigestAlgorithm) -> Self {
+        match algorithm {
+            DigestAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            DigestAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+        }
+    }
+}
+
+/// 扣留输入末尾 `trailer_len` 字节的 `Read` 适配器
+///
+/// 上层按原样读取密文块，读到 EOF 后再通过 [`TrailerReader::trailer`] 取出尾部。
+pub struct TrailerReader<R> {
+    inner: R,
+    trailer_len: usize,
+    held: Vec<u8>,
+    eof: bool,
+}
+
+impl<R: Read> TrailerReader<R> {
+    pub fn new(inner: R, trailer_len: usize) -> Self {
+        TrailerReader {
+            inner,
+            trailer_len,
+            held: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// 输入读完后返回被扣留的尾部，输入不足尾部长度时报错
+    pub fn trailer(&mut self) -> io::Result<&[u8]> {
+        let mut rest = Vec::new();
+        self.read_to_end(&mut rest)?;
+        if self.held.len() != self.trailer_len {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "密文被截断：缺少摘要尾部"));
+        }
+        Ok(&self.held)
+    }
+}
+
+impl<R: Read> Read for TrailerReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // 先攒够“尾部 + 本次请求”的字节，或直到 EOF
+        while !self.eof && self.held.len() < self.trailer_len + buf.len() {
+            let start = self.held.len();
+            self.held.resize(self.trailer_len + buf.len(), 0);
+            match self.inner.read(&mut self.held[start..]) {
+                Ok(0) => {
+                    self.held.truncate(start);
+                    self.eof = true;
+                }
+                Ok(n) => self.held.truncate(start + n),
+                Err(e) => {
+                    self.held.truncate(start);
+                    return Err(e);
+                }
+            }
+        }
+        let n = self.held.len().saturating_sub(self.trailer_len).min(buf.len());
+        buf[..n].copy_from_slice(&self.held[..n]);
+        self.held.drain(..n);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::format::{Algorithm, Header, TAG_LEN};
+    use crate::stream::decrypt_stream;
+    use crate::stream::test::{cipher, encrypt, raw_header};
+
+    /// 每次最多返回 `step` 字节的读取器，模拟短读
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.step.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn trailer_reader_holds_back_the_trailer() {
+        let data: Vec<u8> = (0..100).collect();
+        for step in [1, 7, 100] {
+            for buf_len in [1, 5, 64, 200] {
+                let mut reader = TrailerReader::new(Trickle { data: &data, step }, 10);
+                let mut body = Vec::new();
+                let mut buf = vec![0u8; buf_len];
+                loop {
+                    let n = reader.read(&mut buf).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    body.extend_from_slice(&buf[..n]);
+                }
+                assert_eq!(body, data[..90]);
+                assert_eq!(reader.trailer().unwrap(), &data[90..]);
+            }
+        }
+    }
+
+    #[test]
+    fn trailer_reader_without_trailer_passes_everything_through() {
+        let data: Vec<u8> = (0..20).collect();
+        let mut reader = TrailerReader::new(&data[..], 0);
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body).unwrap();
+        assert_eq!(body, data);
+        assert!(reader.trailer().unwrap().is_empty());
+    }
+
+    #[test]
+    fn trailer_reader_reports_a_short_input() {
+        let mut reader = TrailerReader::new(&[1u8, 2, 3][..], 10);
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body).unwrap();
+        assert!(body.is_empty());
+        assert_eq!(reader.trailer().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn hasher_matches_the_digests() {
+        let data = b"the quick brown fox";
+        for (algorithm, expected) in [
+            (DigestAlgorithm::Sha256, Sha256::digest(data).to_vec()),
+            (DigestAlgorithm::Blake3, blake3::hash(data).as_bytes().to_vec()),
+        ] {
+            let mut hasher = Hasher::new(algorithm);
+            hasher.update(&data[..4]);
+            hasher.update(&data[4..]);
+            let digest = hasher.finalize();
+            assert_eq!(digest.len(), algorithm.output_len());
+            assert_eq!(digest, expected);
+            assert_eq!(DigestAlgorithm::from_id(algorithm.id()), Some(algorithm));
+            assert_eq!(algorithm.to_string().parse::<DigestAlgorithm>().unwrap(), algorithm);
+        }
+        assert_eq!(DigestAlgorithm::from_id(0), None);
+        assert!("md5".parse::<DigestAlgorithm>().is_err());
+    }
+
+    /// 解密并返回明文与记录的摘要
+    fn decrypt(bytes: &[u8]) -> io::Result<(Vec<u8>, Option<Vec<u8>>)> {
+        let mut reader = bytes;
+        let header = Header::read_from(&mut reader)?;
+        let mut out = Vec::new();
+        let summary = decrypt_stream(&mut reader, &mut out, &cipher(header), 1, |_| {})?;
+        Ok((out, summary.digest))
+    }
+
+    #[test]
+    fn digest_trailer_roundtrip() {
+        let plaintext: Vec<u8> = (0..50).collect();
+        for digest in [DigestAlgorithm::Sha256, DigestAlgorithm::Blake3] {
+            let header = raw_header(Algorithm::Aes256Gcm).with_digest(Some(digest));
+            assert_eq!(header.trailer_len(), digest.output_len() + TAG_LEN);
+            let bytes = encrypt(header, &plaintext, 1);
+            let (out, recorded) = decrypt(&bytes).unwrap();
+            assert_eq!(out, plaintext);
+            let mut hasher = Hasher::new(digest);
+            hasher.update(&plaintext);
+            assert_eq!(recorded, Some(hasher.finalize()));
+        }
+    }
+
+    #[test]
+    fn corrupted_trailer_is_rejected() {
+        let header = raw_header(Algorithm::Aes256Gcm).with_digest(Some(DigestAlgorithm::Sha256));
+        let bytes = encrypt(header, &[3; 40], 1);
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert_eq!(decrypt(&corrupted).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn missing_trailer_is_rejected() {
+        let header = raw_header(Algorithm::Aes256Gcm).with_digest(Some(DigestAlgorithm::Blake3));
+        let trailer_len = header.trailer_len();
+        let bytes = encrypt(header, &[3; 40], 1);
+        assert!(decrypt(&bytes[..bytes.len() - trailer_len]).is_err());
+        assert!(decrypt(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn file_without_digest_has_no_trailer() {
+        let header = raw_header(Algorithm::Aes256Gcm);
+        assert_eq!(header.trailer_len(), 0);
+        let bytes = encrypt(header.clone(), &[3; 40], 1);
+        let (out, recorded) = decrypt(&bytes).unwrap();
+        assert_eq!(out, [3; 40]);
+        assert_eq!(recorded, None);
+
+        // 附加在不带摘要的文件末尾的尾部会被当作密文块读取，从而认证失败
+        let with_digest = encrypt(header.with_digest(Some(DigestAlgorithm::Sha256)), &[3; 40], 1);
+        let mut appended = bytes;
+        appended.extend_from_slice(&with_digest[with_digest.len() - (32 + TAG_LEN)..]);
+        assert!(decrypt(&appended).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! 基于 Argon2id 的口令密钥派生
+//!
+//! 盐和代价参数写入文件头部（并随头部一起作为 AAD 被认证），
+//! 解密时只需口令即可重新派生出同一密钥。
+
+use crate::format::{invalid_data, invalid_input};
+use argon2::{Argon2, Params, Version};
+use std::io::{self, Read, Write};
+use zeroize::Zeroizing;
+
+pub const SALT_LEN: usize = 16;
+/// 编码后的长度：盐 + 三个 u32 代价参数
+pub const ENCODED_LEN: usize = SALT_LEN + 4 * 3;
+
+pub const DEFAULT_M_COST: u32 = 64 * 1024; // 64 MiB
+pub const DEFAULT_T_COST: u32 = 3;
+pub const DEFAULT_P_COST: u32 = 1;
+
+/// 解密时接受的上限，防止恶意头部让派生过程耗尽内存或时间
+const MAX_M_COST: u32 = 4 * 1024 * 1024; // 4 GiB
+const MAX_T_COST: u32 = 64;
+const MAX_P_COST: u32 = 64;
+
+/// Argon2id 参数，`m_cost` 以 KiB 为单位
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KdfParams {
+    pub salt: [u8; SALT_LEN],
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl KdfParams {
+    /// 使用随机盐创建参数
+    pub fn new(m_cost: u32, t_cost: u32, p_cost: u32) -> io::Result<Self> {
+        let params = KdfParams {
+            salt: rand::random(),
+            m_cost,
+            t_cost,
+            p_cost,
+        };
+        params.check().map_err(invalid_input)?;
+        Ok(params)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.m_cost > MAX_M_COST || self.t_cost > MAX_T_COST || self.p_cost > MAX_P_COST {
+            return Err(format!(
+                "Argon2id 参数超出允许范围: m={} KiB, t={}, p={}",
+                self.m_cost, self.t_cost, self.p_cost
+            ));
+        }
+        self.argon2_params(32).map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    fn argon2_params(&self, output_len: usize) -> Result<Params, argon2::Error> {
+        Params::new(self.m_cost, self.t_cost, self.p_cost, Some(output_len))
+    }
+
+    /// 从口令派生 `key_len` 字节的密钥，返回值在释放时清零
+    pub fn derive_key(&self, passphrase: &[u8], key_len: usize) -> io::Result<Zeroizing<Vec<u8>>> {
+        let params = self.argon2_params(key_len).map_err(|e| invalid_input(e.to_string()))?;
+        let argon2 = Argon2::new(argon2::Algorithm::Argon2id, Version::V0x13, params);
+        let mut key = Zeroizing::new(vec![0u8; key_len]);
+        argon2
+            .hash_password_into(passphrase, &self.salt, &mut key)
+            .map_err(|e| invalid_input(e.to_string()))?;
+        Ok(key)
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.salt)?;
+        writer.write_all(&self.m_cost.to_be_bytes())?;
+        writer.write_all(&self.t_cost.to_be_bytes())?;
+        writer.write_all(&self.p_cost.to_be_bytes())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = [0u8; ENCODED_LEN];
+        reader.read_exact(&mut bytes)?;
+        let mut salt = [0u8; SALT_LEN];
+        salt.copy_from_slice(&bytes[..SALT_LEN]);
+        let cost = |i: usize| {
+            let at = SALT_LEN + 4 * i;
+            u32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+        };
+        let params = KdfParams {
+            salt,
+            m_cost: cost(0),
+            t_cost: cost(1),
+            p_cost: cost(2),
+        };
+        params.check().map_err(invalid_data)?;
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::format::{Algorithm, Header, KeyMode};
+    use crate::stream::{ChunkCipher, decrypt_stream, encrypt_stream};
+
+    /// 测试用的低代价参数
+    fn cheap_params() -> KdfParams {
+        KdfParams::new(64, 1, 1).unwrap()
+    }
+
+    #[test]
+    fn params_roundtrip_through_the_header() {
+        let params = cheap_params();
+        let mut encoded = Vec::new();
+        params.write_to(&mut encoded).unwrap();
+        assert_eq!(encoded.len(), ENCODED_LEN);
+        assert_eq!(encoded[..SALT_LEN], params.salt);
+        assert_eq!(encoded[SALT_LEN..], [0, 0, 0, 64, 0, 0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(KdfParams::read_from(&mut &encoded[..]).unwrap(), params);
+
+        let header = Header::new(Algorithm::Aes256Gcm, 4096, KeyMode::Passphrase(params.clone())).unwrap();
+        let parsed = Header::read_from(&mut &header.to_bytes()[..]).unwrap();
+        assert_eq!(parsed.key_mode, KeyMode::Passphrase(params));
+    }
+
+    #[test]
+    fn read_from_rejects_excessive_or_invalid_costs() {
+        for (m_cost, t_cost, p_cost) in [
+            (MAX_M_COST + 1, 1, 1),
+            (64, MAX_T_COST + 1, 1),
+            (64, 1, MAX_P_COST + 1),
+            (64, 0, 1),
+        ] {
+            let params = KdfParams {
+                salt: [0; SALT_LEN],
+                m_cost,
+                t_cost,
+                p_cost,
+            };
+            let mut encoded = Vec::new();
+            params.write_to(&mut encoded).unwrap();
+            let err = KdfParams::read_from(&mut &encoded[..]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(KdfParams::new(m_cost, t_cost, p_cost).is_err());
+        }
+        assert!(KdfParams::read_from(&mut &[0u8; ENCODED_LEN - 1][..]).is_err());
+    }
+
+    #[test]
+    fn derive_key_depends_on_passphrase_and_salt() {
+        let params = cheap_params();
+        let key = params.derive_key(b"correct horse", 32).unwrap();
+        assert_eq!(key.len(), 32);
+        assert_eq!(params.derive_key(b"correct horse", 32).unwrap(), key);
+        assert_ne!(params.derive_key(b"wrong horse", 32).unwrap(), key);
+        assert_ne!(cheap_params().derive_key(b"correct horse", 32).unwrap(), key);
+        assert_eq!(params.derive_key(b"correct horse", 16).unwrap().len(), 16);
+    }
+
+    #[test]
+    fn wrong_passphrase_fails_to_decrypt() {
+        let params = cheap_params();
+        let header = Header::new(Algorithm::ChaCha20Poly1305, 16, KeyMode::Passphrase(params.clone())).unwrap();
+        let key = params.derive_key(b"correct horse", 32).unwrap();
+        let mut encrypted = Vec::new();
+        let cipher = ChunkCipher::new(header, &key).unwrap();
+        encrypt_stream(
+            &mut &b"attack at dawn, bring snacks"[..],
+            &mut encrypted,
+            &cipher,
+            1,
+            |_| {},
+        )
+        .unwrap();
+
+        let decrypt = |passphrase: &[u8]| {
+            let mut reader = &encrypted[..];
+            let header = Header::read_from(&mut reader)?;
+            let KeyMode::Passphrase(params) = &header.key_mode else {
+                panic!("头部中应为口令模式");
+            };
+            let key = params.derive_key(passphrase, header.algorithm.key_len())?;
+            let mut out = Vec::new();
+            decrypt_stream(&mut reader, &mut out, &ChunkCipher::new(header, &key)?, 1, |_| {})?;
+            Ok::<_, io::Error>(out)
+        };
+        assert_eq!(decrypt(b"correct horse").unwrap(), b"attack at dawn, bring snacks");
+        assert_eq!(decrypt(b"wrong horse").unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
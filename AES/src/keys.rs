@@ -0,0 +1,176 @@
+//! 密钥来源与密钥文件管理
+//!
+//! 所有密钥材料都放在 [`Zeroizing`] 中，离开作用域时从内存中清除。
+
+use crate::format::{Header, KeyMode, invalid_data, invalid_input};
+use crate::hybrid::{self, Identity};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+/// 解密时的密钥来源
+pub enum KeySource {
+    /// 原始密钥文件，内容为二进制密钥或其十六进制文本
+    File(PathBuf),
+    /// 口令，经头部中记录的 Argon2id 参数派生出密钥
+    Passphrase(Zeroizing<String>),
+    /// X25519 私钥，用于解开头部中的接收者条目
+    Identity(Identity),
+}
+
+impl KeySource {
+    /// 为给定头部解析出密钥，长度由头部中的算法决定
+    pub fn key_for(&self, header: &Header) -> io::Result<Zeroizing<Vec<u8>>> {
+        let key_len = header.algorithm.key_len();
+        let key = match (self, &header.key_mode) {
+            (KeySource::File(path), KeyMode::Raw) => read_key_file(path, key_len)?,
+            (KeySource::Passphrase(passphrase), KeyMode::Passphrase(params)) => {
+                params.derive_key(passphrase.as_bytes(), key_len)?
+            }
+            (KeySource::Identity(identity), KeyMode::Recipients(stanzas)) => hybrid::unwrap_file_key(stanzas, identity)?,
+            (_, KeyMode::Raw) => return Err(invalid_input("该文件使用原始密钥加密，请提供 --key-file")),
+            (_, KeyMode::Passphrase(_)) => return Err(invalid_input("该文件使用口令加密，请提供 --passphrase")),
+            (_, KeyMode::Recipients(_)) => return Err(invalid_input("该文件使用公钥加密，请提供 --identity")),
+        };
+        if key.len() != key_len {
+            return Err(invalid_data(format!("密钥长度应为 {} 字节", key_len)));
+        }
+        Ok(key)
+    }
+}
+
+/// 读取 128 位或 256 位原始密钥
+pub fn read_key_file(path: &Path, key_len: usize) -> io::Result<Zeroizing<Vec<u8>>> {
+    let contents = Zeroizing::new(fs::read(path)?);
+    if contents.len() == key_len {
+        return Ok(contents);
+    }
+    let text = std::str::from_utf8(&contents)
+        .map_err(|_| invalid_data(format!("密钥文件长度应为 {} 字节", key_len)))?;
+    let key = Zeroizing::new(hex::decode(text.trim()).map_err(invalid_data)?);
+    if key.len() != key_len {
+        return Err(invalid_data(format!(
+            "密钥文件长度应为 {} 字节，实际为 {} 字节",
+            key_len,
+            key.len()
+        )));
+    }
+    Ok(key)
+}
+
+/// 生成 128 或 256 位随机密钥并以十六进制写入新文件，Unix 上文件权限为 0600
+pub fn generate_key_file(path: &Path, bits: usize) -> io::Result<()> {
+    if bits != 128 && bits != 256 {
+        return Err(invalid_input(format!("不支持的密钥长度: {} 位", bits)));
+    }
+    let key_len = bits / 8;
+    let key: Zeroizing<Vec<u8>> = Zeroizing::new((0..key_len).map(|_| rand::random()).collect());
+    let encoded = Zeroizing::new(hex::encode(&*key));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    writeln!(file, "{}", &*encoded)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::format::Algorithm;
+    use crate::kdf::KdfParams;
+
+    /// 测试结束时删除的临时文件
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("aes-keys-{}-{}", std::process::id(), name));
+            let _ = fs::remove_file(&path);
+            TempPath(path)
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn generated_key_files_load_back() {
+        for bits in [128, 256] {
+            let file = TempPath::new(&format!("generated-{}", bits));
+            generate_key_file(&file.0, bits).unwrap();
+            let key = read_key_file(&file.0, bits / 8).unwrap();
+            assert_eq!(key.len(), bits / 8);
+            assert_eq!(
+                fs::read_to_string(&file.0).unwrap(),
+                format!("{}\n", hex::encode(&*key))
+            );
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                assert_eq!(fs::metadata(&file.0).unwrap().permissions().mode() & 0o777, 0o600);
+            }
+            // 不覆盖已有文件
+            assert!(generate_key_file(&file.0, bits).is_err());
+        }
+        assert!(generate_key_file(&TempPath::new("generated-192").0, 192).is_err());
+    }
+
+    #[test]
+    fn read_key_file_accepts_binary_and_hex() {
+        let file = TempPath::new("binary");
+        fs::write(&file.0, [3u8; 16]).unwrap();
+        assert_eq!(*read_key_file(&file.0, 16).unwrap(), [3u8; 16]);
+
+        fs::write(&file.0, format!("  {}\r\n", hex::encode([4u8; 32]))).unwrap();
+        assert_eq!(*read_key_file(&file.0, 32).unwrap(), [4u8; 32]);
+    }
+
+    #[test]
+    fn read_key_file_rejects_wrong_lengths_and_bad_hex() {
+        let file = TempPath::new("bad");
+        for contents in [hex::encode([1u8; 24]).into_bytes(), b"not hex".to_vec(), vec![0xff; 20]] {
+            fs::write(&file.0, &contents).unwrap();
+            assert_eq!(
+                read_key_file(&file.0, 32).unwrap_err().kind(),
+                io::ErrorKind::InvalidData
+            );
+        }
+        assert!(read_key_file(&TempPath::new("missing").0, 32).is_err());
+    }
+
+    #[test]
+    fn key_for_matches_source_to_key_mode() {
+        let file = TempPath::new("source");
+        fs::write(&file.0, hex::encode([5u8; 16])).unwrap();
+        let raw = Header::new(Algorithm::Aes128Gcm, 4096, KeyMode::Raw).unwrap();
+        let params = KdfParams::new(64, 1, 1).unwrap();
+        let passphrase = Header::new(Algorithm::Aes256Gcm, 4096, KeyMode::Passphrase(params.clone())).unwrap();
+
+        let from_file = KeySource::File(file.0.clone());
+        assert_eq!(*from_file.key_for(&raw).unwrap(), [5u8; 16]);
+        let from_passphrase = KeySource::Passphrase(Zeroizing::new("hunter2".to_string()));
+        assert_eq!(
+            from_passphrase.key_for(&passphrase).unwrap(),
+            params.derive_key(b"hunter2", 32).unwrap()
+        );
+
+        assert_eq!(
+            from_file.key_for(&passphrase).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            from_passphrase.key_for(&raw).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
@@ -1,214 +1,455 @@
-use aes::Aes128;
-use block_modes::{BlockMode, Cbc};
-use block_modes::block_padding::Pkcs7;
-use hex_literal::hex;
+mod cli;
+mod envelope;
+mod format;
+mod hybrid;
+mod integrity;
+mod kdf;
+mod keys;
+mod matrix;
+mod metrics;
+mod pipeline;
+mod stream;
+
+use anyhow::{Context, anyhow, bail};
+use clap::Parser;
+use cli::{
+    BenchArgs, BenchMatrixArgs, Cli, Command, DecryptArgs, EncryptArgs, EncryptKeyArgs, KdfArgs, KeyArgs, KeygenArgs,
+    PreCommand, PreDecryptArgs, PreDelegateArgs, PreEncryptArgs, PreKeygenArgs, PreTransformKeyArgs, VerifyArgs,
+};
+use format::{Algorithm, Header, KeyMode};
+use hybrid::{Identity, Recipient};
+use kdf::KdfParams;
+use keys::KeySource;
+use metrics::{Collector, Machine};
+use recrypt::api::{CryptoOps, Ed25519Ops, KeyGenOps, Recrypt};
 use std::fs;
-use std::io::{self, Read, Write};
+use std::io::{self, BufReader, BufWriter, Read, Write};
 use std::path::Path;
-use std::time::Instant;
-use sysinfo::{System};
-use std::mem;
+use std::process::ExitCode;
+use stream::{ChunkCipher, StreamSummary};
+use zeroize::Zeroizing;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Encrypt(args) => run_encrypt(args),
+        Command::Decrypt(args) => run_decrypt(args),
+        Command::Verify(args) => run_verify(args),
+        Command::Bench(args) => run_bench(args),
+        Command::BenchMatrix(args) => run_bench_matrix(args),
+        Command::Keygen(args) => run_keygen(args),
+        Command::Pre(PreCommand::Keygen(args)) => run_pre_keygen(args),
+        Command::Pre(PreCommand::TransformKey(args)) => run_pre_transform_key(args),
+        Command::Pre(PreCommand::Encrypt(args)) => run_pre_encrypt(args),
+        Command::Pre(PreCommand::Delegate(args)) => run_pre_delegate(args),
+        Command::Pre(PreCommand::Decrypt(args)) => run_pre_decrypt(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("错误: {:#}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn key_source(args: KeyArgs) -> anyhow::Result<KeySource> {
+    match (args.key_file, args.passphrase, args.identity) {
+        (Some(path), _, _) => Ok(KeySource::File(path)),
+        (None, Some(passphrase), _) => Ok(KeySource::Passphrase(Zeroizing::new(passphrase))),
+        (None, None, Some(path)) => {
+            let identity = Identity::load(&path).with_context(|| format!("无法读取私钥 {}", path.display()))?;
+            Ok(KeySource::Identity(identity))
+        }
+        // clap 的参数组保证三者必有其一
+        (None, None, None) => unreachable!("缺少密钥来源"),
+    }
+}
+
+/// 确定新文件的密钥模式，并返回加密正文所用的密钥
+fn encryption_key(
+    args: EncryptKeyArgs,
+    kdf: &KdfArgs,
+    algorithm: Algorithm,
+) -> anyhow::Result<(KeyMode, Zeroizing<Vec<u8>>)> {
+    if let Some(path) = args.key_file {
+        let key = keys::read_key_file(&path, algorithm.key_len())
+            .with_context(|| format!("无法读取密钥文件 {}", path.display()))?;
+        return Ok((KeyMode::Raw, key));
+    }
+    if let Some(passphrase) = args.passphrase {
+        let passphrase = Zeroizing::new(passphrase);
+        let params = KdfParams::new(kdf.kdf_memory, kdf.kdf_iterations, kdf.kdf_parallelism)?;
+        let key = params.derive_key(passphrase.as_bytes(), algorithm.key_len())?;
+        return Ok((KeyMode::Passphrase(params), key));
+    }
+    let recipients = args
+        .recipient
+        .iter()
+        .map(|value| Recipient::parse(value).with_context(|| format!("无法解析接收者 {}", value)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let file_key: Zeroizing<Vec<u8>> = Zeroizing::new((0..algorithm.key_len()).map(|_| rand::random()).collect());
+    let stanzas = hybrid::wrap_file_key(&file_key, &recipients)?;
+    Ok((KeyMode::Recipients(stanzas), file_key))
+}
+
+/// 打开输入，`-` 表示标准输入
+fn open_input(path: &Path) -> io::Result<Box<dyn Read>> {
+    if path == Path::new("-") {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(BufReader::new(fs::File::open(path)?)))
+    }
+}
+
+/// 创建输出，`-` 表示标准输出
+fn create_output(path: &Path) -> io::Result<Box<dyn Write>> {
+    if path == Path::new("-") {
+        Ok(Box::new(BufWriter::new(io::stdout().lock())))
+    } else {
+        Ok(Box::new(BufWriter::new(fs::File::create(path)?)))
+    }
+}
+
+fn run_encrypt(args: EncryptArgs) -> anyhow::Result<()> {
+    let (key_mode, key) = encryption_key(args.key, &args.kdf, args.cipher)?;
+    let header = Header::new(args.cipher, args.chunk_size, key_mode)?.with_digest(args.digest);
+    let cipher = ChunkCipher::new(header, &key)?;
+
+    let mut input = open_input(&args.input).with_context(|| format!("无法打开 {}", args.input.display()))?;
+    let mut output = create_output(&args.output).with_context(|| format!("无法创建 {}", args.output.display()))?;
+    let threads = pipeline::resolve_threads(args.threads);
+    let summary = stream::encrypt_stream(&mut input, &mut output, &cipher, threads, |_| {}).context("加密失败")?;
+    print_digest(&cipher, &summary);
+    Ok(())
+}
 
-const CHUNK_SIZE: usize = 16 * 1024; // 16KB
-const REFRESH_INTERVAL: usize = 100; // 每100次循环刷新一次
+/// 启用摘要时在标准错误上输出明文摘要，便于之后用 `verify --expect-digest` 核对
+fn print_digest(cipher: &ChunkCipher, summary: &StreamSummary) {
+    if let (Some(algorithm), Some(digest)) = (cipher.header().digest, &summary.digest) {
+        eprintln!("明文 {} 摘要: {}", algorithm, hex::encode(digest));
+    }
+}
 
-fn main() -> io::Result<()> {
-    let key = hex!("000102030405060708090a0b0c0d0e0f");
-    let iv = hex!("1a1b1c1d1e1f20212223242526272829");
+fn run_decrypt(args: DecryptArgs) -> anyhow::Result<()> {
+    if args.offset.is_some() || args.length.is_some() {
+        return run_decrypt_range(args);
+    }
+    let keys = key_source(args.key)?;
+    let mut input = open_input(&args.input).with_context(|| format!("无法打开 {}", args.input.display()))?;
+    let header = Header::read_from(&mut input).context("无法读取文件头部")?;
+    let key = keys.key_for(&header)?;
+    let cipher = ChunkCipher::new(header, &key)?;
+
+    let mut output = create_output(&args.output).with_context(|| format!("无法创建 {}", args.output.display()))?;
+    let threads = pipeline::resolve_threads(args.threads);
+    stream::decrypt_stream(&mut input, &mut output, &cipher, threads, |_| {}).context("解密失败")?;
+    Ok(())
+}
 
-    let input_path = Path::new("assets/278M.mp4");
-    let output_path = Path::new("assets/encrypted.bin");
-    let decrypted_path = Path::new("assets/decrypted278M.mp4");
+/// 只解密 `--offset`/`--length` 指定的明文区间，需要定位输入文件
+fn run_decrypt_range(args: DecryptArgs) -> anyhow::Result<()> {
+    if args.input == Path::new("-") {
+        bail!("区间解密需要可定位的输入文件，不支持标准输入");
+    }
+    let keys = key_source(args.key)?;
+    let file = fs::File::open(&args.input).with_context(|| format!("无法打开 {}", args.input.display()))?;
+    let mut input = BufReader::new(file);
+    let header = Header::read_from(&mut input).context("无法读取文件头部")?;
+    let key = keys.key_for(&header)?;
+    let cipher = ChunkCipher::new(header, &key)?;
+
+    let mut output = create_output(&args.output).with_context(|| format!("无法创建 {}", args.output.display()))?;
+    let threads = pipeline::resolve_threads(args.threads);
+    let offset = args.offset.unwrap_or(0);
+    let length = args.length.unwrap_or(u64::MAX);
+    stream::decrypt_range(&mut input, &mut output, &cipher, offset, length, threads).context("解密失败")?;
+    Ok(())
+}
 
-    // 获取系统信息
-    let mut system = System::new();
-    system.refresh_all();
+fn run_verify(args: VerifyArgs) -> anyhow::Result<()> {
+    let keys = key_source(args.key)?;
+    let mut input = open_input(&args.input).with_context(|| format!("无法打开 {}", args.input.display()))?;
+    let header = Header::read_from(&mut input).context("无法读取文件头部")?;
+    let key = keys.key_for(&header)?;
+    let cipher = ChunkCipher::new(header, &key)?;
+
+    let expected = match &args.expect_digest {
+        Some(_) if cipher.header().digest.is_none() => bail!("该文件没有记录明文摘要"),
+        Some(text) => Some(hex::decode(text.trim()).context("摘要应为十六进制")?),
+        None => None,
+    };
+
+    let threads = pipeline::resolve_threads(args.threads);
+    let summary = match args.plaintext {
+        Some(path) => {
+            let reference = BufReader::new(fs::File::open(&path).with_context(|| format!("无法打开 {}", path.display()))?);
+            let mut compare = CompareWriter::new(reference);
+            let summary =
+                stream::decrypt_stream(&mut input, &mut compare, &cipher, threads, |_| {}).context("密文认证失败")?;
+            if !compare.finish()? {
+                bail!("解密结果与 {} 不一致", path.display());
+            }
+            summary
+        }
+        // 明文只流经摘要计算，不落盘
+        None => stream::decrypt_stream(&mut input, &mut io::sink(), &cipher, threads, |_| {}).context("密文认证失败")?,
+    };
+    if let Some(expected) = expected
+        && summary.digest.as_ref() != Some(&expected)
+    {
+        bail!("明文摘要与 --expect-digest 不一致");
+    }
+    print_digest(&cipher, &summary);
+    eprintln!("验证成功，明文共 {} 字节", summary.bytes);
+    Ok(())
+}
 
-    // 记录开始时间
-    let start_time = Instant::now();
-    let mut encrypt_counter = 0;
-    let mut encrypt_cpu_usages = Vec::new();
-    let mut encrypt_memory_usages = Vec::new();
-    let mut encrypt_buffer_sizes = Vec::new();
-    let mut encrypt_cipher_sizes = Vec::new();
+fn run_bench(args: BenchArgs) -> anyhow::Result<()> {
+    let work_dir = args.work_dir.unwrap_or_else(std::env::temp_dir);
+    let input_path = args.input.as_path();
+    let output_path = work_dir.join("faes-bench.enc");
+    let decrypted_path = work_dir.join("faes-bench.dec");
+    let key: Zeroizing<Vec<u8>> = Zeroizing::new((0..args.cipher.key_len()).map(|_| rand::random()).collect());
+    let threads = pipeline::resolve_threads(args.threads);
+    let cipher_name = args.cipher.to_string();
 
     // 1. 加密文件
-    encrypt_file(
-        input_path,
-        output_path,
-        &key,
-        &iv,
-        &mut system,
-        &mut encrypt_counter,
-        &mut encrypt_cpu_usages,
-        &mut encrypt_memory_usages,
-        &mut encrypt_buffer_sizes,
-        &mut encrypt_cipher_sizes,
-    )?;
-    println!("加密完成，密文已保存到 encrypted.bin");
-
-    // 计算加密所用的时间
-    let encryption_time = start_time.elapsed();
-    println!("加密时间: {:?}", encryption_time);
-
-    // 记录解密开始时间
-    let start_time = Instant::now();
-    let mut decrypt_counter = 0;
-    let mut decrypt_cpu_usages = Vec::new();
-    let mut decrypt_memory_usages = Vec::new();
-    let mut decrypt_buffer_sizes = Vec::new();
-    let mut decrypt_cipher_sizes = Vec::new();
+    let mut collector = Collector::start();
+    encrypt_file(input_path, &output_path, &key, args.cipher, args.chunk_size, threads, &mut collector)?;
+    let encrypt_record = collector.finish("encrypt", &cipher_name, args.chunk_size, threads);
+    eprintln!("加密完成，密文已保存到 {}", output_path.display());
 
     // 2. 解密文件
-    decrypt_file(
-        output_path,
-        decrypted_path,
-        &key,
-        &iv,
-        &mut system,
-        &mut decrypt_counter,
-        &mut decrypt_cpu_usages,
-        &mut decrypt_memory_usages,
-        &mut decrypt_buffer_sizes,
-        &mut decrypt_cipher_sizes,
-    )?;
-    println!("解密完成，内容已保存到 decrypted2.2G.mp4");
-
-    // 计算解密所用的时间
-    let decryption_time = start_time.elapsed();
-    println!("解密时间: {:?}", decryption_time);
+    let mut collector = Collector::start();
+    decrypt_file(&output_path, &decrypted_path, &key, threads, &mut collector)?;
+    let decrypt_record = collector.finish("decrypt", &cipher_name, args.chunk_size, threads);
+    eprintln!("解密完成，内容已保存到 {}", decrypted_path.display());
 
     // 3. 验证解密结果
-    if files_equal(input_path, decrypted_path)? {
-        println!("解密验证成功！");
-    } else {
-        println!("解密验证失败！");
+    if !files_equal(input_path, &decrypted_path)? {
+        bail!("解密验证失败：{} 与原文不一致", decrypted_path.display());
     }
+    eprintln!("解密验证成功！");
 
-    // 打印加密统计信息
-    print_statistics("加密", &encrypt_cpu_usages, &encrypt_memory_usages, &encrypt_buffer_sizes, &encrypt_cipher_sizes);
+    // 4. 输出报告
+    let machine = Machine::detect();
+    metrics::write_report(args.report.as_deref(), args.format, &machine, &[encrypt_record, decrypt_record])
+        .context("无法写出基准测试报告")?;
+    Ok(())
+}
 
-    // 打印解密统计信息
-    print_statistics("解密", &decrypt_cpu_usages, &decrypt_memory_usages, &decrypt_buffer_sizes, &decrypt_cipher_sizes);
+fn run_bench_matrix(args: BenchMatrixArgs) -> anyhow::Result<()> {
+    let input = match &args.input {
+        Some(path) => fs::read(path).with_context(|| format!("无法读取 {}", path.display()))?,
+        None => matrix::synthetic_input(args.size),
+    };
+    let threads = pipeline::resolve_threads(args.threads);
+
+    let mut records = Vec::new();
+    for &chunk_size in &args.chunk_sizes {
+        if chunk_size == 0 || chunk_size > format::MAX_CHUNK_SIZE {
+            bail!("分块大小无效: {}", chunk_size);
+        }
+        for &cipher in &args.ciphers {
+            eprintln!("{}，分块 {} 字节 ...", cipher, chunk_size);
+            let case = matrix::run_case(&input, cipher, chunk_size, threads)
+                .with_context(|| format!("{} 测试失败", cipher))?;
+            records.extend(case);
+        }
+    }
+    matrix::fill_relative(&mut records);
 
+    let machine = Machine::detect();
+    metrics::write_report(args.report.as_deref(), args.format, &machine, &records).context("无法写出基准测试报告")?;
     Ok(())
 }
 
-/// 分块加密文件并监控资源使用情况
-fn encrypt_file(
-    input_path: &Path,
-    output_path: &Path,
-    key: &[u8],
-    iv: &[u8],
-    system: &mut System,
-    counter: &mut usize,
-    cpu_usages: &mut Vec<f32>,
-    memory_usages: &mut Vec<u64>,
-    buffer_sizes: &mut Vec<usize>,
-    cipher_sizes: &mut Vec<usize>,
-) -> io::Result<()> {
-    let mut input_file = fs::File::open(input_path)?;
-    let mut output_file = fs::File::create(output_path)?;
+fn run_keygen(args: KeygenArgs) -> anyhow::Result<()> {
+    if args.x25519 {
+        let recipient = hybrid::generate_identity(&args.output)
+            .with_context(|| format!("无法写入私钥文件 {}", args.output.display()))?;
+        let mut public_path = args.output.clone().into_os_string();
+        public_path.push(".pub");
+        fs::write(&public_path, format!("{}\n", recipient.encode()))
+            .with_context(|| format!("无法写入公钥文件 {}", Path::new(&public_path).display()))?;
+        println!("{}", recipient.encode());
+        return Ok(());
+    }
+    keys::generate_key_file(&args.output, args.bits)
+        .with_context(|| format!("无法写入密钥文件 {}", args.output.display()))?;
+    eprintln!("已生成 {} 位密钥: {}", args.bits, args.output.display());
+    Ok(())
+}
 
-    let mut buffer = vec![0u8; CHUNK_SIZE];
+fn run_pre_keygen(args: PreKeygenArgs) -> anyhow::Result<()> {
+    let recrypt = Recrypt::new();
+    let (private_key, public_key) = recrypt.generate_key_pair().map_err(|e| anyhow!("{}", e))?;
+    envelope::write_private_key(&args.output, &private_key)
+        .with_context(|| format!("无法写入私钥文件 {}", args.output.display()))?;
+    let mut public_path = args.output.clone().into_os_string();
+    public_path.push(".pub");
+    let encoded = envelope::encode_public_key(&public_key);
+    fs::write(&public_path, format!("{}\n", encoded))
+        .with_context(|| format!("无法写入公钥文件 {}", Path::new(&public_path).display()))?;
+    println!("{}", encoded);
+    Ok(())
+}
 
-    loop {
-        let bytes_read = input_file.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
+fn run_pre_transform_key(args: PreTransformKeyArgs) -> anyhow::Result<()> {
+    let recrypt = Recrypt::new();
+    let from = envelope::read_private_key(&args.from).with_context(|| format!("无法读取私钥 {}", args.from.display()))?;
+    let to = envelope::read_public_key(&args.to).with_context(|| format!("无法读取公钥 {}", args.to.display()))?;
+    let signing_keypair = recrypt.generate_ed25519_key_pair();
+    let transform_key = recrypt
+        .generate_transform_key(&from, &to, &signing_keypair)
+        .map_err(|e| anyhow!("无法生成变换密钥: {}", e))?;
+    envelope::write_transform_key(&args.output, &transform_key)
+        .with_context(|| format!("无法写入变换密钥 {}", args.output.display()))?;
+    Ok(())
+}
 
-        // 每次循环都创建新的加密器实例
-        let cipher = Cbc::<Aes128, Pkcs7>::new_from_slices(key, iv)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-        if bytes_read < CHUNK_SIZE {
-            let chunk = &buffer[..bytes_read];
-            let ciphertext = cipher.encrypt_vec(chunk);
-            output_file.write_all(&ciphertext)?;
-        } else {
-            let ciphertext = cipher.encrypt_vec(&buffer);
-            output_file.write_all(&ciphertext)?;
+fn run_pre_encrypt(args: PreEncryptArgs) -> anyhow::Result<()> {
+    envelope::check_body_key_len(args.cipher.key_len())?;
+    let recrypt = Recrypt::new();
+    let to = envelope::read_public_key(&args.to).with_context(|| format!("无法读取公钥 {}", args.to.display()))?;
+
+    // 正文密钥由随机 Plaintext 派生，Plaintext 加密给接收者后放入信封头部
+    let plaintext = recrypt.gen_plaintext();
+    let signing_keypair = recrypt.generate_ed25519_key_pair();
+    let encrypted_value = recrypt
+        .encrypt(&plaintext, &to, &signing_keypair)
+        .map_err(|e| anyhow!("recrypt 加密失败: {}", e))?;
+    let body_key = recrypt.derive_symmetric_key(&plaintext);
+    let header = Header::new(args.cipher, args.chunk_size, KeyMode::Raw)?.with_digest(args.digest);
+    let cipher = ChunkCipher::new(header, body_key.bytes())?;
+
+    let mut input = open_input(&args.input).with_context(|| format!("无法打开 {}", args.input.display()))?;
+    let mut output = create_output(&args.output).with_context(|| format!("无法创建 {}", args.output.display()))?;
+    envelope::write_header(&mut output, &encrypted_value)?;
+    let threads = pipeline::resolve_threads(args.threads);
+    let summary = stream::encrypt_stream(&mut input, &mut output, &cipher, threads, |_| {}).context("加密失败")?;
+    print_digest(&cipher, &summary);
+    Ok(())
+}
+
+fn run_pre_delegate(args: PreDelegateArgs) -> anyhow::Result<()> {
+    let recrypt = Recrypt::new();
+    let transform_key = envelope::read_transform_key(&args.transform_key)
+        .with_context(|| format!("无法读取变换密钥 {}", args.transform_key.display()))?;
+    let mut input = open_input(&args.input).with_context(|| format!("无法打开 {}", args.input.display()))?;
+    let encrypted_value = envelope::read_header(&mut input).context("无法读取信封头部")?;
+
+    let signing_keypair = recrypt.generate_ed25519_key_pair();
+    let transformed = recrypt
+        .transform(encrypted_value, transform_key, &signing_keypair)
+        .map_err(|e| anyhow!("变换失败: {}", e))?;
+
+    let mut output = create_output(&args.output).with_context(|| format!("无法创建 {}", args.output.display()))?;
+    envelope::write_header(&mut output, &transformed)?;
+    io::copy(&mut input, &mut output)?;
+    output.flush()?;
+    Ok(())
+}
+
+fn run_pre_decrypt(args: PreDecryptArgs) -> anyhow::Result<()> {
+    let recrypt = Recrypt::new();
+    let private_key = envelope::read_private_key(&args.identity)
+        .with_context(|| format!("无法读取私钥 {}", args.identity.display()))?;
+    let mut input = open_input(&args.input).with_context(|| format!("无法打开 {}", args.input.display()))?;
+    let encrypted_value = envelope::read_header(&mut input).context("无法读取信封头部")?;
+    let plaintext = recrypt
+        .decrypt(encrypted_value, &private_key)
+        .map_err(|e| anyhow!("recrypt 解密失败: {}", e))?;
+    let body_key = recrypt.derive_symmetric_key(&plaintext);
+
+    let header = Header::read_from(&mut input).context("无法读取正文头部")?;
+    if header.key_mode != KeyMode::Raw {
+        bail!("信封正文的密钥模式无效");
+    }
+    let cipher = ChunkCipher::new(header, body_key.bytes())?;
+    let mut output = create_output(&args.output).with_context(|| format!("无法创建 {}", args.output.display()))?;
+    let threads = pipeline::resolve_threads(args.threads);
+    stream::decrypt_stream(&mut input, &mut output, &cipher, threads, |_| {}).context("解密失败")?;
+    Ok(())
+}
+
+/// 将写入的数据与参考输入逐字节比较的 `Write`
+struct CompareWriter<R: Read> {
+    reference: R,
+    buffer: Vec<u8>,
+    equal: bool,
+}
+
+impl<R: Read> CompareWriter<R> {
+    fn new(reference: R) -> Self {
+        CompareWriter {
+            reference,
+            buffer: Vec::new(),
+            equal: true,
         }
+    }
 
-        *counter += 1;
-        if *counter % REFRESH_INTERVAL == 0 {
-            system.refresh_all();
-            let process = system.process(sysinfo::get_current_pid().unwrap()).unwrap();
-            let cpu_usage = process.cpu_usage();
-            let memory_usage = process.memory();
-            let buffer_size = CHUNK_SIZE / 1024;
-            let cipher_size = mem::size_of::<Cbc<Aes128, Pkcs7>>() / 1024;
-
-            cpu_usages.push(cpu_usage);
-            memory_usages.push(memory_usage);
-            buffer_sizes.push(buffer_size);
-            cipher_sizes.push(cipher_size);
-
-            // println!("加密中 CPU 使用率: {:.2}%", cpu_usage);
-            // println!("当前内存使用量: {:?} KB", memory_usage);
-            // println!("缓冲区大小: {} KB", buffer_size);
-            // println!("加密器大小: {} KB", cipher_size);
+    /// 写入结束后调用，参考输入还有剩余数据时同样视为不一致
+    fn finish(mut self) -> io::Result<bool> {
+        let mut probe = [0u8; 1];
+        Ok(self.equal && stream::read_full(&mut self.reference, &mut probe)? == 0)
+    }
+}
+
+impl<R: Read> Write for CompareWriter<R> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.equal {
+            self.buffer.resize(buf.len(), 0);
+            let n = stream::read_full(&mut self.reference, &mut self.buffer)?;
+            self.equal = n == buf.len() && self.buffer[..] == *buf;
         }
+        Ok(buf.len())
     }
 
-    Ok(())
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
-/// 分块解密文件并监控资源使用情况
-fn decrypt_file(
+/// 分块加密文件，每块的完成情况记入 `collector`
+fn encrypt_file(
     input_path: &Path,
     output_path: &Path,
     key: &[u8],
-    iv: &[u8],
-    system: &mut System,
-    counter: &mut usize,
-    cpu_usages: &mut Vec<f32>,
-    memory_usages: &mut Vec<u64>,
-    buffer_sizes: &mut Vec<usize>,
-    cipher_sizes: &mut Vec<usize>,
+    algorithm: Algorithm,
+    chunk_size: u32,
+    threads: usize,
+    collector: &mut Collector,
 ) -> io::Result<()> {
     let mut input_file = fs::File::open(input_path)?;
-    let mut output_file = fs::File::create(output_path)?;
+    let mut output_file = BufWriter::new(fs::File::create(output_path)?);
 
-    let mut buffer = vec![0u8; CHUNK_SIZE + 16];
+    // 每个文件使用随机 nonce 前缀，分块之间通过计数器区分
+    let header = Header::new(algorithm, chunk_size, KeyMode::Raw)?;
+    let cipher = ChunkCipher::new(header, key)?;
 
-    loop {
-        let bytes_read = input_file.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
+    stream::encrypt_stream(&mut input_file, &mut output_file, &cipher, threads, |n| collector.record_chunk(n))?;
+    Ok(())
+}
 
-        // 每次循环都创建新的加密器实例
-        let cipher = Cbc::<Aes128, Pkcs7>::new_from_slices(key, iv)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-        let decrypted = cipher.decrypt_vec(&buffer[..bytes_read])
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-        output_file.write_all(&decrypted)?;
-
-        *counter += 1;
-        if *counter % REFRESH_INTERVAL == 0 {
-            system.refresh_all();
-            let process = system.process(sysinfo::get_current_pid().unwrap()).unwrap();
-            let cpu_usage = process.cpu_usage();
-            let memory_usage = process.memory();
-            let buffer_size = (CHUNK_SIZE + 16) / 1024;
-            let cipher_size = mem::size_of::<Cbc<Aes128, Pkcs7>>() / 1024;
-
-            cpu_usages.push(cpu_usage);
-            memory_usages.push(memory_usage);
-            buffer_sizes.push(buffer_size);
-            cipher_sizes.push(cipher_size);
-
-            // println!("解密中 CPU 使用率: {:.2}%", cpu_usage);
-            // println!("当前内存使用量: {:?} KB", memory_usage);
-            // println!("缓冲区大小: {} KB", buffer_size);
-            // println!("加密器大小: {} KB", cipher_size);
-        }
-    }
+/// 分块解密文件，每块的完成情况记入 `collector`
+///
+/// 算法、分块大小和 nonce 前缀都从文件头部读取，任一分块认证失败即返回错误。
+fn decrypt_file(
+    input_path: &Path,
+    output_path: &Path,
+    key: &[u8],
+    threads: usize,
+    collector: &mut Collector,
+) -> io::Result<()> {
+    let mut input_file = BufReader::new(fs::File::open(input_path)?);
+    let mut output_file = BufWriter::new(fs::File::create(output_path)?);
 
+    let header = Header::read_from(&mut input_file)?;
+    let cipher = ChunkCipher::new(header, key)?;
+
+    stream::decrypt_stream(&mut input_file, &mut output_file, &cipher, threads, |n| collector.record_chunk(n))?;
     Ok(())
 }
 
@@ -235,44 +476,3 @@ fn files_equal(path1: &Path, path2: &Path) -> io::Result<bool> {
         }
     }
 }
-
-/// 打印统计信息
-fn print_statistics(
-    operation: &str,
-    cpu_usages: &[f32],
-    memory_usages: &[u64],
-    buffer_sizes: &[usize],
-    cipher_sizes: &[usize],
-) {
-    if cpu_usages.is_empty() {
-        println!("{} 没有收集到 CPU 使用率数据", operation);
-        return;
-    }
-
-    let cpu_usage_sum: f32 = cpu_usages.iter().sum();
-    let cpu_usage_avg = cpu_usage_sum / cpu_usages.len() as f32;
-    let cpu_usage_min = cpu_usages.iter().copied().fold(f32::INFINITY, f32::min);
-    let cpu_usage_max = cpu_usages.iter().copied().fold(f32::NEG_INFINITY, f32::max);
-
-    let memory_usage_sum: u64 = memory_usages.iter().sum();
-    let memory_usage_avg = memory_usage_sum as f64 / memory_usages.len() as f64;
-    let memory_usage_min = *memory_usages.iter().min().unwrap();
-    let memory_usage_max = *memory_usages.iter().max().unwrap();
-    let memory_usage_sum: u64 = memory_usages.iter().sum();
-
-    let buffer_size_sum: usize = buffer_sizes.iter().sum();
-    let buffer_size_avg = buffer_size_sum as f64 / buffer_sizes.len() as f64;
-    let buffer_size_min = *buffer_sizes.iter().min().unwrap();
-    let buffer_size_max = *buffer_sizes.iter().max().unwrap();
-
-    let cipher_size_sum: usize = cipher_sizes.iter().sum();
-    let cipher_size_avg = cipher_size_sum as f64 / cipher_sizes.len() as f64;
-    let cipher_size_min = *cipher_sizes.iter().min().unwrap();
-    let cipher_size_max = *cipher_sizes.iter().max().unwrap();
-
-    // println!("{} 统计信息:", operation);
-    // println!("  CPU 使用率 - 平均: {:.2}%, 最小: {:.2}%, 最大: {:.2}%", cpu_usage_avg, cpu_usage_min, cpu_usage_max);
-    // println!("  内存使用量 - 平均: {:.2} KB, 最小: {} KB, 最大: {} KB, 总计: {} KB", memory_usage_avg, memory_usage_min, memory_usage_max, memory_usage_sum);
-    // println!("  缓冲区大小 - 平均: {:.2} KB, 最小: {} KB, 最大: {} KB", buffer_size_avg, buffer_size_min, buffer_size_max);
-    // println!("  加密器大小 - 平均: {:.2} KB, 最小: {} KB, 最大: {} KB", cipher_size_avg, cipher_size_min, cipher_size_max);
-}
@@ -0,0 +1,363 @@
+//! 算法 × 分块大小的对比基准
+//!
+//! 在内存中用同一份输入依次跑 AES-128/256 的 CBC、CTR、GCM 以及 ChaCha20-Poly1305，
+//! 排除磁盘 I/O 的影响，只比较原语本身的吞吐量与资源占用。
+//! CBC 和 CTR 没有认证，这里的分块方式只用于测速，不是可用的文件格式。
+
+use crate::format::{Algorithm, Header, KeyMode, invalid_data, invalid_input};
+use crate::metrics::{Collector, Record};
+use crate::pipeline::{self, Chunk};
+use crate::stream::ChunkCipher;
+use aes::{Aes128, Aes256};
+use block_modes::block_padding::Pkcs7;
+use block_modes::{BlockMode, Cbc};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use rand::RngCore;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+type Aes128Cbc = Cbc<Aes128, Pkcs7>;
+type Aes256Cbc = Cbc<Aes256, Pkcs7>;
+type Aes128Ctr = ctr::Ctr128BE<aes_gcm::aes::Aes128>;
+type Aes256Ctr = ctr::Ctr128BE<aes_gcm::aes::Aes256>;
+
+/// 参与对比的算法与模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchCipher {
+    Aes128Cbc,
+    Aes256Cbc,
+    Aes128Ctr,
+    Aes256Ctr,
+    Aead(Algorithm),
+}
+
+impl BenchCipher {
+    pub const ALL: [BenchCipher; 7] = [
+        BenchCipher::Aes128Cbc,
+        BenchCipher::Aes256Cbc,
+        BenchCipher::Aes128Ctr,
+        BenchCipher::Aes256Ctr,
+        BenchCipher::Aead(Algorithm::Aes128Gcm),
+        BenchCipher::Aead(Algorithm::Aes256Gcm),
+        BenchCipher::Aead(Algorithm::ChaCha20Poly1305),
+    ];
+
+    fn key_len(self) -> usize {
+        match self {
+            BenchCipher::Aes128Cbc | BenchCipher::Aes128Ctr => 16,
+            BenchCipher::Aes256Cbc | BenchCipher::Aes256Ctr => 32,
+            BenchCipher::Aead(algorithm) => algorithm.key_len(),
+        }
+    }
+}
+
+impl fmt::Display for BenchCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BenchCipher::Aes128Cbc => f.write_str("aes128-cbc"),
+            BenchCipher::Aes256Cbc => f.write_str("aes256-cbc"),
+            BenchCipher::Aes128Ctr => f.write_str("aes128-ctr"),
+            BenchCipher::Aes256Ctr => f.write_str("aes256-ctr"),
+            BenchCipher::Aead(algorithm) => algorithm.fmt(f),
+        }
+    }
+}
+
+impl FromStr for BenchCipher {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        BenchCipher::ALL
+            .into_iter()
+            .find(|cipher| cipher.to_string() == s)
+            .ok_or_else(|| {
+                let names: Vec<String> = BenchCipher::ALL.iter().map(ToString::to_string).collect();
+                invalid_input(format!("未知的算法: {}（可选 {}）", s, names.join("、")))
+            })
+    }
+}
+
+/// 绑定了随机密钥的单个算法实例
+enum Engine {
+    Cbc128(Zeroizing<Vec<u8>>, [u8; 16]),
+    Cbc256(Zeroizing<Vec<u8>>, [u8; 16]),
+    Ctr128(Zeroizing<Vec<u8>>, [u8; 16]),
+    Ctr256(Zeroizing<Vec<u8>>, [u8; 16]),
+    Aead(Box<ChunkCipher>),
+}
+
+impl Engine {
+    fn new(cipher: BenchCipher, chunk_size: u32) -> io::Result<Self> {
+        let mut rng = rand::rng();
+        let mut key = Zeroizing::new(vec![0u8; cipher.key_len()]);
+        rng.fill_bytes(&mut key);
+        let mut iv = [0u8; 16];
+        rng.fill_bytes(&mut iv[..12]);
+        Ok(match cipher {
+            BenchCipher::Aes128Cbc => Engine::Cbc128(key, iv),
+            BenchCipher::Aes256Cbc => Engine::Cbc256(key, iv),
+            BenchCipher::Aes128Ctr => Engine::Ctr128(key, iv),
+            BenchCipher::Aes256Ctr => Engine::Ctr256(key, iv),
+            BenchCipher::Aead(algorithm) => {
+                let header = Header::new(algorithm, chunk_size, KeyMode::Raw)?;
+                Engine::Aead(Box::new(ChunkCipher::new(header, &key)?))
+            }
+        })
+    }
+
+    /// 每块的 IV 由随机前缀与块序号组成，CTR 的块内计数器从 0 开始
+    fn chunk_iv(base: &[u8; 16], index: u32) -> [u8; 16] {
+        let mut iv = *base;
+        iv[8..12].copy_from_slice(&index.to_be_bytes());
+        iv
+    }
+
+    fn encrypt(&self, chunk: &Chunk) -> io::Result<Vec<u8>> {
+        match self {
+            Engine::Cbc128(key, iv) => Ok(Aes128Cbc::new_from_slices(key, &Self::chunk_iv(iv, chunk.index))
+                .map_err(invalid_input)?
+                .encrypt_vec(&chunk.data)),
+            Engine::Cbc256(key, iv) => Ok(Aes256Cbc::new_from_slices(key, &Self::chunk_iv(iv, chunk.index))
+                .map_err(invalid_input)?
+                .encrypt_vec(&chunk.data)),
+            Engine::Ctr128(key, iv) => apply_ctr::<Aes128Ctr>(key, &Self::chunk_iv(iv, chunk.index), &chunk.data),
+            Engine::Ctr256(key, iv) => apply_ctr::<Aes256Ctr>(key, &Self::chunk_iv(iv, chunk.index), &chunk.data),
+            Engine::Aead(cipher) => cipher.seal(chunk.index, chunk.last, &chunk.data),
+        }
+    }
+
+    fn decrypt(&self, chunk: &Chunk) -> io::Result<Vec<u8>> {
+        match self {
+            Engine::Cbc128(key, iv) => Aes128Cbc::new_from_slices(key, &Self::chunk_iv(iv, chunk.index))
+                .map_err(invalid_input)?
+                .decrypt_vec(&chunk.data)
+                .map_err(invalid_data),
+            Engine::Cbc256(key, iv) => Aes256Cbc::new_from_slices(key, &Self::chunk_iv(iv, chunk.index))
+                .map_err(invalid_input)?
+                .decrypt_vec(&chunk.data)
+                .map_err(invalid_data),
+            Engine::Ctr128(key, iv) => apply_ctr::<Aes128Ctr>(key, &Self::chunk_iv(iv, chunk.index), &chunk.data),
+            Engine::Ctr256(key, iv) => apply_ctr::<Aes256Ctr>(key, &Self::chunk_iv(iv, chunk.index), &chunk.data),
+            Engine::Aead(cipher) => cipher.open(chunk.index, chunk.last, &chunk.data),
+        }
+    }
+}
+
+fn apply_ctr<C: KeyIvInit + StreamCipher>(key: &[u8], iv: &[u8; 16], data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut cipher = C::new_from_slices(key, iv).map_err(invalid_input)?;
+    let mut output = data.to_vec();
+    cipher.apply_keystream(&mut output);
+    Ok(output)
+}
+
+/// 生成指定大小的随机输入
+pub fn synthetic_input(size: usize) -> Vec<u8> {
+    let mut data = vec![0u8; size];
+    rand::rng().fill_bytes(&mut data);
+    data
+}
+
+/// 按给定分块大小切分输入，返回逐块产出 [`Chunk`] 的闭包
+fn chunks_of<'a>(
+    data: &'a [u8],
+    lens: impl IntoIterator<Item = usize> + 'a,
+) -> impl FnMut() -> io::Result<Option<Chunk>> + 'a {
+    let mut lens = lens.into_iter().peekable();
+    let mut offset = 0;
+    let mut index = 0u32;
+    move || {
+        let Some(len) = lens.next() else { return Ok(None) };
+        let chunk = Chunk {
+            index,
+            last: lens.peek().is_none(),
+            data: data[offset..offset + len].to_vec(),
+        };
+        offset += len;
+        index += 1;
+        Ok(Some(chunk))
+    }
+}
+
+/// 对一个算法和分块大小各跑一遍加密和解密，并校验解密结果与输入一致
+pub fn run_case(input: &[u8], cipher: BenchCipher, chunk_size: u32, threads: usize) -> io::Result<[Record; 2]> {
+    let engine = Engine::new(cipher, chunk_size)?;
+    let name = cipher.to_string();
+
+    // 与 STREAM 格式一致：末块总是短于分块大小，整除时补一个空块
+    let size = chunk_size as usize;
+    let plaintext_lens: Vec<usize> = input
+        .chunks(size)
+        .map(<[u8]>::len)
+        .chain(input.len().is_multiple_of(size).then_some(0))
+        .collect();
+    if plaintext_lens.len() > u32::MAX as usize {
+        return Err(invalid_input("分块数量超出计数器范围"));
+    }
+
+    let mut ciphertext = Vec::with_capacity(input.len() + plaintext_lens.len() * 32);
+    let mut ciphertext_lens = Vec::with_capacity(plaintext_lens.len());
+    let mut collector = Collector::start();
+    pipeline::run(
+        chunks_of(input, plaintext_lens),
+        &mut ciphertext,
+        threads,
+        |chunk| engine.encrypt(chunk),
+        |chunk, output| {
+            ciphertext_lens.push(output.len());
+            collector.record_chunk(chunk.data.len());
+        },
+    )?;
+    let encrypt = collector.finish("encrypt", &name, chunk_size, threads);
+
+    let mut decrypted = Vec::with_capacity(input.len());
+    let mut collector = Collector::start();
+    pipeline::run(
+        chunks_of(&ciphertext, ciphertext_lens),
+        &mut decrypted,
+        threads,
+        |chunk| engine.decrypt(chunk),
+        |_, output| collector.record_chunk(output.len()),
+    )?;
+    let decrypt = collector.finish("decrypt", &name, chunk_size, threads);
+
+    if decrypted != input {
+        return Err(invalid_data(format!("{} 解密结果与输入不一致", name)));
+    }
+    Ok([encrypt, decrypt])
+}
+
+/// 以同一操作、同一分块大小下最快的一项为 1，填写每项的相对吞吐量
+pub fn fill_relative(records: &mut [Record]) {
+    let fastest: Vec<f64> = records
+        .iter()
+        .map(|r| {
+            records
+                .iter()
+                .filter(|o| o.operation == r.operation && o.chunk_size == r.chunk_size)
+                .map(Record::throughput)
+                .fold(0.0, f64::max)
+        })
+        .collect();
+    for (record, fastest) in records.iter_mut().zip(fastest) {
+        record.relative = (fastest > 0.0).then(|| record.throughput() / fastest);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    /// 吞吐量为 `bytes / seconds` 字节每秒的记录
+    fn record(operation: &str, cipher: &str, chunk_size: u32, bytes: u64, seconds: u64) -> Record {
+        Record {
+            operation: operation.to_string(),
+            cipher: cipher.to_string(),
+            chunk_size,
+            threads: 1,
+            bytes,
+            chunks: 1,
+            elapsed: Duration::from_secs(seconds),
+            latency_p50: Duration::ZERO,
+            latency_p90: Duration::ZERO,
+            latency_p99: Duration::ZERO,
+            latency_max: Duration::ZERO,
+            rss_peak: 0,
+            cpu_avg: 0.0,
+            cpu_max: 0.0,
+            relative: None,
+        }
+    }
+
+    #[test]
+    fn run_case_roundtrips_every_cipher() {
+        let input = synthetic_input(1000);
+        // 1000 按 16 切分余 8 字节，按 100 整除会多出一个空块，按 333 余 1 字节，按 4096 只有一块 1000 字节
+        for cipher in BenchCipher::ALL {
+            for (chunk_size, chunks) in [(16, 63), (100, 11), (333, 4), (4096, 1)] {
+                for threads in [1, 3] {
+                    let [encrypt, decrypt] = run_case(&input, cipher, chunk_size, threads)
+                        .unwrap_or_else(|err| panic!("{} 分块 {}: {}", cipher, chunk_size, err));
+                    for (record, operation) in [(&encrypt, "encrypt"), (&decrypt, "decrypt")] {
+                        assert_eq!(record.operation, operation);
+                        assert_eq!(record.cipher, cipher.to_string());
+                        assert_eq!(record.chunk_size, chunk_size);
+                        assert_eq!(record.threads, threads);
+                        assert_eq!(record.bytes, 1000);
+                        assert_eq!(record.chunks, chunks);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn run_case_handles_empty_input() {
+        for cipher in BenchCipher::ALL {
+            let [encrypt, decrypt] = run_case(&[], cipher, 64, 2).unwrap();
+            assert_eq!((encrypt.bytes, encrypt.chunks), (0, 1));
+            assert_eq!((decrypt.bytes, decrypt.chunks), (0, 1));
+        }
+    }
+
+    #[test]
+    fn cbc_pads_chunks_and_every_chunk_gets_its_own_iv() {
+        let data = synthetic_input(100);
+        for cipher in [BenchCipher::Aes128Cbc, BenchCipher::Aes256Cbc] {
+            let engine = Engine::new(cipher, 100).unwrap();
+            let chunk = |index| Chunk {
+                index,
+                last: false,
+                data: data.clone(),
+            };
+            let first = engine.encrypt(&chunk(0)).unwrap();
+            assert_eq!(first.len(), 112);
+            assert_ne!(engine.encrypt(&chunk(1)).unwrap(), first);
+            let sealed = Chunk {
+                index: 0,
+                last: false,
+                data: first,
+            };
+            assert_eq!(engine.decrypt(&sealed).unwrap(), data);
+            let wrong_index = Chunk { index: 1, ..sealed };
+            assert_ne!(engine.decrypt(&wrong_index).ok(), Some(data.clone()));
+        }
+    }
+
+    #[test]
+    fn fill_relative_compares_within_operation_and_chunk_size() {
+        let mut records = [
+            record("encrypt", "aes128-ctr", 4096, 400, 1),
+            record("encrypt", "aes128-cbc", 4096, 100, 1),
+            record("decrypt", "aes128-cbc", 4096, 50, 1),
+            record("encrypt", "aes128-cbc", 65536, 300, 1),
+            record("decrypt", "aes128-ctr", 65536, 0, 1),
+        ];
+        fill_relative(&mut records);
+        let relative: Vec<Option<f64>> = records.iter().map(|r| r.relative).collect();
+        // 同组最快为 0 时无法比较
+        assert_eq!(relative, [Some(1.0), Some(0.25), Some(1.0), Some(1.0), None]);
+    }
+
+    #[test]
+    fn cipher_names_parse_back() {
+        for cipher in BenchCipher::ALL {
+            assert_eq!(cipher.to_string().parse::<BenchCipher>().unwrap(), cipher);
+        }
+        assert_eq!(
+            "aes256-gcm".parse::<BenchCipher>().unwrap(),
+            BenchCipher::Aead(Algorithm::Aes256Gcm)
+        );
+        assert_eq!(
+            "chacha20-poly1305".parse::<BenchCipher>().unwrap(),
+            BenchCipher::Aead(Algorithm::ChaCha20Poly1305)
+        );
+        for name in ["AES128-CBC", "aes-128-cbc", "aes192-ctr", ""] {
+            let err = name.parse::<BenchCipher>().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            assert!(err.to_string().contains("aes128-cbc、aes256-cbc、aes128-ctr"));
+        }
+    }
+}
@@ -0,0 +1,573 @@
+//! 基准测试指标收集与报告
+//!
+//! [`Collector`] 在加解密过程中记录每块的完成时间，并每隔若干块采样一次进程的
+//! CPU 使用率和常驻内存（RSS）。结束后汇总为 [`Record`]，可输出为文本、JSON 或 CSV，
+//! 便于在不同算法、分块大小和机器之间对比。
+
+use crate::format::invalid_input;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessesToUpdate, System};
+
+/// 每处理多少块采样一次进程资源
+pub const SAMPLE_INTERVAL: usize = 100;
+
+/// 单次加密或解密过程中的指标收集器
+pub struct Collector {
+    system: System,
+    pid: Option<Pid>,
+    start: Instant,
+    last: Instant,
+    latencies: Vec<Duration>,
+    bytes: u64,
+    cpu_samples: Vec<f32>,
+    /// 进程累计 CPU 时间（毫秒）：开始时与最近一次采样时
+    cpu_time_start: u64,
+    cpu_time: u64,
+    rss_peak: u64,
+}
+
+impl Collector {
+    pub fn start() -> Self {
+        let mut collector = Collector {
+            system: System::new(),
+            pid: sysinfo::get_current_pid().ok(),
+            start: Instant::now(),
+            last: Instant::now(),
+            latencies: Vec::new(),
+            bytes: 0,
+            cpu_samples: Vec::new(),
+            cpu_time_start: 0,
+            cpu_time: 0,
+            rss_peak: 0,
+        };
+        // 首次刷新只作为 CPU 使用率的基准，不计入样本
+        collector.refresh();
+        collector.cpu_samples.clear();
+        collector.cpu_time_start = collector.cpu_time;
+        collector.start = Instant::now();
+        collector.last = collector.start;
+        collector
+    }
+
+    /// 每完成一块调用一次；延迟按相邻两块的完成间隔计
+    pub fn record_chunk(&mut self, bytes: usize) {
+        let now = Instant::now();
+        self.latencies.push(now - self.last);
+        self.last = now;
+        self.bytes += bytes as u64;
+        if self.latencies.len().is_multiple_of(SAMPLE_INTERVAL) {
+            self.refresh();
+        }
+    }
+
+    fn refresh(&mut self) {
+        let Some(pid) = self.pid else { return };
+        self.system.refresh_processes(ProcessesToUpdate::Some(&[pid]), false);
+        if let Some(process) = self.system.process(pid) {
+            self.cpu_samples.push(process.cpu_usage());
+            self.cpu_time = process.accumulated_cpu_time();
+            self.rss_peak = self.rss_peak.max(process.memory());
+        }
+    }
+
+    pub fn finish(mut self, operation: &str, cipher: &str, chunk_size: u32, threads: usize) -> Record {
+        let elapsed = self.start.elapsed();
+        self.refresh();
+        self.latencies.sort_unstable();
+        // 平均值由累计 CPU 时间求得，不受采样次数影响
+        let cpu_ms = self.cpu_time.saturating_sub(self.cpu_time_start) as f64;
+        let cpu_avg = (cpu_ms / elapsed.as_secs_f64().max(1e-9) / 10.0) as f32;
+        Record {
+            operation: operation.to_string(),
+            cipher: cipher.to_string(),
+            chunk_size,
+            threads,
+            bytes: self.bytes,
+            chunks: self.latencies.len(),
+            elapsed,
+            latency_p50: percentile(&self.latencies, 50.0),
+            latency_p90: percentile(&self.latencies, 90.0),
+            latency_p99: percentile(&self.latencies, 99.0),
+            latency_max: self.latencies.last().copied().unwrap_or_default(),
+            rss_peak: self.rss_peak,
+            cpu_avg,
+            cpu_max: self.cpu_samples.iter().copied().fold(cpu_avg, f32::max),
+            relative: None,
+        }
+    }
+}
+
+/// 最近秩法求百分位数，`sorted` 须已升序排列
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// 一次加密或解密的汇总结果
+pub struct Record {
+    pub operation: String,
+    pub cipher: String,
+    pub chunk_size: u32,
+    pub threads: usize,
+    pub bytes: u64,
+    pub chunks: usize,
+    pub elapsed: Duration,
+    pub latency_p50: Duration,
+    pub latency_p90: Duration,
+    pub latency_p99: Duration,
+    pub latency_max: Duration,
+    /// 常驻内存峰值（字节）
+    pub rss_peak: u64,
+    /// 进程 CPU 使用率（%），多核并行时可超过 100
+    pub cpu_avg: f32,
+    pub cpu_max: f32,
+    /// 相对同组最快一项的吞吐量，仅对比基准填写
+    pub relative: Option<f64>,
+}
+
+impl Record {
+    /// 吞吐量（MB/s，1 MB = 10^6 字节）
+    pub fn throughput(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return 0.0;
+        }
+        self.bytes as f64 / 1e6 / seconds
+    }
+}
+
+/// 运行环境信息，用于跨机器比较
+pub struct Machine {
+    pub host: String,
+    pub cpu: String,
+    pub cores: usize,
+    pub os: String,
+}
+
+impl Machine {
+    pub fn detect() -> Self {
+        let mut system = System::new();
+        system.refresh_cpu_all();
+        Machine {
+            host: System::host_name().unwrap_or_default(),
+            cpu: system.cpus().first().map(|cpu| cpu.brand().trim().to_string()).unwrap_or_default(),
+            cores: system.cpus().len(),
+            os: System::long_os_version().unwrap_or_default(),
+        }
+    }
+}
+
+/// 报告格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReportFormat::Text => "text",
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+        })
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            other => Err(invalid_input(format!("未知的报告格式: {}（可选 text、json、csv）", other))),
+        }
+    }
+}
+
+/// 输出报告：`path` 为空时写到标准输出；CSV 写入已有的非空文件时追加数据行而不重复表头
+pub fn write_report(path: Option<&Path>, format: ReportFormat, machine: &Machine, records: &[Record]) -> io::Result<()> {
+    let mut text = String::new();
+    match format {
+        ReportFormat::Text => render_text(&mut text, machine, records),
+        ReportFormat::Json => render_json(&mut text, machine, records),
+        ReportFormat::Csv => {
+            let append = path.is_some_and(|p| fs::metadata(p).is_ok_and(|m| m.len() > 0));
+            render_csv(&mut text, machine, records, !append);
+            if let Some(path) = path.filter(|_| append) {
+                let mut file = fs::OpenOptions::new().append(true).open(path)?;
+                return file.write_all(text.as_bytes());
+            }
+        }
+    }
+    match path {
+        Some(path) => fs::write(path, text),
+        None => io::stdout().lock().write_all(text.as_bytes()),
+    }
+}
+
+fn micros(d: Duration) -> f64 {
+    d.as_secs_f64() * 1e6
+}
+
+fn render_text(out: &mut String, machine: &Machine, records: &[Record]) {
+    use fmt::Write;
+    let _ = writeln!(out, "主机: {}，CPU: {}（{} 核），系统: {}", machine.host, machine.cpu, machine.cores, machine.os);
+    for r in records {
+        let _ = writeln!(out, "{} {}（分块 {} 字节，{} 线程）:", r.cipher, r.operation, r.chunk_size, r.threads);
+        let _ = write!(
+            out,
+            "  数据量: {} 字节，{} 块，耗时 {:.3} s，吞吐量 {:.2} MB/s",
+            r.bytes,
+            r.chunks,
+            r.elapsed.as_secs_f64(),
+            r.throughput()
+        );
+        let _ = match r.relative {
+            Some(relative) => writeln!(out, "（相对最快: {:.2}）", relative),
+            None => writeln!(out),
+        };
+        let _ = writeln!(
+            out,
+            "  分块延迟 - p50: {:.1} µs, p90: {:.1} µs, p99: {:.1} µs, 最大: {:.1} µs",
+            micros(r.latency_p50),
+            micros(r.latency_p90),
+            micros(r.latency_p99),
+            micros(r.latency_max)
+        );
+        let _ = writeln!(
+            out,
+            "  CPU 使用率 - 平均: {:.2}%, 最大: {:.2}%；RSS 峰值: {} KB",
+            r.cpu_avg,
+            r.cpu_max,
+            r.rss_peak / 1024
+        );
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn render_json(out: &mut String, machine: &Machine, records: &[Record]) {
+    use fmt::Write;
+    let _ = writeln!(out, "{{");
+    let _ = writeln!(out, "  \"timestamp\": {},", json_string(&chrono::Local::now().to_rfc3339()));
+    let _ = writeln!(
+        out,
+        "  \"machine\": {{\"host\": {}, \"cpu\": {}, \"cores\": {}, \"os\": {}}},",
+        json_string(&machine.host),
+        json_string(&machine.cpu),
+        machine.cores,
+        json_string(&machine.os)
+    );
+    let _ = writeln!(out, "  \"results\": [");
+    for (i, r) in records.iter().enumerate() {
+        let _ = write!(
+            out,
+            "    {{\"operation\": {}, \"cipher\": {}, \"chunk_size\": {}, \"threads\": {}, \"bytes\": {}, \
+             \"chunks\": {}, \"seconds\": {:.6}, \"throughput_mb_s\": {:.3}, \
+             \"relative_throughput\": {}, \"latency_us\": {{\"p50\": {:.3}, \
+             \"p90\": {:.3}, \"p99\": {:.3}, \"max\": {:.3}}}, \"rss_peak_bytes\": {}, \"cpu_avg_percent\": {:.2}, \
+             \"cpu_max_percent\": {:.2}}}",
+            json_string(&r.operation),
+            json_string(&r.cipher),
+            r.chunk_size,
+            r.threads,
+            r.bytes,
+            r.chunks,
+            r.elapsed.as_secs_f64(),
+            r.throughput(),
+            r.relative.map_or("null".to_string(), |v| format!("{:.4}", v)),
+            micros(r.latency_p50),
+            micros(r.latency_p90),
+            micros(r.latency_p99),
+            micros(r.latency_max),
+            r.rss_peak,
+            r.cpu_avg,
+            r.cpu_max
+        );
+        let _ = writeln!(out, "{}", if i + 1 < records.len() { "," } else { "" });
+    }
+    let _ = writeln!(out, "  ]");
+    let _ = writeln!(out, "}}");
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn render_csv(out: &mut String, machine: &Machine, records: &[Record], header: bool) {
+    use fmt::Write;
+    if header {
+        let _ = writeln!(
+            out,
+            "timestamp,host,cpu,cores,operation,cipher,chunk_size,threads,bytes,chunks,seconds,throughput_mb_s,\
+             relative_throughput,latency_p50_us,latency_p90_us,latency_p99_us,latency_max_us,rss_peak_bytes,cpu_avg_percent,cpu_max_percent"
+        );
+    }
+    let timestamp = chrono::Local::now().to_rfc3339();
+    for r in records {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{:.6},{:.3},{},{:.3},{:.3},{:.3},{:.3},{},{:.2},{:.2}",
+            timestamp,
+            csv_field(&machine.host),
+            csv_field(&machine.cpu),
+            machine.cores,
+            r.operation,
+            csv_field(&r.cipher),
+            r.chunk_size,
+            r.threads,
+            r.bytes,
+            r.chunks,
+            r.elapsed.as_secs_f64(),
+            r.throughput(),
+            r.relative.map_or(String::new(), |v| format!("{:.4}", v)),
+            micros(r.latency_p50),
+            micros(r.latency_p90),
+            micros(r.latency_p99),
+            micros(r.latency_max),
+            r.rss_peak,
+            r.cpu_avg,
+            r.cpu_max
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn record(operation: &str, cipher: &str, relative: Option<f64>) -> Record {
+        Record {
+            operation: operation.to_string(),
+            cipher: cipher.to_string(),
+            chunk_size: 4096,
+            threads: 2,
+            bytes: 2_000_000,
+            chunks: 489,
+            elapsed: Duration::from_secs(2),
+            latency_p50: Duration::from_micros(10),
+            latency_p90: Duration::from_micros(20),
+            latency_p99: Duration::from_micros(30),
+            latency_max: Duration::from_micros(40),
+            rss_peak: 8192,
+            cpu_avg: 150.0,
+            cpu_max: 199.5,
+            relative,
+        }
+    }
+
+    fn machine() -> Machine {
+        Machine {
+            host: "host \"a\"".to_string(),
+            cpu: "Vendor CPU, 8 cores".to_string(),
+            cores: 8,
+            os: "Linux\n6.1".to_string(),
+        }
+    }
+
+    /// 按 CSV 规则拆分一行，引号内的逗号不分隔字段
+    fn csv_fields(line: &str) -> Vec<String> {
+        let mut fields = vec![String::new()];
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    fields.last_mut().unwrap().push('"');
+                }
+                '"' => quoted = !quoted,
+                ',' if !quoted => fields.push(String::new()),
+                c => fields.last_mut().unwrap().push(c),
+            }
+        }
+        fields
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let sorted: Vec<Duration> = (1..=10).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&sorted, 50.0), Duration::from_millis(5));
+        assert_eq!(percentile(&sorted, 90.0), Duration::from_millis(9));
+        assert_eq!(percentile(&sorted, 91.0), Duration::from_millis(10));
+        assert_eq!(percentile(&sorted, 100.0), Duration::from_millis(10));
+        assert_eq!(percentile(&sorted, 0.0), Duration::from_millis(1));
+        assert_eq!(percentile(&sorted[..1], 99.0), Duration::from_millis(1));
+        assert_eq!(percentile(&[], 50.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn json_string_escapes_quotes_backslashes_and_control_characters() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(json_string("line\n\ttab\u{1}"), "\"line\\u000a\\u0009tab\\u0001\"");
+        assert_eq!(json_string("中文 µs"), "\"中文 µs\"");
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("aes-256-gcm"), "aes-256-gcm");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn render_json_lists_every_record() {
+        let mut out = String::new();
+        render_json(
+            &mut out,
+            &machine(),
+            &[
+                record("encrypt", "aes-256-gcm", Some(1.0)),
+                record("decrypt", "chacha20-poly1305", None),
+            ],
+        );
+        assert!(out.starts_with("{\n  \"timestamp\": \""));
+        assert!(out.ends_with("  ]\n}\n"));
+        assert!(out.contains(
+            "\"machine\": {\"host\": \"host \\\"a\\\"\", \"cpu\": \"Vendor CPU, 8 cores\", \"cores\": 8, \"os\": \"Linux\\u000a6.1\"}"
+        ));
+        let results: Vec<&str> = out.lines().filter(|line| line.starts_with("    {")).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].ends_with("},"));
+        assert!(results[1].ends_with('}'));
+        assert!(results[0].contains("\"operation\": \"encrypt\", \"cipher\": \"aes-256-gcm\", \"chunk_size\": 4096"));
+        assert!(results[0].contains("\"seconds\": 2.000000, \"throughput_mb_s\": 1.000"));
+        assert!(results[0].contains("\"relative_throughput\": 1.0000"));
+        assert!(
+            results[0].contains("\"latency_us\": {\"p50\": 10.000, \"p90\": 20.000, \"p99\": 30.000, \"max\": 40.000}")
+        );
+        assert!(
+            results[0].contains("\"rss_peak_bytes\": 8192, \"cpu_avg_percent\": 150.00, \"cpu_max_percent\": 199.50")
+        );
+        assert!(results[1].contains("\"relative_throughput\": null"));
+    }
+
+    #[test]
+    fn render_csv_writes_one_row_per_record() {
+        let machine = machine();
+        let records = [
+            record("encrypt", "aes-256-gcm", Some(0.5)),
+            record("decrypt", "aes,odd", None),
+        ];
+        let mut out = String::new();
+        render_csv(&mut out, &machine, &records, true);
+        // 系统名称（含换行）不写入 CSV，每条记录正好一行
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let header = csv_fields(lines[0]);
+        assert_eq!(header.len(), 20);
+        assert_eq!(header[..3], ["timestamp", "host", "cpu"]);
+        assert_eq!(header[19], "cpu_max_percent");
+
+        let row = csv_fields(lines[1]);
+        assert_eq!(row.len(), header.len());
+        assert_eq!(
+            row[1..10],
+            [
+                "host \"a\"",
+                "Vendor CPU, 8 cores",
+                "8",
+                "encrypt",
+                "aes-256-gcm",
+                "4096",
+                "2",
+                "2000000",
+                "489"
+            ]
+        );
+        assert_eq!(
+            row[10..],
+            [
+                "2.000000", "1.000", "0.5000", "10.000", "20.000", "30.000", "40.000", "8192", "150.00", "199.50"
+            ]
+        );
+        let row = csv_fields(lines[2]);
+        assert_eq!(row[5], "aes,odd");
+        assert_eq!(row[12], "");
+
+        let mut rows_only = String::new();
+        render_csv(&mut rows_only, &machine, &records, false);
+        assert_eq!(rows_only.lines().count(), 2);
+        assert!(!rows_only.contains("timestamp,host"));
+    }
+
+    #[test]
+    fn write_report_appends_csv_rows_without_repeating_the_header() {
+        struct TempPath(PathBuf);
+        impl Drop for TempPath {
+            fn drop(&mut self) {
+                let _ = fs::remove_file(&self.0);
+            }
+        }
+        let file = TempPath(std::env::temp_dir().join(format!("aes-metrics-{}.csv", std::process::id())));
+        let machine = machine();
+        // 空文件视为新文件，写入表头
+        fs::write(&file.0, "").unwrap();
+        write_report(
+            Some(&file.0),
+            ReportFormat::Csv,
+            &machine,
+            &[record("encrypt", "aes-256-gcm", None)],
+        )
+        .unwrap();
+        write_report(
+            Some(&file.0),
+            ReportFormat::Csv,
+            &machine,
+            &[
+                record("encrypt", "aes-256-ctr", None),
+                record("decrypt", "aes-256-ctr", None),
+            ],
+        )
+        .unwrap();
+
+        let written = fs::read_to_string(&file.0).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("timestamp,host,cpu,"));
+        let ciphers: Vec<String> = lines[1..].iter().map(|line| csv_fields(line)[5].clone()).collect();
+        assert_eq!(ciphers, ["aes-256-gcm", "aes-256-ctr", "aes-256-ctr"]);
+
+        // 其他格式覆盖已有文件
+        write_report(
+            Some(&file.0),
+            ReportFormat::Json,
+            &machine,
+            &[record("encrypt", "aes-256-gcm", None)],
+        )
+        .unwrap();
+        assert!(fs::read_to_string(&file.0).unwrap().starts_with('{'));
+    }
+}
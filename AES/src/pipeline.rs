@@ -0,0 +1,221 @@
+//! 读取 → 工作线程池 → 按序写出的分块流水线
+//!
+//! 每块的加解密只依赖块序号和末块标志，因此可以交给任意线程处理；
+//! 写出端按序号重排后依次写入，输出与线程数无关。
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+
+/// 流水线中的一个分块
+pub struct Chunk {
+    pub index: u32,
+    pub last: bool,
+    pub data: Vec<u8>,
+}
+
+/// 解析命令行中的线程数，0 表示使用全部可用 CPU 核心
+pub fn resolve_threads(requested: usize) -> usize {
+    if requested > 0 {
+        return requested;
+    }
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// 依次取出 `next_chunk` 产生的分块，经 `process` 处理后按序写入 `writer`
+///
+/// `threads <= 1` 时在当前线程串行处理。读取与写出始终在当前线程进行，
+/// 因此 `next_chunk` 与 `writer` 不必是 `Send`。同时在途的分块不超过线程数的两倍。
+/// 每写出一块调用一次 `on_chunk(输入块, 输出)`，遇到第一个错误即停止。
+pub fn run<W, N, P, C>(
+    mut next_chunk: N,
+    writer: &mut W,
+    threads: usize,
+    process: P,
+    mut on_chunk: C,
+) -> io::Result<()>
+where
+    W: Write + ?Sized,
+    N: FnMut() -> io::Result<Option<Chunk>>,
+    P: Fn(&Chunk) -> io::Result<Vec<u8>> + Sync,
+    C: FnMut(&Chunk, &[u8]),
+{
+    if threads <= 1 {
+        while let Some(chunk) = next_chunk()? {
+            let output = process(&chunk)?;
+            writer.write_all(&output)?;
+            on_chunk(&chunk, &output);
+        }
+        return Ok(());
+    }
+
+    // 分块按读取顺序编号，写出端据此重排，与 `Chunk::index` 的起点无关
+    let (job_tx, job_rx) = mpsc::channel::<(u64, Chunk)>();
+    let job_rx = Mutex::new(job_rx);
+    let process = &process;
+
+    thread::scope(|scope| {
+        let (result_tx, result_rx) = mpsc::channel::<(u64, Chunk, io::Result<Vec<u8>>)>();
+        for _ in 0..threads {
+            let job_rx = &job_rx;
+            let result_tx = result_tx.clone();
+            scope.spawn(move || {
+                loop {
+                    // 只在取任务时持锁，处理过程不阻塞其他线程
+                    let job = job_rx.lock().unwrap().recv();
+                    let Ok((seq, chunk)) = job else { break };
+                    let output = process(&chunk);
+                    if result_tx.send((seq, chunk, output)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(result_tx);
+
+        let max_in_flight = threads * 2;
+        let result = (|| -> io::Result<()> {
+            let mut pending = BTreeMap::new();
+            let mut next_read = 0u64;
+            let mut next_write = 0u64;
+            let mut in_flight = 0usize;
+            let mut reading = true;
+            loop {
+                while reading && in_flight < max_in_flight {
+                    match next_chunk()? {
+                        Some(chunk) => {
+                            job_tx
+                                .send((next_read, chunk))
+                                .map_err(|_| io::Error::other("工作线程异常退出"))?;
+                            next_read += 1;
+                            in_flight += 1;
+                        }
+                        None => reading = false,
+                    }
+                }
+                if in_flight == 0 {
+                    return Ok(());
+                }
+
+                let (seq, chunk, output) = result_rx
+                    .recv()
+                    .map_err(|_| io::Error::other("工作线程异常退出"))?;
+                in_flight -= 1;
+                pending.insert(seq, (chunk, output));
+                while let Some((chunk, output)) = pending.remove(&next_write) {
+                    let output = output?;
+                    writer.write_all(&output)?;
+                    on_chunk(&chunk, &output);
+                    next_write += 1;
+                }
+            }
+        })();
+
+        // 关闭任务通道让工作线程退出，scope 结束时等待它们完成
+        drop(job_tx);
+        result
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::format::Algorithm;
+    use crate::stream::test::{decrypt, encrypt, raw_header};
+    use std::time::Duration;
+
+    const CHUNKS: u32 = 12;
+
+    fn chunks() -> impl FnMut() -> io::Result<Option<Chunk>> {
+        let mut index = 0;
+        move || {
+            if index == CHUNKS {
+                return Ok(None);
+            }
+            let chunk = Chunk {
+                index,
+                last: index == CHUNKS - 1,
+                data: vec![index as u8; 3],
+            };
+            index += 1;
+            Ok(Some(chunk))
+        }
+    }
+
+    /// 序号越小处理得越慢，让后读入的块先完成
+    fn slow_first(chunk: &Chunk, finished: &Mutex<Vec<u32>>) -> io::Result<Vec<u8>> {
+        thread::sleep(Duration::from_millis(5 * (CHUNKS - chunk.index) as u64));
+        finished.lock().unwrap().push(chunk.index);
+        Ok(chunk.data.iter().map(|b| b ^ 0x5a).collect())
+    }
+
+    #[test]
+    fn out_of_order_results_are_written_in_order() {
+        let finished = Mutex::new(Vec::new());
+        let mut sequential = Vec::new();
+        run(
+            chunks(),
+            &mut sequential,
+            1,
+            |chunk| slow_first(chunk, &finished),
+            |_, _| {},
+        )
+        .unwrap();
+
+        finished.lock().unwrap().clear();
+        let mut parallel = Vec::new();
+        let mut written = Vec::new();
+        run(
+            chunks(),
+            &mut parallel,
+            4,
+            |chunk| slow_first(chunk, &finished),
+            |chunk, _| written.push(chunk.index),
+        )
+        .unwrap();
+
+        let finished = finished.into_inner().unwrap();
+        assert!(
+            finished.windows(2).any(|pair| pair[0] > pair[1]),
+            "工作线程应乱序完成: {:?}",
+            finished
+        );
+        assert_eq!(written, (0..CHUNKS).collect::<Vec<_>>());
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn stops_at_the_first_error_in_order() {
+        let mut out = Vec::new();
+        let result = run(
+            chunks(),
+            &mut out,
+            4,
+            |chunk| {
+                if chunk.index == 5 {
+                    Err(io::Error::other("第 5 块失败"))
+                } else {
+                    Ok(vec![chunk.index as u8])
+                }
+            },
+            |_, _| {},
+        );
+        assert!(result.is_err());
+        assert_eq!(out, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn parallel_stream_matches_sequential_byte_for_byte() {
+        let plaintext: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        for algorithm in [Algorithm::Aes256Gcm, Algorithm::ChaCha20Poly1305] {
+            let header = raw_header(algorithm);
+            let sequential = encrypt(header.clone(), &plaintext, 1);
+            for threads in [2, 3, 8] {
+                let parallel = encrypt(header.clone(), &plaintext, threads);
+                assert_eq!(parallel, sequential);
+                assert_eq!(decrypt(&parallel, threads).unwrap(), plaintext);
+            }
+        }
+    }
+}
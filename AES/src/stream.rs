@@ -0,0 +1,568 @@
+//! STREAM 分块认证加密
+//!
+//! 明文按头部中的分块大小切分，第 i 块使用 nonce
+//! `前缀(7) || i(u32 大端) || 末块标志(1)` 加密，头部字节作为 AAD。
+//! 最后一块总是短于分块大小（明文恰好整除时补一个空块），
+//! 因此解密端无需预读即可识别末块，截断、重排和篡改都会导致认证失败。
+//!
+//! 每条密文记录固定为 `分块大小 + 16` 字节（末块除外），第 i 块位于正文偏移
+//! `i * (分块大小 + 16)` 处，因此可以只读取覆盖某个明文区间的分块，见 [`decrypt_range`]。
+//!
+//! 头部启用摘要时，末块之后还有一条以标志 2、序号 0 的 nonce 密封的摘要尾部。
+
+use crate::format::{Algorithm, Header, TAG_LEN, invalid_data, invalid_input};
+use crate::integrity::{Hasher, TrailerReader};
+use crate::pipeline::{self, Chunk};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes128Gcm, Aes256Gcm};
+use chacha20poly1305::ChaCha20Poly1305;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+pub const NONCE_LEN: usize = 12;
+
+/// nonce 末字节：普通块为 0，末块为 1，摘要尾部为 2
+const FLAG_TRAILER: u8 = 2;
+
+// 每个文件只构造一个，变体大小差异无关紧要
+#[allow(clippy::large_enum_variant)]
+enum Inner {
+    Aes128Gcm(Aes128Gcm),
+    Aes256Gcm(Aes256Gcm),
+    ChaCha20Poly1305(ChaCha20Poly1305),
+}
+
+/// 绑定到某个文件头部的分块加解密器，每块的加解密互不依赖
+pub struct ChunkCipher {
+    inner: Inner,
+    header: Header,
+    aad: Vec<u8>,
+}
+
+impl ChunkCipher {
+    pub fn new(header: Header, key: &[u8]) -> io::Result<Self> {
+        let algorithm = header.algorithm;
+        if key.len() != algorithm.key_len() {
+            return Err(invalid_input(format!(
+                "{} 需要 {} 字节的密钥，实际为 {} 字节",
+                algorithm,
+                algorithm.key_len(),
+                key.len()
+            )));
+        }
+        let inner = match algorithm {
+            Algorithm::Aes128Gcm => Inner::Aes128Gcm(Aes128Gcm::new_from_slice(key).map_err(invalid_input)?),
+            Algorithm::Aes256Gcm => Inner::Aes256Gcm(Aes256Gcm::new_from_slice(key).map_err(invalid_input)?),
+            Algorithm::ChaCha20Poly1305 => {
+                Inner::ChaCha20Poly1305(ChaCha20Poly1305::new_from_slice(key).map_err(invalid_input)?)
+            }
+        };
+        let aad = header.to_bytes();
+        Ok(ChunkCipher { inner, header, aad })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    fn nonce(&self, index: u32, flag: u8) -> [u8; NONCE_LEN] {
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[..7].copy_from_slice(&self.header.nonce_prefix);
+        nonce[7..11].copy_from_slice(&index.to_be_bytes());
+        nonce[11] = flag;
+        nonce
+    }
+
+    /// 加密第 `index` 块，返回 `密文 || 标签`
+    pub fn seal(&self, index: u32, last: bool, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+        self.seal_with(self.nonce(index, last as u8), plaintext)
+    }
+
+    /// 解密并认证第 `index` 块
+    pub fn open(&self, index: u32, last: bool, ciphertext: &[u8]) -> io::Result<Vec<u8>> {
+        self.open_with(self.nonce(index, last as u8), ciphertext)
+            .map_err(|_| invalid_data(format!("第 {} 块认证失败：密文被篡改、截断或密钥错误", index)))
+    }
+
+    /// 密封明文摘要尾部
+    pub fn seal_trailer(&self, digest: &[u8]) -> io::Result<Vec<u8>> {
+        self.seal_with(self.nonce(0, FLAG_TRAILER), digest)
+    }
+
+    /// 解开明文摘要尾部
+    pub fn open_trailer(&self, trailer: &[u8]) -> io::Result<Vec<u8>> {
+        self.open_with(self.nonce(0, FLAG_TRAILER), trailer)
+            .map_err(|_| invalid_data("摘要尾部认证失败：密文被篡改、截断或密钥错误"))
+    }
+
+    fn seal_with(&self, nonce: [u8; NONCE_LEN], plaintext: &[u8]) -> io::Result<Vec<u8>> {
+        let payload = Payload {
+            msg: plaintext,
+            aad: &self.aad,
+        };
+        let result = match &self.inner {
+            Inner::Aes128Gcm(c) => c.encrypt(&nonce.into(), payload),
+            Inner::Aes256Gcm(c) => c.encrypt(&nonce.into(), payload),
+            Inner::ChaCha20Poly1305(c) => c.encrypt(&nonce.into(), payload),
+        };
+        result.map_err(|_| invalid_data("分块加密失败"))
+    }
+
+    fn open_with(&self, nonce: [u8; NONCE_LEN], ciphertext: &[u8]) -> Result<Vec<u8>, aes_gcm::aead::Error> {
+        let payload = Payload {
+            msg: ciphertext,
+            aad: &self.aad,
+        };
+        match &self.inner {
+            Inner::Aes128Gcm(c) => c.decrypt(&nonce.into(), payload),
+            Inner::Aes256Gcm(c) => c.decrypt(&nonce.into(), payload),
+            Inner::ChaCha20Poly1305(c) => c.decrypt(&nonce.into(), payload),
+        }
+    }
+}
+
+/// 尽量读满缓冲区，只有到达 EOF 时才返回小于缓冲区长度的值
+pub fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn next_index(index: u32) -> io::Result<u32> {
+    index
+        .checked_add(1)
+        .ok_or_else(|| invalid_input("分块数量超出计数器范围"))
+}
+
+/// 一次加密或解密的结果
+pub struct StreamSummary {
+    /// 明文总字节数
+    pub bytes: u64,
+    /// 头部启用摘要时为明文摘要
+    pub digest: Option<Vec<u8>>,
+}
+
+/// 写出头部并逐块加密，每处理完一块调用一次 `on_chunk(明文长度)`
+///
+/// `threads` 大于 1 时由工作线程并行加密，输出与串行完全一致。
+/// 头部启用摘要时，末块之后追加密封的明文摘要。
+pub fn encrypt_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    cipher: &ChunkCipher,
+    threads: usize,
+    mut on_chunk: impl FnMut(usize),
+) -> io::Result<StreamSummary> {
+    cipher.header().write_to(writer)?;
+
+    let chunk_size = cipher.header().chunk_size as usize;
+    let mut hasher = cipher.header().digest.map(Hasher::new);
+    let mut index = 0u32;
+    let mut done = false;
+    let mut total = 0u64;
+
+    // 读取顺序即明文顺序，摘要在读取端计算
+    let next_chunk = || -> io::Result<Option<Chunk>> {
+        if done {
+            return Ok(None);
+        }
+        let mut data = vec![0u8; chunk_size];
+        let bytes_read = read_full(reader, &mut data)?;
+        data.truncate(bytes_read);
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(&data);
+        }
+        let last = bytes_read < chunk_size;
+        let chunk = Chunk { index, last, data };
+        if last {
+            done = true;
+        } else {
+            index = next_index(index)?;
+        }
+        Ok(Some(chunk))
+    };
+
+    pipeline::run(
+        next_chunk,
+        writer,
+        threads,
+        |chunk| cipher.seal(chunk.index, chunk.last, &chunk.data),
+        |chunk, _| {
+            total += chunk.data.len() as u64;
+            on_chunk(chunk.data.len());
+        },
+    )?;
+
+    let digest = hasher.map(Hasher::finalize);
+    if let Some(digest) = &digest {
+        writer.write_all(&cipher.seal_trailer(digest)?)?;
+    }
+    writer.flush()?;
+    Ok(StreamSummary { bytes: total, digest })
+}
+
+/// 逐块解密并认证，每处理完一块调用一次 `on_chunk(明文长度)`
+///
+/// 头部需已由调用方读出并用于构造 `cipher`。`threads` 大于 1 时并行解密，
+/// 明文仍按顺序写出，认证失败的块之后不再输出。头部启用摘要时，
+/// 写完全部明文后再核对摘要尾部，不一致则返回错误。
+pub fn decrypt_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    cipher: &ChunkCipher,
+    threads: usize,
+    mut on_chunk: impl FnMut(usize),
+) -> io::Result<StreamSummary> {
+    let record_size = cipher.header().chunk_size as usize + TAG_LEN;
+    let mut reader = TrailerReader::new(reader, cipher.header().trailer_len());
+    let mut hasher = cipher.header().digest.map(Hasher::new);
+    let mut index = 0u32;
+    let mut done = false;
+    let mut total = 0u64;
+
+    let next_chunk = || -> io::Result<Option<Chunk>> {
+        if done {
+            return Ok(None);
+        }
+        let mut data = vec![0u8; record_size];
+        let bytes_read = read_full(&mut reader, &mut data)?;
+        data.truncate(bytes_read);
+        let last = bytes_read < record_size;
+        if last && bytes_read < TAG_LEN {
+            return Err(invalid_data("密文被截断：缺少末块"));
+        }
+        let chunk = Chunk { index, last, data };
+        if last {
+            done = true;
+        } else {
+            index = next_index(index)?;
+        }
+        Ok(Some(chunk))
+    };
+
+    pipeline::run(
+        next_chunk,
+        writer,
+        threads,
+        |chunk| cipher.open(chunk.index, chunk.last, &chunk.data),
+        |_, plaintext| {
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(plaintext);
+            }
+            total += plaintext.len() as u64;
+            on_chunk(plaintext.len());
+        },
+    )?;
+    writer.flush()?;
+
+    let digest = match hasher {
+        Some(hasher) => {
+            let expected = cipher.open_trailer(reader.trailer()?)?;
+            let actual = hasher.finalize();
+            if actual != expected {
+                return Err(invalid_data("明文摘要与加密时记录的不一致"));
+            }
+            Some(actual)
+        }
+        None => None,
+    };
+    Ok(StreamSummary { bytes: total, digest })
+}
+
+/// 解密明文区间 `[offset, offset + len)`，只读取并认证覆盖该区间的分块
+///
+/// `reader` 需位于头部之后。区间超出明文末尾时截断到末尾，返回写出的字节数。
+/// 区间之外的分块不会被认证，因此无法发现这些分块上的篡改；摘要尾部也不会核对。
+pub fn decrypt_range<R: Read + Seek, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    cipher: &ChunkCipher,
+    offset: u64,
+    len: u64,
+    threads: usize,
+) -> io::Result<u64> {
+    let chunk_size = cipher.header().chunk_size as u64;
+    let record_size = chunk_size + TAG_LEN as u64;
+
+    // 由正文长度推出末块位置与明文总长
+    let body_start = reader.stream_position()?;
+    let body_len = (reader.seek(SeekFrom::End(0))? - body_start)
+        .checked_sub(cipher.header().trailer_len() as u64)
+        .ok_or_else(|| invalid_data("密文被截断：缺少摘要尾部"))?;
+    let last_index = body_len / record_size;
+    let last_len = body_len % record_size;
+    if last_len < TAG_LEN as u64 {
+        return Err(invalid_data("密文被截断：缺少末块"));
+    }
+    let last_index = u32::try_from(last_index).map_err(|_| invalid_data("分块数量超出计数器范围"))?;
+    let plaintext_len = last_index as u64 * chunk_size + last_len - TAG_LEN as u64;
+
+    let end = offset.saturating_add(len).min(plaintext_len);
+    if offset >= end {
+        return Ok(0);
+    }
+    let first = (offset / chunk_size) as u32;
+    let final_index = ((end - 1) / chunk_size) as u32;
+    reader.seek(SeekFrom::Start(body_start + first as u64 * record_size))?;
+
+    let mut index = first;
+    let next_chunk = || -> io::Result<Option<Chunk>> {
+        if index > final_index {
+            return Ok(None);
+        }
+        let last = index == last_index;
+        let size = if last { last_len } else { record_size };
+        let mut data = vec![0u8; size as usize];
+        reader.read_exact(&mut data)?;
+        let chunk = Chunk { index, last, data };
+        index += 1;
+        Ok(Some(chunk))
+    };
+
+    let mut total = 0u64;
+    pipeline::run(
+        next_chunk,
+        writer,
+        threads,
+        |chunk| {
+            let plaintext = cipher.open(chunk.index, chunk.last, &chunk.data)?;
+            // 只保留与请求区间相交的部分
+            let chunk_start = chunk.index as u64 * chunk_size;
+            let from = offset.saturating_sub(chunk_start) as usize;
+            let to = (end - chunk_start).min(plaintext.len() as u64) as usize;
+            Ok(plaintext[from..to].to_vec())
+        },
+        |_, output| total += output.len() as u64,
+    )?;
+
+    writer.flush()?;
+    Ok(total)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::format::KeyMode;
+
+    pub const KEY: [u8; 32] = [7; 32];
+    pub const CHUNK_SIZE: u32 = 16;
+
+    pub fn cipher(header: Header) -> ChunkCipher {
+        let key_len = header.algorithm.key_len();
+        ChunkCipher::new(header, &KEY[..key_len]).unwrap()
+    }
+
+    /// 按 `header` 加密，返回头部与正文
+    pub fn encrypt(header: Header, plaintext: &[u8], threads: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        encrypt_stream(&mut &plaintext[..], &mut out, &cipher(header), threads, |_| {}).unwrap();
+        out
+    }
+
+    pub fn decrypt(bytes: &[u8], threads: usize) -> io::Result<Vec<u8>> {
+        let mut reader = bytes;
+        let header = Header::read_from(&mut reader)?;
+        let mut out = Vec::new();
+        decrypt_stream(&mut reader, &mut out, &cipher(header), threads, |_| {})?;
+        Ok(out)
+    }
+
+    pub fn raw_header(algorithm: Algorithm) -> Header {
+        Header::new(algorithm, CHUNK_SIZE, KeyMode::Raw).unwrap()
+    }
+
+    /// 头部长度与各条密文记录
+    fn split_records(bytes: &[u8]) -> (usize, Vec<Vec<u8>>) {
+        let header_len = Header::read_from(&mut &bytes[..]).unwrap().to_bytes().len();
+        let records = bytes[header_len..]
+            .chunks(CHUNK_SIZE as usize + TAG_LEN)
+            .map(<[u8]>::to_vec)
+            .collect();
+        (header_len, records)
+    }
+
+    fn reassemble(bytes: &[u8], records: &[Vec<u8>]) -> Vec<u8> {
+        let (header_len, _) = split_records(bytes);
+        [bytes[..header_len].to_vec(), records.concat()].concat()
+    }
+
+    #[test]
+    fn roundtrip() {
+        for algorithm in [Algorithm::Aes128Gcm, Algorithm::Aes256Gcm, Algorithm::ChaCha20Poly1305] {
+            // 空明文、短于一块、恰好整除和带余数的情况
+            for len in [0, 5, 16, 48, 50] {
+                let plaintext: Vec<u8> = (0..len as u8).collect();
+                let bytes = encrypt(raw_header(algorithm), &plaintext, 1);
+                assert_eq!(decrypt(&bytes, 1).unwrap(), plaintext);
+            }
+        }
+    }
+
+    #[test]
+    fn exact_multiple_ends_with_empty_last_chunk() {
+        let bytes = encrypt(raw_header(Algorithm::Aes256Gcm), &[1; 32], 1);
+        let (_, records) = split_records(&bytes);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].len(), TAG_LEN);
+    }
+
+    #[test]
+    fn nonce_layout() {
+        let cipher = cipher(raw_header(Algorithm::Aes256Gcm));
+        let prefix = cipher.header().nonce_prefix;
+        let nonce = cipher.nonce(0x0102_0304, 1);
+        assert_eq!(nonce[..7], prefix);
+        assert_eq!(nonce[7..11], [1, 2, 3, 4]);
+        assert_eq!(nonce[11], 1);
+        assert_eq!(cipher.nonce(5, 0)[11], 0);
+        assert_eq!(cipher.nonce(0, FLAG_TRAILER)[11], FLAG_TRAILER);
+    }
+
+    #[test]
+    fn chunks_only_open_at_their_own_index_and_flag() {
+        let cipher = cipher(raw_header(Algorithm::Aes256Gcm));
+        let sealed = cipher.seal(3, false, b"chunk").unwrap();
+        assert_eq!(cipher.open(3, false, &sealed).unwrap(), b"chunk");
+        assert!(cipher.open(4, false, &sealed).is_err());
+        assert!(cipher.open(3, true, &sealed).is_err());
+        assert!(cipher.open_trailer(&sealed).is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_chunks() {
+        let bytes = encrypt(raw_header(Algorithm::Aes256Gcm), &[9; 40], 1);
+        let (header_len, _) = split_records(&bytes);
+        for index in [header_len, header_len + 20, bytes.len() - 1] {
+            let mut tampered = bytes.clone();
+            tampered[index] ^= 1;
+            assert_eq!(decrypt(&tampered, 1).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+        // 头部是每块的 AAD
+        let mut tampered = bytes.clone();
+        tampered[12] ^= 1;
+        assert!(decrypt(&tampered, 1).is_err());
+    }
+
+    #[test]
+    fn rejects_reordered_chunks() {
+        let bytes = encrypt(raw_header(Algorithm::Aes256Gcm), &[1, 2, 3].repeat(16), 1);
+        let (_, mut records) = split_records(&bytes);
+        records.swap(0, 1);
+        assert!(decrypt(&reassemble(&bytes, &records), 1).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_ciphertext() {
+        let bytes = encrypt(raw_header(Algorithm::Aes256Gcm), &[5; 40], 1);
+        for cut in [1, TAG_LEN, TAG_LEN + 1] {
+            assert!(decrypt(&bytes[..bytes.len() - cut], 1).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_missing_last_chunk() {
+        // 明文带余数：去掉末块后，最后一个完整块会被当作末块，标志不符
+        let bytes = encrypt(raw_header(Algorithm::Aes256Gcm), &[5; 40], 1);
+        let (_, records) = split_records(&bytes);
+        assert!(decrypt(&reassemble(&bytes, &records[..records.len() - 1]), 1).is_err());
+
+        // 明文恰好整除：去掉空末块后密文正好停在记录边界上
+        let bytes = encrypt(raw_header(Algorithm::Aes256Gcm), &[5; 32], 1);
+        let (_, records) = split_records(&bytes);
+        let err = decrypt(&reassemble(&bytes, &records[..records.len() - 1]), 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    /// 解密 `bytes` 中明文区间 `[offset, offset + len)`
+    fn decrypt_range_of(bytes: &[u8], offset: u64, len: u64, threads: usize) -> io::Result<Vec<u8>> {
+        let mut reader = io::Cursor::new(bytes);
+        let header = Header::read_from(&mut reader)?;
+        let mut out = Vec::new();
+        let written = decrypt_range(&mut reader, &mut out, &cipher(header), offset, len, threads)?;
+        assert_eq!(written, out.len() as u64);
+        Ok(out)
+    }
+
+    #[test]
+    fn range_spanning_chunk_boundaries() {
+        let plaintext: Vec<u8> = (0..100).collect();
+        let bytes = encrypt(raw_header(Algorithm::Aes256Gcm), &plaintext, 1);
+        // 跨一个和多个边界，以及恰好从边界开始或结束
+        for (offset, len) in [(10, 10), (15, 2), (5, 60), (16, 16), (0, 32), (31, 1)] {
+            let range = offset as usize..(offset + len) as usize;
+            assert_eq!(
+                decrypt_range_of(&bytes, offset, len, 1).unwrap(),
+                plaintext[range.clone()]
+            );
+            assert_eq!(decrypt_range_of(&bytes, offset, len, 3).unwrap(), plaintext[range]);
+        }
+    }
+
+    #[test]
+    fn range_in_final_chunk() {
+        // 100 = 6 * 16 + 4，末块只有 4 字节
+        let plaintext: Vec<u8> = (0..100).collect();
+        let bytes = encrypt(raw_header(Algorithm::ChaCha20Poly1305), &plaintext, 1);
+        assert_eq!(decrypt_range_of(&bytes, 97, 2, 1).unwrap(), plaintext[97..99]);
+        assert_eq!(decrypt_range_of(&bytes, 90, 10, 1).unwrap(), plaintext[90..]);
+
+        // 恰好整除时末块为空，最后的明文在倒数第二块
+        let bytes = encrypt(raw_header(Algorithm::ChaCha20Poly1305), &plaintext[..96], 1);
+        assert_eq!(decrypt_range_of(&bytes, 95, 1, 1).unwrap(), plaintext[95..96]);
+    }
+
+    #[test]
+    fn range_matches_every_slice() {
+        let plaintext: Vec<u8> = (0..40).collect();
+        let bytes = encrypt(raw_header(Algorithm::Aes128Gcm), &plaintext, 1);
+        for offset in 0..=40u64 {
+            for len in 0..=(40 - offset) {
+                let expected = &plaintext[offset as usize..(offset + len) as usize];
+                assert_eq!(decrypt_range_of(&bytes, offset, len, 1).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_bounds_ranges_are_clamped() {
+        let plaintext: Vec<u8> = (0..50).collect();
+        let bytes = encrypt(raw_header(Algorithm::Aes256Gcm), &plaintext, 1);
+        assert_eq!(decrypt_range_of(&bytes, 45, 100, 1).unwrap(), plaintext[45..]);
+        assert_eq!(decrypt_range_of(&bytes, 40, u64::MAX, 1).unwrap(), plaintext[40..]);
+        assert!(decrypt_range_of(&bytes, 50, 10, 1).unwrap().is_empty());
+        assert!(decrypt_range_of(&bytes, 1000, 10, 1).unwrap().is_empty());
+        assert!(decrypt_range_of(&bytes, 10, 0, 1).unwrap().is_empty());
+
+        let empty = encrypt(raw_header(Algorithm::Aes256Gcm), &[], 1);
+        assert!(decrypt_range_of(&empty, 0, 10, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn range_only_authenticates_the_chunks_it_reads() {
+        let plaintext: Vec<u8> = (0..50).collect();
+        let bytes = encrypt(raw_header(Algorithm::Aes256Gcm), &plaintext, 1);
+        let (header_len, _) = split_records(&bytes);
+        let mut tampered = bytes.clone();
+        // 篡改第 0 块
+        tampered[header_len + 3] ^= 1;
+        assert!(decrypt_range_of(&tampered, 0, 5, 1).is_err());
+        assert_eq!(decrypt_range_of(&tampered, 20, 5, 1).unwrap(), plaintext[20..25]);
+    }
+
+    #[test]
+    fn range_rejects_truncated_body() {
+        let bytes = encrypt(raw_header(Algorithm::Aes256Gcm), &[1; 32], 1);
+        // 去掉只有标签的空末块后，正文停在记录边界上
+        let truncated = &bytes[..bytes.len() - TAG_LEN];
+        assert_eq!(
+            decrypt_range_of(truncated, 0, 4, 1).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -1,23 +1,27 @@
 pub use crate::api_common::DefaultRng;
+pub use crate::api_common::MaybeSync;
 pub use crate::api_common::RecryptErr;
 use crate::api_common::Result;
+pub use crate::api_common::TransformPolicy;
 use crate::internal;
 pub use crate::internal::ByteVector;
 use crate::internal::bytedecoder::{BytesDecoder, DecodeErr};
 use crate::internal::curve;
 pub use crate::internal::ed25519::{
-    Ed25519, Ed25519Signature, Ed25519Signing, PublicSigningKey, SigningKeypair,
+    Ed25519, Ed25519Signature, Ed25519Signing, Ed25519SigningKey, PublicSigningKey, SigningKeypair,
 };
 use crate::internal::fp::fr_256::Fr256;
 use crate::internal::fp12elem::Fp12Elem;
 pub use crate::internal::hashable::Hashable;
 use crate::internal::hashable::Hashable32;
-use crate::internal::homogeneouspoint::TwistedHPoint;
+use crate::internal::homogeneouspoint::{HomogeneousPoint, TwistedHPoint};
 use crate::internal::memlock;
 use crate::internal::pairing;
 pub use crate::internal::rand_bytes::*;
 use crate::internal::schnorr::{SchnorrSign, SchnorrSigning};
-pub use crate::internal::sha256::{Sha256, Sha256Hashing};
+use crate::internal::sha256::HashDomain;
+pub use crate::internal::sha256::{DigestHash, ProtocolVersion, Sha256, Sha256Hashing};
+use crate::internal::wire::{self, WireReader, WireWriter};
 use crate::nonemptyvec::NonEmptyVec;
 use clear_on_drop::clear::Clear;
 use derivative::Derivative;
@@ -27,6 +31,8 @@ use rand;
 use rand::SeedableRng;
 use rand::rngs::adapter::ReseedingRng;
 use rand_chacha;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std;
 use std::fmt;
 
@@ -39,6 +45,8 @@ pub struct Recrypt<H, S, R> {
     pairing: internal::pairing::Pairing<Monty256>,
     curve_points: &'static internal::curve::CurvePoints<Monty256>,
     schnorr_signing: SchnorrSign<Monty256, Fr256, H>,
+    protocol_version: ProtocolVersion,
+    transform_policy: TransformPolicy,
 }
 
 impl Recrypt<Sha256, Ed25519, RandomBytes<DefaultRng>> {
@@ -46,13 +54,28 @@ impl Recrypt<Sha256, Ed25519, RandomBytes<DefaultRng>> {
     ///
     /// The RNG will periodically reseed itself from the system's best entropy source.
     pub fn new() -> Recrypt<Sha256, Ed25519, RandomBytes<DefaultRng>> {
+        Recrypt::new_with_backends(Sha256, Ed25519)
+    }
+}
+
+impl<H: Sha256Hashing + Clone, S: Ed25519Signing> Recrypt<H, S, RandomBytes<DefaultRng>> {
+    /// Construct a Recrypt with the given hashing and signing implementations and the same CSPRNG as `new()`.
+    ///
+    /// The hash can be any 32 byte hash, such as a `DigestHash` with its own domain separation tag, and the signer
+    /// can forward to an Ed25519 key held elsewhere (a remote service or an HSM). Values can only be exchanged
+    /// between Recrypts that use the same hash.
+    pub fn new_with_backends(sha_256: H, ed25519: S) -> Recrypt<H, S, RandomBytes<DefaultRng>> {
         // 1 MB
         const BYTES_BEFORE_RESEEDING: u64 = 1024 * 1024;
-        Recrypt::new_with_rand(ReseedingRng::new(
-            rand_chacha::ChaChaCore::from_entropy(),
-            BYTES_BEFORE_RESEEDING,
-            rand::rngs::OsRng,
-        ))
+        Recrypt::new_with_backends_and_rand(
+            sha_256,
+            ed25519,
+            ReseedingRng::new(
+                rand_chacha::ChaChaCore::from_entropy(),
+                BYTES_BEFORE_RESEEDING,
+                rand::rngs::OsRng,
+            ),
+        )
     }
 }
 
@@ -65,18 +88,90 @@ impl Default for Recrypt<Sha256, Ed25519, RandomBytes<DefaultRng>> {
 impl<CR: rand::CryptoRng + rand::RngCore> Recrypt<Sha256, Ed25519, RandomBytes<CR>> {
     /// Construct a Recrypt with the given RNG. Unless you have specific needs using `new()` is recommended.
     pub fn new_with_rand(r: CR) -> Recrypt<Sha256, Ed25519, RandomBytes<CR>> {
+        Recrypt::new_with_backends_and_rand(Sha256, Ed25519, r)
+    }
+}
+
+impl<H: Sha256Hashing + Clone, S: Ed25519Signing, CR: rand::CryptoRng + rand::RngCore>
+    Recrypt<H, S, RandomBytes<CR>>
+{
+    /// Construct a Recrypt with the given hashing and signing implementations and RNG. See `new_with_backends`.
+    pub fn new_with_backends_and_rand(
+        sha_256: H,
+        ed25519: S,
+        r: CR,
+    ) -> Recrypt<H, S, RandomBytes<CR>> {
         let pairing = internal::pairing::Pairing::new();
         let curve_points = &*curve::FP_256_CURVE_POINTS;
-        let schnorr_signing = internal::schnorr::SchnorrSign::<Monty256, Fr256, Sha256>::new_256();
+        let schnorr_signing =
+            internal::schnorr::SchnorrSign::<Monty256, Fr256, H>::new_256(sha_256.clone());
         Recrypt {
             random_bytes: RandomBytes::new(r),
-            sha_256: Sha256,
-            ed25519: Ed25519,
+            sha_256,
+            ed25519,
             pairing,
             curve_points,
             schnorr_signing,
+            protocol_version: ProtocolVersion::Legacy,
+            transform_policy: TransformPolicy::default(),
+        }
+    }
+}
+
+impl<H, S, R> Recrypt<H, S, R> {
+    /// Use `version` for the values this Recrypt encrypts, the transform keys it generates and its Schnorr
+    /// signatures.
+    ///
+    /// A Recrypt decrypts values of either version, but a transform key only transforms values of its own version.
+    /// `derive_symmetric_key` and `derive_private_key` also hash with `version`, so everyone deriving keys from the
+    /// same plaintext has to use the same version. The default is `ProtocolVersion::Legacy`, which interoperates
+    /// with values and keys made before versions were added.
+    pub fn with_protocol_version(self, version: ProtocolVersion) -> Recrypt<H, S, R> {
+        Recrypt {
+            schnorr_signing: self.schnorr_signing.with_version(version),
+            protocol_version: version,
+            ..self
+        }
+    }
+
+    /// The protocol version of the values and keys this Recrypt produces.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version
+    }
+
+    /// Also accept Schnorr signatures made with the hashing of `ProtocolVersion::Legacy` in `schnorr_verify`.
+    ///
+    /// A signature doesn't record the version it was made with, so by default only signatures of this Recrypt's
+    /// protocol version verify. Opting in lets a V2 Recrypt verify signatures made before upgrading, at the cost of
+    /// accepting signatures without domain separation.
+    pub fn with_legacy_schnorr_verification(self) -> Recrypt<H, S, R> {
+        Recrypt {
+            schnorr_signing: self.schnorr_signing.with_legacy_verification(),
+            ..self
+        }
+    }
+
+    /// Enforce `policy` on the values this Recrypt transforms and decrypts. See `TransformPolicy`.
+    pub fn with_transform_policy(self, policy: TransformPolicy) -> Recrypt<H, S, R> {
+        Recrypt {
+            transform_policy: policy,
+            ..self
         }
     }
+
+    /// The policy this Recrypt enforces on the values it transforms and decrypts.
+    pub fn transform_policy(&self) -> &TransformPolicy {
+        &self.transform_policy
+    }
+
+    fn suite(&self) -> internal::Suite<'_, Monty256, H, S> {
+        internal::Suite::new(
+            self.curve_points,
+            &self.pairing,
+            &self.sha_256,
+            &self.ed25519,
+        )
+    }
 }
 
 // Hashed but not encrypted Plaintext used for envelope encryption
@@ -215,6 +310,53 @@ impl TransformBlock {
         &self.encrypted_random_transform_temp_key
     }
 
+    /// Encode this block in the canonical, versioned wire format. See [`EncryptedValue::to_wire_bytes`].
+    pub fn to_wire_bytes(&self) -> ByteVector {
+        self.encode(false)
+    }
+
+    /// Encode this block in the compact wire format. See [`EncryptedValue::to_compact_wire_bytes`].
+    pub fn to_compact_wire_bytes(&self) -> ByteVector {
+        self.encode(true)
+    }
+
+    fn encode(&self, compact: bool) -> ByteVector {
+        let writer = WireWriter::new(wire::tag(wire::TAG_TRANSFORM_BLOCK_256, compact));
+        let writer = self.public_key.write_wire(writer, compact);
+        let writer = writer.field(self.encrypted_temp_key.bytes());
+        let writer = self.random_transform_public_key.write_wire(writer, compact);
+        writer
+            .field(self.encrypted_random_transform_temp_key.bytes())
+            .finish()
+    }
+
+    /// Decode a block produced by [`TransformBlock::to_wire_bytes`] or [`TransformBlock::to_compact_wire_bytes`].
+    pub fn from_wire_bytes(bytes: &[u8]) -> Result<TransformBlock> {
+        TransformBlock::decode(
+            bytes,
+            &[
+                wire::TAG_TRANSFORM_BLOCK_256,
+                wire::tag(wire::TAG_TRANSFORM_BLOCK_256, true),
+            ],
+        )
+    }
+
+    fn decode(bytes: &[u8], expected_tags: &[u8]) -> Result<TransformBlock> {
+        let (tag, mut reader) = WireReader::new(bytes, expected_tags)?;
+        let compact = wire::is_compact(tag);
+        let public_key = PublicKey::read_wire(&mut reader, compact)?;
+        let encrypted_temp_key = reader.read(EncryptedTempKey::new_from_slice)?;
+        let random_transform_public_key = PublicKey::read_wire(&mut reader, compact)?;
+        let encrypted_random_transform_temp_key = reader.read(EncryptedTempKey::new_from_slice)?;
+        reader.finish()?;
+        TransformBlock::new(
+            &public_key,
+            &encrypted_temp_key,
+            &random_transform_public_key,
+            &encrypted_random_transform_temp_key,
+        )
+    }
+
     fn try_from(re_block: internal::ReencryptionBlock<Monty256>) -> Result<Self> {
         Ok(TransformBlock {
             public_key: PublicKey::try_from(&re_block.public_key)?,
@@ -238,12 +380,14 @@ pub enum EncryptedValue {
     /// `auth_hash`             - authentication hash for the Plaintext
     /// `public_signing_key`    - public portion of Ed25519 signing key
     /// `signature`             - Ed25519-produced signature
+    /// `protocol_version`      - protocol version the value was encrypted with
     EncryptedOnceValue {
         ephemeral_public_key: PublicKey,
         encrypted_message: EncryptedMessage,
         auth_hash: AuthHash,
         public_signing_key: PublicSigningKey,
         signature: Ed25519Signature,
+        protocol_version: ProtocolVersion,
     },
     /// Value which has been encrypted and then transformed n times for n > 0.
     /// `ephemeral_public_key`  - public key of the ephemeral private key that was used to encrypt
@@ -252,6 +396,7 @@ pub enum EncryptedValue {
     /// `transform_blocks`      - information used in transformation process. One entry for each transform.
     /// `public_signing_key`    - public portion of Ed25519 signing key
     /// `signature`             - Ed25519-produced signature
+    /// `protocol_version`      - protocol version the value was encrypted and transformed with
     TransformedValue {
         ephemeral_public_key: PublicKey,
         encrypted_message: EncryptedMessage,
@@ -259,10 +404,209 @@ pub enum EncryptedValue {
         transform_blocks: NonEmptyVec<TransformBlock>,
         public_signing_key: PublicSigningKey,
         signature: Ed25519Signature,
+        protocol_version: ProtocolVersion,
     },
 }
 
 impl EncryptedValue {
+    /// Encode this value in the canonical, versioned wire format.
+    ///
+    /// The encoding is a version byte, a tag identifying the variant and then every field in
+    /// declaration order, each prefixed by its big-endian `u32` length. The transform blocks
+    /// of a `TransformedValue` are preceded by their count and each block is encoded with
+    /// [`TransformBlock::to_wire_bytes`].
+    pub fn to_wire_bytes(&self) -> ByteVector {
+        self.encode(false)
+    }
+
+    /// Encode this value in the compact variant of the wire format.
+    ///
+    /// The layout is the same as [`EncryptedValue::to_wire_bytes`], but the tags have
+    /// `wire::COMPACT` set and every public key is stored compressed (see [`PublicKey::to_compressed`]),
+    /// which roughly halves the size of each key at the cost of a square root when decoding.
+    pub fn to_compact_wire_bytes(&self) -> ByteVector {
+        self.encode(true)
+    }
+
+    fn encode(&self, compact: bool) -> ByteVector {
+        match self {
+            EncryptedValue::EncryptedOnceValue {
+                ephemeral_public_key,
+                encrypted_message,
+                auth_hash,
+                public_signing_key,
+                signature,
+                protocol_version,
+            } => {
+                let writer = WireWriter::new(wire::versioned_tag(
+                    wire::TAG_ENCRYPTED_ONCE_VALUE_256,
+                    compact,
+                    *protocol_version,
+                ));
+                ephemeral_public_key
+                    .write_wire(writer, compact)
+                    .field(encrypted_message.bytes())
+                    .field(auth_hash.bytes())
+                    .field(public_signing_key.bytes())
+                    .field(signature.bytes())
+                    .finish()
+            }
+            EncryptedValue::TransformedValue {
+                ephemeral_public_key,
+                encrypted_message,
+                auth_hash,
+                transform_blocks,
+                public_signing_key,
+                signature,
+                protocol_version,
+            } => {
+                let writer = WireWriter::new(wire::versioned_tag(
+                    wire::TAG_TRANSFORMED_VALUE_256,
+                    compact,
+                    *protocol_version,
+                ));
+                let writer = ephemeral_public_key
+                    .write_wire(writer, compact)
+                    .field(encrypted_message.bytes())
+                    .field(auth_hash.bytes())
+                    .count(transform_blocks.len());
+                std::iter::once(transform_blocks.first())
+                    .chain(transform_blocks.rest())
+                    .fold(writer, |writer, block| writer.field(&block.encode(compact)))
+                    .field(public_signing_key.bytes())
+                    .field(signature.bytes())
+                    .finish()
+            }
+        }
+    }
+
+    /// Decode a value produced by [`EncryptedValue::to_wire_bytes`] or
+    /// [`EncryptedValue::to_compact_wire_bytes`].
+    ///
+    /// Decoding is strict: an unknown version or tag, a field of the wrong length, an empty
+    /// list of transform blocks, blocks whose format differs from the enclosing value or
+    /// trailing bytes result in `RecryptErr::DecodeFailure`.
+    pub fn from_wire_bytes(bytes: &[u8]) -> Result<EncryptedValue> {
+        let (tag, mut reader) = WireReader::new(
+            bytes,
+            &[
+                wire::versioned_tags(wire::TAG_ENCRYPTED_ONCE_VALUE_256),
+                wire::versioned_tags(wire::TAG_TRANSFORMED_VALUE_256),
+            ]
+            .concat(),
+        )?;
+        let compact = wire::is_compact(tag);
+        let protocol_version = wire::protocol_version(tag);
+        let block_tags = [wire::tag(wire::TAG_TRANSFORM_BLOCK_256, compact)];
+        let ephemeral_public_key = PublicKey::read_wire(&mut reader, compact)?;
+        let encrypted_message = reader.read(EncryptedMessage::new_from_slice)?;
+        let auth_hash = reader.read(AuthHash::new_from_slice)?;
+        let value = if wire::base_tag(tag) == wire::TAG_ENCRYPTED_ONCE_VALUE_256 {
+            EncryptedValue::EncryptedOnceValue {
+                ephemeral_public_key,
+                encrypted_message,
+                auth_hash,
+                public_signing_key: reader.read(PublicSigningKey::new_from_slice)?,
+                signature: reader.read(Ed25519Signature::new_from_slice)?,
+                protocol_version,
+            }
+        } else {
+            let block_count = reader.count()?;
+            if block_count == 0 {
+                return Err(reader
+                    .invalid("a transformed value needs at least one transform block")
+                    .into());
+            }
+            let first = reader.read(|bytes| TransformBlock::decode(bytes, &block_tags))?;
+            let rest = (1..block_count)
+                .map(|_| reader.read(|bytes| TransformBlock::decode(bytes, &block_tags)))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            EncryptedValue::TransformedValue {
+                ephemeral_public_key,
+                encrypted_message,
+                auth_hash,
+                transform_blocks: NonEmptyVec::new(first, rest),
+                public_signing_key: reader.read(PublicSigningKey::new_from_slice)?,
+                signature: reader.read(Ed25519Signature::new_from_slice)?,
+                protocol_version,
+            }
+        };
+        reader.finish()?;
+        Ok(value)
+    }
+
+    /// Check the transform blocks and signing key of this value against `policy`.
+    fn check_policy(&self, policy: &TransformPolicy) -> Result<()> {
+        let (EncryptedValue::EncryptedOnceValue {
+            public_signing_key, ..
+        }
+        | EncryptedValue::TransformedValue {
+            public_signing_key, ..
+        }) = self;
+        policy.check(&self.block_public_keys(), public_signing_key)
+    }
+
+    /// Check that `policy` allows transforming this value once more, with the transform signed by
+    /// `public_signing_key`.
+    fn check_next_hop(
+        &self,
+        policy: &TransformPolicy,
+        public_signing_key: &PublicSigningKey,
+    ) -> Result<()> {
+        policy.check_hop(self.block_public_keys().len() + 1, public_signing_key)
+    }
+
+    /// The public keys of the transform blocks of this value, in order.
+    fn block_public_keys(&self) -> Vec<&PublicKey> {
+        match self {
+            EncryptedValue::EncryptedOnceValue { .. } => vec![],
+            EncryptedValue::TransformedValue {
+                transform_blocks, ..
+            } => std::iter::once(transform_blocks.first())
+                .chain(transform_blocks.rest())
+                .map(TransformBlock::public_key)
+                .collect(),
+        }
+    }
+
+    /// The public key of the ephemeral private key that was used to encrypt.
+    fn ephemeral_public_key(&self) -> &PublicKey {
+        match self {
+            EncryptedValue::EncryptedOnceValue {
+                ephemeral_public_key,
+                ..
+            }
+            | EncryptedValue::TransformedValue {
+                ephemeral_public_key,
+                ..
+            } => ephemeral_public_key,
+        }
+    }
+
+    /// The bytes of the fields that transforming a value never changes, which are what a sender signs in
+    /// `SenderAuthOps::encrypt_authenticated`. The encrypted message isn't one of them, since the first transform
+    /// replaces it, but the auth hash still binds the signature to the plaintext.
+    fn sender_bound_bytes(&self) -> ByteVector {
+        let (EncryptedValue::EncryptedOnceValue {
+            ephemeral_public_key,
+            auth_hash,
+            protocol_version,
+            ..
+        }
+        | EncryptedValue::TransformedValue {
+            ephemeral_public_key,
+            auth_hash,
+            protocol_version,
+            ..
+        }) = self;
+        (
+            ephemeral_public_key,
+            &auth_hash.bytes().to_vec(),
+            protocol_version,
+        )
+            .to_bytes()
+    }
+
     fn try_from(
         signed_value: internal::SignedValue<internal::EncryptedValue<Monty256>>,
     ) -> Result<EncryptedValue> {
@@ -273,6 +617,7 @@ impl EncryptedValue {
                 ephemeral_public_key,
                 encrypted_message,
                 auth_hash,
+                version,
             }) => {
                 let result = EncryptedValueP::EncryptedOnceValue {
                     ephemeral_public_key: PublicKey::try_from(&ephemeral_public_key)?,
@@ -280,6 +625,7 @@ impl EncryptedValue {
                     auth_hash: AuthHash::new(auth_hash.bytes),
                     public_signing_key: signed_value.public_signing_key,
                     signature: signed_value.signature,
+                    protocol_version: version,
                 };
                 Ok(result)
             }
@@ -288,6 +634,7 @@ impl EncryptedValue {
                 encrypted_message,
                 auth_hash,
                 encryption_blocks,
+                version,
             }) => {
                 let maybe_first_block = TransformBlock::try_from(*encryption_blocks.first());
                 let maybe_transform_blocks_rest: Result<Vec<TransformBlock>> = encryption_blocks
@@ -306,6 +653,7 @@ impl EncryptedValue {
                             transform_blocks: NonEmptyVec::new(good_first, good_rest),
                             public_signing_key: signed_value.public_signing_key,
                             signature: signed_value.signature,
+                            protocol_version: version,
                         };
                         Ok(result)
                     }
@@ -336,6 +684,7 @@ impl EncryptedValue {
                 auth_hash,
                 public_signing_key,
                 signature,
+                protocol_version,
             } => {
                 let fp12 = Fp12Elem::<Monty256>::decode(encrypted_message.to_vec())?;
                 Ok(
@@ -349,6 +698,7 @@ impl EncryptedValue {
                                 auth_hash: internal::AuthHash {
                                     bytes: auth_hash.bytes,
                                 },
+                                version: protocol_version,
                             },
                         ),
                     },
@@ -364,6 +714,7 @@ impl EncryptedValue {
                 transform_blocks,
                 public_signing_key,
                 signature,
+                protocol_version,
             } => {
                 let fp12 = Fp12Elem::<Monty256>::decode(encrypted_message.to_vec())?;
                 let first_block = transform_blocks.first()._internal_re_block;
@@ -384,6 +735,7 @@ impl EncryptedValue {
                                     bytes: auth_hash.bytes,
                                 },
                                 encryption_blocks: NonEmptyVec::new(first_block, rest_blocks),
+                                version: protocol_version,
                             },
                         ),
                     },
@@ -459,6 +811,17 @@ impl HashedValue {
     pub fn bytes(&self) -> &[u8; HashedValue::ENCODED_SIZE_BYTES] {
         &self.bytes
     }
+    /// Compressed encoding of the point, used by the compact wire format.
+    fn to_compressed(self) -> ByteVector {
+        self._internal_value
+            .to_compressed()
+            .expect("Developer error: a HashedValue is never the zero point")
+    }
+    fn from_compressed(bytes: &[u8]) -> Result<Self> {
+        Ok(HashedValue::from(
+            TwistedHPoint::<Monty256>::from_compressed(bytes)?,
+        ))
+    }
     pub fn new_from_slice(bytes: &[u8]) -> Result<Self> {
         if bytes.len() == HashedValue::ENCODED_SIZE_BYTES {
             let mut dest = [0u8; HashedValue::ENCODED_SIZE_BYTES];
@@ -500,6 +863,7 @@ impl From<TwistedHPoint<Monty256>> for HashedValue {
 /// `to_public_key`         - public key of the delagatee
 /// `encrypted_k`           - random value K, encrypted to the delegatee; used to un-roll successive levels of multi-hop transform encryption
 /// `hashed_k`              - combination of the hash of K and the secret key of the delegator; used to recover K from `encrypted_k`
+/// `protocol_version`      - protocol version of `hashed_k`, and of the values this key can transform
 #[derive(Derivative, Debug, Clone)] //can't derive Copy because of NonEmptyVec
 #[derivative(PartialEq, Hash, Eq)]
 pub struct TransformKey {
@@ -509,6 +873,7 @@ pub struct TransformKey {
     hashed_temp_key: HashedValue,
     public_signing_key: PublicSigningKey,
     signature: Ed25519Signature,
+    protocol_version: ProtocolVersion,
     #[derivative(Hash = "ignore", PartialEq = "ignore")]
     _internal_key: internal::SignedValue<internal::ReencryptionKey<Monty256>>,
 }
@@ -516,11 +881,14 @@ pub struct TransformKey {
 impl Hashable for TransformKey {
     fn to_bytes(&self) -> ByteVector {
         (
-            &self.ephemeral_public_key,
-            &self.to_public_key,
-            &self.encrypted_temp_key,
-            &self.hashed_temp_key,
-            &self.public_signing_key,
+            &self.protocol_version,
+            &(
+                &self.ephemeral_public_key,
+                &self.to_public_key,
+                &self.encrypted_temp_key,
+                &self.hashed_temp_key,
+                &self.public_signing_key,
+            ),
         )
             .to_bytes()
     }
@@ -545,6 +913,64 @@ impl TransformKey {
     pub fn signature(&self) -> &Ed25519Signature {
         &self.signature
     }
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version
+    }
+
+    /// Encode this key in the canonical, versioned wire format. See [`EncryptedValue::to_wire_bytes`].
+    pub fn to_wire_bytes(&self) -> ByteVector {
+        self.encode(false)
+    }
+
+    /// Encode this key in the compact wire format, which also compresses the hashed temp key.
+    /// See [`EncryptedValue::to_compact_wire_bytes`].
+    pub fn to_compact_wire_bytes(&self) -> ByteVector {
+        self.encode(true)
+    }
+
+    fn encode(&self, compact: bool) -> ByteVector {
+        let writer = WireWriter::new(wire::versioned_tag(
+            wire::TAG_TRANSFORM_KEY_256,
+            compact,
+            self.protocol_version,
+        ));
+        let writer = self.ephemeral_public_key.write_wire(writer, compact);
+        let writer = self
+            .to_public_key
+            .write_wire(writer, compact)
+            .field(self.encrypted_temp_key.bytes());
+        let writer = if compact {
+            writer.field(&self.hashed_temp_key.to_compressed())
+        } else {
+            writer.field(self.hashed_temp_key.bytes())
+        };
+        writer
+            .field(self.public_signing_key.bytes())
+            .field(self.signature.bytes())
+            .finish()
+    }
+
+    /// Decode a key produced by [`TransformKey::to_wire_bytes`] or [`TransformKey::to_compact_wire_bytes`].
+    pub fn from_wire_bytes(bytes: &[u8]) -> Result<TransformKey> {
+        let (tag, mut reader) =
+            WireReader::new(bytes, &wire::versioned_tags(wire::TAG_TRANSFORM_KEY_256))?;
+        let compact = wire::is_compact(tag);
+        let transform_key = TransformKey::new(
+            PublicKey::read_wire(&mut reader, compact)?,
+            PublicKey::read_wire(&mut reader, compact)?,
+            reader.read(EncryptedTempKey::new_from_slice)?,
+            if compact {
+                reader.read(HashedValue::from_compressed)?
+            } else {
+                reader.read(HashedValue::new_from_slice)?
+            },
+            reader.read(PublicSigningKey::new_from_slice)?,
+            reader.read(Ed25519Signature::new_from_slice)?,
+            wire::protocol_version(tag),
+        );
+        reader.finish()?;
+        Ok(transform_key)
+    }
     fn try_from_internal(
         re_key: internal::SignedValue<internal::ReencryptionKey<Monty256>>,
     ) -> Result<TransformKey> {
@@ -555,6 +981,7 @@ impl TransformKey {
             hashed_temp_key: HashedValue::from(re_key.payload.hashed_k),
             public_signing_key: re_key.public_signing_key,
             signature: re_key.signature,
+            protocol_version: re_key.payload.version,
             _internal_key: re_key,
         };
         Ok(result)
@@ -568,12 +995,14 @@ impl TransformKey {
         hashed_temp_key: HashedValue,
         public_signing_key: PublicSigningKey,
         signature: Ed25519Signature,
+        protocol_version: ProtocolVersion,
     ) -> TransformKey {
         let reencryption_key = internal::ReencryptionKey {
             re_public_key: ephemeral_public_key._internal_key,
             to_public_key: to_public_key._internal_key,
             encrypted_k: encrypted_temp_key._internal_fp12,
             hashed_k: hashed_temp_key._internal_value,
+            version: protocol_version,
         };
 
         let internal_key = internal::SignedValue {
@@ -590,6 +1019,7 @@ impl TransformKey {
             hashed_temp_key,
             public_signing_key,
             signature,
+            protocol_version,
             _internal_key: internal_key,
         }
     }
@@ -600,7 +1030,7 @@ impl TransformKey {
         let new_internal = self
             ._internal_key
             .payload
-            .augment(&private_key.into(), &curve::FP_256_CURVE_POINTS.g1);
+            .augment(&private_key.into(), &curve::FP_256_CURVE_POINTS);
         TransformKey::try_from_internal(internal::SignedValue {
             payload: new_internal,
             ..self._internal_key
@@ -683,6 +1113,10 @@ impl<H, S, CR: rand::RngCore + rand::CryptoRng> Ed25519Ops for Recrypt<H, S, Ran
 
 /// Key generation operations
 pub trait KeyGenOps {
+    /// The key that transform keys and encrypted values are signed with. It is `SigningKeypair` unless the
+    /// `Recrypt` was built with a signer of its own (see `new_with_backends`).
+    type SigningKey;
+
     /// Compute a `PublicKey` given a `PrivateKey`
     fn compute_public_key(&self, private_key: &PrivateKey) -> Result<PublicKey>;
 
@@ -710,16 +1144,16 @@ pub trait KeyGenOps {
         &self,
         from_private_key: &PrivateKey,
         to_public_key: &PublicKey,
-        signing_keypair: &SigningKeypair,
+        signing_keypair: &Self::SigningKey,
     ) -> Result<TransformKey>;
 }
 
 impl<R: RandomBytesGen, H: Sha256Hashing, S: Ed25519Signing> KeyGenOps for Recrypt<H, S, R> {
+    type SigningKey = S::Key;
+
     fn compute_public_key(&self, private_key: &PrivateKey) -> Result<PublicKey> {
-        let pub_key_internal = internal::public_keygen(
-            internal::PrivateKey::from(private_key),
-            self.curve_points.generator,
-        );
+        let pub_key_internal =
+            internal::public_keygen(internal::PrivateKey::from(private_key), self.curve_points);
         PublicKey::try_from(&pub_key_internal)
     }
 
@@ -737,7 +1171,7 @@ impl<R: RandomBytesGen, H: Sha256Hashing, S: Ed25519Signing> KeyGenOps for Recry
         &self,
         from_private_key: &PrivateKey,
         to_public_key: &PublicKey,
-        signing_keypair: &SigningKeypair,
+        signing_keypair: &Self::SigningKey,
     ) -> Result<TransformKey> {
         let ephem_reencryption_private_key = self.random_private_key();
         let temp_key = internal::KValue(gen_random_fp12(&self.pairing, &self.random_bytes));
@@ -747,10 +1181,8 @@ impl<R: RandomBytesGen, H: Sha256Hashing, S: Ed25519Signing> KeyGenOps for Recry
             ephem_reencryption_private_key._internal_key,
             temp_key,
             signing_keypair,
-            self.curve_points,
-            &self.pairing,
-            &self.sha_256,
-            &self.ed25519,
+            &self.suite(),
+            self.protocol_version,
         )?;
 
         TransformKey::try_from_internal(reencryption_key)
@@ -759,6 +1191,9 @@ impl<R: RandomBytesGen, H: Sha256Hashing, S: Ed25519Signing> KeyGenOps for Recry
 
 /// Encrypt, Decrypt, Transform, and supporting operations.
 pub trait CryptoOps {
+    /// The key that encrypted values are signed with, as for `KeyGenOps::SigningKey`.
+    type SigningKey;
+
     /// Using the random_bytes, generate a random element of G_T, which is one of the rth roots of unity in FP12.
     ///
     /// What it means to be an rth root (for Fp256):
@@ -789,7 +1224,7 @@ pub trait CryptoOps {
         &self,
         plaintext: &Plaintext,
         to_public_key: &PublicKey,
-        signing_keypair: &SigningKeypair,
+        signing_keypair: &Self::SigningKey,
     ) -> Result<EncryptedValue>;
 
     /// Decrypt the value using `private_key`.
@@ -806,6 +1241,20 @@ pub trait CryptoOps {
         private_key: &PrivateKey,
     ) -> Result<Plaintext>;
 
+    /// Decrypt each of the `encrypted_values` using `private_key`.
+    ///
+    /// This gives the same results as calling `decrypt` on each value, but the signatures of all the values are
+    /// verified together with Ed25519 batch verification, and the pairings share the cost of their final
+    /// exponentiation. Each value gets its own result, so one value that can't be decrypted doesn't affect the others.
+    ///
+    /// # Return
+    /// One result per value, in the same order as `encrypted_values`.
+    fn decrypt_batch(
+        &self,
+        encrypted_values: Vec<EncryptedValue>,
+        private_key: &PrivateKey,
+    ) -> Vec<Result<Plaintext>>;
+
     /// Transform the value `encrypted_value` using the `transform_key`.
     /// The returned value can be decrypted by the private key associated to the `to_public_key` in the `transform_key`.
     ///
@@ -815,29 +1264,58 @@ pub trait CryptoOps {
         &self,
         encrypted_value: EncryptedValue,
         transform_key: TransformKey,
-        signing_keypair: &SigningKeypair,
+        signing_keypair: &Self::SigningKey,
     ) -> Result<EncryptedValue>;
+
+    /// Transform each of the `encrypted_values` using the same `transform_key`.
+    ///
+    /// This gives the same results as calling `transform` on each value, but the signature of `transform_key`
+    /// is only verified once. Each value gets its own result, so one value that can't be transformed (for example
+    /// because its signature is invalid) doesn't affect the others. With the `parallel` feature the values are
+    /// transformed in parallel.
+    ///
+    /// # Return
+    /// One result per value, in the same order as `encrypted_values`, or `RecryptErr::InvalidTransformKey` if the
+    /// signature of `transform_key` is invalid.
+    fn transform_batch(
+        &self,
+        encrypted_values: &[EncryptedValue],
+        transform_key: TransformKey,
+        signing_keypair: &Self::SigningKey,
+    ) -> Result<Vec<Result<EncryptedValue>>>;
 }
 
-impl<R: RandomBytesGen, H: Sha256Hashing, S: Ed25519Signing> CryptoOps for Recrypt<H, S, R> {
+impl<R: RandomBytesGen, H: Sha256Hashing + MaybeSync, S: Ed25519Signing<Key: MaybeSync> + MaybeSync>
+    CryptoOps for Recrypt<H, S, R>
+{
+    type SigningKey = S::Key;
+
     fn gen_plaintext(&self) -> Plaintext {
         let rand_fp12 = gen_random_fp12(&self.pairing, &self.random_bytes);
         Plaintext::from(rand_fp12)
     }
 
     fn derive_symmetric_key(&self, decrypted_value: &Plaintext) -> DerivedSymmetricKey {
-        DerivedSymmetricKey::new(self.sha_256.hash(decrypted_value))
+        DerivedSymmetricKey::new(self.protocol_version.hash(
+            &self.sha_256,
+            HashDomain::SymmetricKey,
+            decrypted_value,
+        ))
     }
 
     fn derive_private_key(&self, plaintext: &Plaintext) -> PrivateKey {
-        PrivateKey::new(self.sha_256.hash(plaintext))
+        PrivateKey::new(self.protocol_version.hash(
+            &self.sha_256,
+            HashDomain::PrivateKey,
+            plaintext,
+        ))
     }
 
     fn encrypt(
         &self,
         plaintext: &Plaintext,
         to_public_key: &PublicKey,
-        signing_keypair: &SigningKeypair,
+        signing_keypair: &Self::SigningKey,
     ) -> Result<EncryptedValue> {
         //generate a ephemeral private key
         let ephem_private_key = self.random_private_key();
@@ -848,10 +1326,8 @@ impl<R: RandomBytesGen, H: Sha256Hashing, S: Ed25519Signing> CryptoOps for Recry
             plaintext_fp12,
             internal::PrivateKey::from(ephem_private_key),
             signing_keypair,
-            &self.pairing,
-            self.curve_points,
-            &self.sha_256,
-            &self.ed25519,
+            &self.suite(),
+            self.protocol_version,
         )?;
 
         EncryptedValue::try_from(encrypted_value_internal)
@@ -862,6 +1338,7 @@ impl<R: RandomBytesGen, H: Sha256Hashing, S: Ed25519Signing> CryptoOps for Recry
         encrypted_value: EncryptedValue,
         private_key: &PrivateKey,
     ) -> Result<Plaintext> {
+        encrypted_value.check_policy(&self.transform_policy)?;
         Ok(internal::decrypt(
             internal::PrivateKey::from(private_key),
             EncryptedValue::try_into(encrypted_value)?,
@@ -873,80 +1350,512 @@ impl<R: RandomBytesGen, H: Sha256Hashing, S: Ed25519Signing> CryptoOps for Recry
         .map(Plaintext::from)?)
     }
 
+    fn decrypt_batch(
+        &self,
+        encrypted_values: Vec<EncryptedValue>,
+        private_key: &PrivateKey,
+    ) -> Vec<Result<Plaintext>> {
+        // Values that can't be converted keep their error, the rest are decrypted together.
+        let mut signed_values: Vec<internal::SignedValue<internal::EncryptedValue<Monty256>>> =
+            Vec::with_capacity(encrypted_values.len());
+        let conversions: Vec<Result<()>> = encrypted_values
+            .into_iter()
+            .map(|encrypted_value| {
+                encrypted_value.check_policy(&self.transform_policy)?;
+                signed_values.push(EncryptedValue::try_into(encrypted_value)?);
+                Ok(())
+            })
+            .collect();
+        let mut decrypted = internal::decrypt_batch(
+            internal::PrivateKey::from(private_key),
+            signed_values,
+            &self.pairing,
+            self.curve_points,
+            &self.sha_256,
+            &self.ed25519,
+        )
+        .into_iter();
+        conversions
+            .into_iter()
+            .map(|conversion| {
+                conversion?;
+                Ok(decrypted
+                    .next()
+                    .expect("Developer error: one decrypted result per converted value")
+                    .map(Plaintext::from)?)
+            })
+            .collect()
+    }
+
     fn transform(
         &self,
         encrypted_value: EncryptedValue,
         transform_key: TransformKey,
-        signing_keypair: &SigningKeypair,
+        signing_keypair: &Self::SigningKey,
     ) -> Result<EncryptedValue> {
+        encrypted_value.check_policy(&self.transform_policy)?;
+        encrypted_value.check_next_hop(&self.transform_policy, &signing_keypair.public_key())?;
         let plaintext = self.gen_plaintext();
         let random_private_key = self.random_private_key();
-        EncryptedValue::try_from(internal::reencrypt(
+        let transformed_value = EncryptedValue::try_from(internal::reencrypt(
             transform_key._internal_key,
             EncryptedValue::try_into(encrypted_value)?,
             internal::PrivateKey::from(random_private_key),
             plaintext.into(),
             signing_keypair,
-            &self.ed25519,
-            &self.sha_256,
-            self.curve_points,
-            &self.pairing,
-        )?)
+            &self.suite(),
+        )?)?;
+        self.transform_policy
+            .check_cycles(&transformed_value.block_public_keys())?;
+        Ok(transformed_value)
     }
-}
 
-fn gen_random_fp12<R: RandomBytesGen>(
-    pairing: &pairing::Pairing<Monty256>,
-    random_bytes: &R,
-) -> Fp12Elem<Monty256> {
-    // generate 12 random Fp values
-    internal::gen_rth_root(
-        pairing,
-        Fp12Elem::create_from_t(
-            Fp256::from(random_bytes.random_bytes_32()),
-            Fp256::from(random_bytes.random_bytes_32()),
-            Fp256::from(random_bytes.random_bytes_32()),
-            Fp256::from(random_bytes.random_bytes_32()),
-            Fp256::from(random_bytes.random_bytes_32()),
-            Fp256::from(random_bytes.random_bytes_32()),
-            Fp256::from(random_bytes.random_bytes_32()),
-            Fp256::from(random_bytes.random_bytes_32()),
-            Fp256::from(random_bytes.random_bytes_32()),
-            Fp256::from(random_bytes.random_bytes_32()),
-            Fp256::from(random_bytes.random_bytes_32()),
-            Fp256::from(random_bytes.random_bytes_32()),
-        )
-        .map(&|fp256| fp256.to_monty()),
-    )
+    fn transform_batch(
+        &self,
+        encrypted_values: &[EncryptedValue],
+        transform_key: TransformKey,
+        signing_keypair: &Self::SigningKey,
+    ) -> Result<Vec<Result<EncryptedValue>>> {
+        let re_key = internal::verify_signed_value(transform_key._internal_key, &self.ed25519)
+            .ok_or(internal::InternalError::CorruptReencryptionKey)?;
+        // The random values are drawn up front so that the random number generator isn't shared between threads.
+        let inputs: Vec<_> = encrypted_values
+            .iter()
+            .map(|encrypted_value| {
+                (
+                    encrypted_value,
+                    self.gen_plaintext(),
+                    self.random_private_key(),
+                )
+            })
+            .collect();
+        let (suite, transform_policy) = (&self.suite(), &self.transform_policy);
+        let public_signing_key = signing_keypair.public_key();
+        let transform_one = |(encrypted_value, plaintext, random_private_key): (
+            &EncryptedValue,
+            Plaintext,
+            PrivateKey,
+        )|
+         -> Result<EncryptedValue> {
+            encrypted_value.check_policy(transform_policy)?;
+            encrypted_value.check_next_hop(transform_policy, &public_signing_key)?;
+            let encrypted_value = internal::verify_signed_value(
+                EncryptedValue::try_into(encrypted_value.clone())?,
+                suite.ed25519,
+            )
+            .ok_or(internal::InternalError::InvalidEncryptedMessageSignature)?;
+            let transformed_value = EncryptedValue::try_from(internal::reencrypt_verified(
+                re_key.clone(),
+                encrypted_value,
+                internal::PrivateKey::from(random_private_key),
+                plaintext.into(),
+                signing_keypair,
+                suite,
+            )?)?;
+            transform_policy.check_cycles(&transformed_value.block_public_keys())?;
+            Ok(transformed_value)
+        };
+        #[cfg(feature = "parallel")]
+        let results = inputs.into_par_iter().map(transform_one).collect();
+        #[cfg(not(feature = "parallel"))]
+        let results = inputs.into_iter().map(transform_one).collect();
+        Ok(results)
+    }
 }
 
-#[derive(Derivative, Debug, Clone, Copy)]
-#[derivative(PartialEq, Hash, Eq)]
-pub struct PublicKey {
-    x: [u8; 32],
-    y: [u8; 32],
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
-    _internal_key: internal::PublicKey<Monty256>,
-}
+/// Threshold decryption, where a `PrivateKey` is split into shares so that any `threshold` of them are needed to
+/// decrypt the values encrypted (or transformed) to its `PublicKey`.
+///
+/// Each shareholder computes a `PartialDecryption` of a value with its share, and a combiner recovers the
+/// `Plaintext` from the partial decryptions. The private key is never reconstructed.
+pub trait ThresholdOps {
+    /// The key that encrypted values are signed with, as for `KeyGenOps::SigningKey`.
+    type SigningKey;
+
+    /// Split `private_key` into `share_count` shares with Shamir secret sharing over Fr, such that any `threshold`
+    /// of the shares can decrypt together and fewer reveal nothing about the key.
+    ///
+    /// Relies on `Api::random_bytes` to generate the random polynomial.
+    ///
+    /// # Return
+    /// The shares, with indices `1..=share_count`, or `RecryptErr::InvalidKeyShares` if `threshold` is zero or
+    /// greater than `share_count`.
+    fn split_private_key(
+        &self,
+        private_key: &PrivateKey,
+        threshold: u32,
+        share_count: u32,
+    ) -> Result<Vec<PrivateKeyShare>>;
 
-impl Hashable for PublicKey {
-    fn to_bytes(&self) -> ByteVector {
-        self._internal_key.to_bytes()
-    }
+    /// Compute the partial decryption of `encrypted_value` with one share of the private key it is encrypted to.
+    ///
+    /// # Return
+    /// The partial decryption, or an error if the signature of `encrypted_value` is invalid.
+    fn decrypt_partial(
+        &self,
+        encrypted_value: &EncryptedValue,
+        share: &PrivateKeyShare,
+    ) -> Result<PartialDecryption>;
+
+    /// Recover the plaintext of `encrypted_value` from the partial decryptions of at least `threshold` shares.
+    ///
+    /// # Return
+    /// The recovered plaintext. `RecryptErr::InvalidKeyShares` if there are no partial decryptions, two of them are
+    /// from the same share, or they were computed for a different kind of value. With fewer partial decryptions than
+    /// the threshold the result is `RecryptErr::DecryptFailed`, as with the wrong key for `decrypt`.
+    fn combine_partial_decryptions(
+        &self,
+        encrypted_value: EncryptedValue,
+        partial_decryptions: &[PartialDecryption],
+    ) -> Result<Plaintext>;
 }
 
-impl PublicKey {
-    pub const ENCODED_SIZE_BYTES: usize = Monty256::ENCODED_SIZE_BYTES * 2;
+impl<R: RandomBytesGen, H: Sha256Hashing, S: Ed25519Signing> ThresholdOps for Recrypt<H, S, R> {
+    type SigningKey = S::Key;
 
-    fn try_from(internal_key: &internal::PublicKey<Monty256>) -> Result<PublicKey> {
-        Ok(internal_key
-            .to_byte_vectors_32()
-            .map(|(x, y)| PublicKey {
-                x,
-                y,
-                _internal_key: *internal_key,
-            })
-            .ok_or(internal::homogeneouspoint::PointErr::ZeroPoint)?)
+    fn split_private_key(
+        &self,
+        private_key: &PrivateKey,
+        threshold: u32,
+        share_count: u32,
+    ) -> Result<Vec<PrivateKeyShare>> {
+        if !(1..=share_count).contains(&threshold) {
+            return Err(RecryptErr::InvalidKeyShares(
+                "the threshold must be between 1 and the number of shares",
+            ));
+        }
+        let shares = internal::threshold::split_secret(
+            Fr256::from(private_key.bytes),
+            threshold,
+            share_count,
+            || Fr256::from_rand_no_bias(&self.random_bytes),
+        );
+        Ok(shares
+            .into_iter()
+            .map(|(index, share)| PrivateKeyShare::new(index, PrivateKey::new(share.to_bytes_32())))
+            .collect())
+    }
+
+    fn decrypt_partial(
+        &self,
+        encrypted_value: &EncryptedValue,
+        share: &PrivateKeyShare,
+    ) -> Result<PartialDecryption> {
+        let points = internal::threshold::decrypt_partial(
+            internal::PrivateKey::from(&share.key),
+            EncryptedValue::try_into(encrypted_value.clone())?,
+            &self.ed25519,
+        )?;
+        Ok(PartialDecryption {
+            index: share.index,
+            points: points
+                .iter()
+                .map(|point| PublicKey::try_from(&internal::PublicKey::new(*point)))
+                .collect::<Result<Vec<PublicKey>>>()?,
+        })
+    }
+
+    fn combine_partial_decryptions(
+        &self,
+        encrypted_value: EncryptedValue,
+        partial_decryptions: &[PartialDecryption],
+    ) -> Result<Plaintext> {
+        let signed_value: internal::SignedValue<internal::EncryptedValue<Monty256>> =
+            EncryptedValue::try_into(encrypted_value)?;
+        let point_count = internal::threshold::key_points(&signed_value.payload).len();
+        let mut indices: Vec<u32> = partial_decryptions.iter().map(|p| p.index).collect();
+        indices.sort_unstable();
+        indices.dedup();
+        if partial_decryptions.is_empty() {
+            Err(RecryptErr::InvalidKeyShares(
+                "there were no partial decryptions",
+            ))
+        } else if indices.len() != partial_decryptions.len() {
+            Err(RecryptErr::InvalidKeyShares(
+                "more than one partial decryption was from the same share",
+            ))
+        } else if partial_decryptions
+            .iter()
+            .any(|p| p.points.len() != point_count)
+        {
+            Err(RecryptErr::InvalidKeyShares(
+                "a partial decryption was computed for a different kind of value",
+            ))
+        } else {
+            let partials: Vec<(u32, Vec<HomogeneousPoint<Monty256>>)> = partial_decryptions
+                .iter()
+                .map(|p| {
+                    let points = p.points.iter().map(|point| point._internal_key.value);
+                    (p.index, points.collect())
+                })
+                .collect();
+            Ok(internal::threshold::decrypt_combined::<_, Fr256, _, _>(
+                signed_value,
+                &partials,
+                &self.pairing,
+                self.curve_points,
+                &self.sha_256,
+                &self.ed25519,
+            )
+            .map(Plaintext::from)?)
+        }
+    }
+}
+
+/// Rotation of a private key without changing the public key that values are encrypted to.
+///
+/// The old private key is replaced by a new one, and the difference between the two becomes an augmentation factor,
+/// so the public key of the new key augmented with the public key of the factor is the old public key. Whoever holds
+/// the factor augments the `TransformKey`s generated from the new key (see `TransformKey::augment`), and values that
+/// were encrypted before the rotation still transform and decrypt. For keys that are already augmented with a server
+/// share, the server's share is augmented with the factor instead (`augment_plus`) and goes on augmenting the
+/// `TransformKey`s as before.
+pub trait KeyRotationOps {
+    /// The key that encrypted values are signed with, as for `KeyGenOps::SigningKey`.
+    type SigningKey;
+
+    /// Rotate `private_key` to a new random private key.
+    ///
+    /// Relies on `Api::random_bytes` to generate cryptographically secure random bytes
+    fn rotate_private_key(&self, private_key: &PrivateKey) -> KeyRotation;
+
+    /// Generate the `TransformKey`s from the rotated private key to each of `to_public_keys`, augmented with the
+    /// augmentation factor of `rotation` so that they delegate from the unchanged public key.
+    ///
+    /// # Return
+    /// One `TransformKey` per public key, in the same order.
+    fn rotate_transform_keys(
+        &self,
+        rotation: &KeyRotation,
+        to_public_keys: &[PublicKey],
+        signing_keypair: &Self::SigningKey,
+    ) -> Result<Vec<TransformKey>>;
+}
+
+impl<R: RandomBytesGen, H: Sha256Hashing, S: Ed25519Signing> KeyRotationOps for Recrypt<H, S, R> {
+    type SigningKey = S::Key;
+
+    fn rotate_private_key(&self, private_key: &PrivateKey) -> KeyRotation {
+        KeyRotation::new(private_key, &self.random_private_key())
+    }
+
+    fn rotate_transform_keys(
+        &self,
+        rotation: &KeyRotation,
+        to_public_keys: &[PublicKey],
+        signing_keypair: &Self::SigningKey,
+    ) -> Result<Vec<TransformKey>> {
+        to_public_keys
+            .iter()
+            .map(|to_public_key| {
+                let transform_key = self.generate_transform_key(
+                    &rotation.rotated_private_key,
+                    to_public_key,
+                    signing_keypair,
+                )?;
+                rotation.augment_transform_key(&transform_key)
+            })
+            .collect()
+    }
+}
+
+/// Encryption that binds the value to the recrypt `PublicKey` of its sender.
+///
+/// The Ed25519 signature of an `EncryptedValue` only shows who holds the `SigningKeypair`, which has nothing to do with
+/// the sender's recrypt keys. `encrypt_authenticated` also signs the value with `SchnorrOps::schnorr_sign`, and
+/// `decrypt_from` only decrypts values whose signature verifies for the expected sender. The signature covers the
+/// sender's public key and the fields of the value that transforms leave alone, so it stays valid through any number
+/// of transforms.
+///
+/// The sender signs with their private key alone, and the value also carries a second signature made with its
+/// ephemeral private key over the same bytes. That one can only be made by whoever encrypted the value, so someone
+/// who copies the value can't sign it as their own.
+pub trait SenderAuthOps {
+    /// The key that encrypted values are signed with, as for `KeyGenOps::SigningKey`.
+    type SigningKey;
+
+    /// Encrypt the plaintext to the `to_public_key` and sign it with `sender_private_key`.
+    ///
+    /// # Arguments
+    /// - `plaintext`             - value to encrypt.
+    /// - `to_public_key`         - identity to encrypt to.
+    /// - `sender_private_key`    - private key of the sender, whose public key the value is bound to.
+    /// - `signing_keypair`       - signing keypair of the person (or device) who is encrypting this value
+    ///
+    /// # Return
+    /// AuthenticatedEncryptedValue which can be decrypted with `decrypt_from` by the matching private key of
+    /// `to_public_key`, or RecryptErr.
+    fn encrypt_authenticated(
+        &self,
+        plaintext: &Plaintext,
+        to_public_key: &PublicKey,
+        sender_private_key: &PrivateKey,
+        signing_keypair: &Self::SigningKey,
+    ) -> Result<AuthenticatedEncryptedValue>;
+
+    /// Transform the value like `CryptoOps::transform`, keeping its sender and sender signature.
+    fn transform_authenticated(
+        &self,
+        authenticated_value: AuthenticatedEncryptedValue,
+        transform_key: TransformKey,
+        signing_keypair: &Self::SigningKey,
+    ) -> Result<AuthenticatedEncryptedValue>;
+
+    /// Decrypt the value using `private_key`, if it was encrypted by `expected_sender`.
+    ///
+    /// # Return
+    /// `RecryptErr::InvalidSenderSignature` if the value isn't bound to `expected_sender` or its sender signature
+    /// doesn't verify, otherwise the same result as `CryptoOps::decrypt`.
+    fn decrypt_from(
+        &self,
+        authenticated_value: AuthenticatedEncryptedValue,
+        private_key: &PrivateKey,
+        expected_sender: &PublicKey,
+    ) -> Result<Plaintext>;
+}
+
+impl<
+    H: Sha256Hashing + MaybeSync,
+    S: Ed25519Signing<Key: MaybeSync> + MaybeSync,
+    CR: rand::RngCore + rand::CryptoRng,
+> SenderAuthOps for Recrypt<H, S, RandomBytes<CR>>
+{
+    type SigningKey = S::Key;
+
+    fn encrypt_authenticated(
+        &self,
+        plaintext: &Plaintext,
+        to_public_key: &PublicKey,
+        sender_private_key: &PrivateKey,
+        signing_keypair: &Self::SigningKey,
+    ) -> Result<AuthenticatedEncryptedValue> {
+        let sender_public_key = self.compute_public_key(sender_private_key)?;
+        let ephem_private_key = self.random_private_key();
+        let encrypted_value = EncryptedValue::try_from(internal::encrypt(
+            to_public_key._internal_key,
+            plaintext._internal_fp12,
+            internal::PrivateKey::from(&ephem_private_key),
+            signing_keypair,
+            &self.suite(),
+            self.protocol_version,
+        )?)?;
+        let message = (&sender_public_key, &encrypted_value.sender_bound_bytes());
+        let sender_signature = self.schnorr_sign(sender_private_key, &sender_public_key, &message);
+        let ephemeral_signature = self.schnorr_sign(
+            &ephem_private_key,
+            encrypted_value.ephemeral_public_key(),
+            &message,
+        );
+        Ok(AuthenticatedEncryptedValue::new(
+            encrypted_value,
+            sender_public_key,
+            sender_signature,
+            ephemeral_signature,
+        ))
+    }
+
+    fn transform_authenticated(
+        &self,
+        authenticated_value: AuthenticatedEncryptedValue,
+        transform_key: TransformKey,
+        signing_keypair: &Self::SigningKey,
+    ) -> Result<AuthenticatedEncryptedValue> {
+        let AuthenticatedEncryptedValue {
+            encrypted_value,
+            sender_public_key,
+            sender_signature,
+            ephemeral_signature,
+        } = authenticated_value;
+        Ok(AuthenticatedEncryptedValue::new(
+            self.transform(encrypted_value, transform_key, signing_keypair)?,
+            sender_public_key,
+            sender_signature,
+            ephemeral_signature,
+        ))
+    }
+
+    fn decrypt_from(
+        &self,
+        authenticated_value: AuthenticatedEncryptedValue,
+        private_key: &PrivateKey,
+        expected_sender: &PublicKey,
+    ) -> Result<Plaintext> {
+        let AuthenticatedEncryptedValue {
+            encrypted_value,
+            sender_public_key,
+            sender_signature,
+            ephemeral_signature,
+        } = authenticated_value;
+        let message = (&sender_public_key, &encrypted_value.sender_bound_bytes());
+        let signed_by_sender = sender_public_key == *expected_sender
+            && self.schnorr_verify(&sender_public_key, None, &message, sender_signature)
+            && self.schnorr_verify(
+                encrypted_value.ephemeral_public_key(),
+                None,
+                &message,
+                ephemeral_signature,
+            );
+        if signed_by_sender {
+            self.decrypt(encrypted_value, private_key)
+        } else {
+            Err(RecryptErr::InvalidSenderSignature)
+        }
+    }
+}
+
+fn gen_random_fp12<R: RandomBytesGen>(
+    pairing: &pairing::Pairing<Monty256>,
+    random_bytes: &R,
+) -> Fp12Elem<Monty256> {
+    // generate 12 random Fp values
+    internal::gen_rth_root(
+        pairing,
+        Fp12Elem::create_from_t(
+            Fp256::from(random_bytes.random_bytes_32()),
+            Fp256::from(random_bytes.random_bytes_32()),
+            Fp256::from(random_bytes.random_bytes_32()),
+            Fp256::from(random_bytes.random_bytes_32()),
+            Fp256::from(random_bytes.random_bytes_32()),
+            Fp256::from(random_bytes.random_bytes_32()),
+            Fp256::from(random_bytes.random_bytes_32()),
+            Fp256::from(random_bytes.random_bytes_32()),
+            Fp256::from(random_bytes.random_bytes_32()),
+            Fp256::from(random_bytes.random_bytes_32()),
+            Fp256::from(random_bytes.random_bytes_32()),
+            Fp256::from(random_bytes.random_bytes_32()),
+        )
+        .map(&|fp256| fp256.to_monty()),
+    )
+}
+
+#[derive(Derivative, Debug, Clone, Copy)]
+#[derivative(PartialEq, Hash, Eq)]
+pub struct PublicKey {
+    x: [u8; 32],
+    y: [u8; 32],
+    #[derivative(Hash = "ignore", PartialEq = "ignore")]
+    _internal_key: internal::PublicKey<Monty256>,
+}
+
+impl Hashable for PublicKey {
+    fn to_bytes(&self) -> ByteVector {
+        self._internal_key.to_bytes()
+    }
+}
+
+impl PublicKey {
+    pub const ENCODED_SIZE_BYTES: usize = Monty256::ENCODED_SIZE_BYTES * 2;
+    pub const COMPRESSED_SIZE_BYTES: usize = Monty256::ENCODED_SIZE_BYTES + 1;
+
+    fn try_from(internal_key: &internal::PublicKey<Monty256>) -> Result<PublicKey> {
+        Ok(internal_key
+            .to_byte_vectors_32()
+            .map(|(x, y)| PublicKey {
+                x,
+                y,
+                _internal_key: *internal_key,
+            })
+            .ok_or(internal::homogeneouspoint::PointErr::ZeroPoint)?)
     }
 
     pub fn new(
@@ -982,6 +1891,50 @@ impl PublicKey {
         (&self.x, &self.y)
     }
 
+    ///SEC1 style compressed encoding of the key: 0x02 or 0x03 depending on the sign of y, followed by x.
+    pub fn to_compressed(&self) -> [u8; PublicKey::COMPRESSED_SIZE_BYTES] {
+        let compressed = self
+            ._internal_key
+            .value
+            .to_compressed()
+            .expect("Developer error: a PublicKey is never the zero point");
+        let mut dest = [0u8; PublicKey::COMPRESSED_SIZE_BYTES];
+        dest.copy_from_slice(&compressed);
+        dest
+    }
+
+    ///Decode a key produced by `to_compressed`, recovering y from the curve equation.
+    pub fn from_compressed(bytes: &[u8]) -> Result<PublicKey> {
+        if bytes.len() == PublicKey::COMPRESSED_SIZE_BYTES {
+            let point = HomogeneousPoint::<Monty256>::from_compressed(bytes)?;
+            PublicKey::try_from(&internal::PublicKey::new(point))
+        } else {
+            Err(RecryptErr::InputWrongSize(
+                "PublicKey",
+                PublicKey::COMPRESSED_SIZE_BYTES,
+            ))
+        }
+    }
+
+    /// Append this key to a wire encoding, compressed if `compact` is set.
+    fn write_wire(&self, writer: WireWriter, compact: bool) -> WireWriter {
+        if compact {
+            writer.field(&self.to_compressed())
+        } else {
+            let (x, y) = self.bytes_x_y();
+            writer.field_pair(x, y)
+        }
+    }
+
+    /// Read a key written by `write_wire`.
+    fn read_wire(reader: &mut WireReader, compact: bool) -> Result<PublicKey> {
+        if compact {
+            Ok(reader.read(PublicKey::from_compressed)?)
+        } else {
+            Ok(reader.read_pair(Monty256::ENCODED_SIZE_BYTES, PublicKey::new_from_slice)?)
+        }
+    }
+
     ///Augment the PublicKey so that messages encrypted to that key cannot be decrypted by this PublicKey's PrivateKey.
     ///This can be useful if you want to force delegation via transform. See `TransformKey.augment`.
     ///Note that by augmenting a PublicKey you're committing to augmenting all `TransformKeys` that are created from
@@ -1039,6 +1992,133 @@ impl PrivateKey {
 
 bytes_eq_and_hash!(PrivateKey);
 
+/// The result of rotating a `PrivateKey`, from `KeyRotation::new` or `KeyRotationOps::rotate_private_key`.
+///
+/// `rotated_private_key` augmented with `augmentation_factor` (`augment_plus`) is the old private key, so the
+/// public key of the rotated key augmented with the public key of the factor is the old public key.
+#[derive(Debug, Clone)]
+pub struct KeyRotation {
+    rotated_private_key: PrivateKey,
+    augmentation_factor: PrivateKey,
+}
+
+impl KeyRotation {
+    /// Rotate `private_key` to `new_private_key`, which should be freshly generated with `random_private_key`.
+    pub fn new(private_key: &PrivateKey, new_private_key: &PrivateKey) -> KeyRotation {
+        KeyRotation {
+            rotated_private_key: new_private_key.clone(),
+            augmentation_factor: private_key.augment_minus(new_private_key),
+        }
+    }
+
+    pub fn rotated_private_key(&self) -> &PrivateKey {
+        &self.rotated_private_key
+    }
+
+    pub fn augmentation_factor(&self) -> &PrivateKey {
+        &self.augmentation_factor
+    }
+
+    /// Augment a `TransformKey` generated from the rotated private key with the augmentation factor, so that it
+    /// transforms values encrypted to the old public key.
+    pub fn augment_transform_key(&self, transform_key: &TransformKey) -> Result<TransformKey> {
+        transform_key.augment(&self.augmentation_factor)
+    }
+}
+
+/// One share of a `PrivateKey`, from `ThresholdOps::split_private_key`.
+///
+/// The index identifies the share when partial decryptions are combined, so it must be kept with the key.
+#[derive(Debug, Clone)]
+pub struct PrivateKeyShare {
+    index: u32,
+    key: PrivateKey,
+}
+
+impl PrivateKeyShare {
+    pub fn new(index: u32, key: PrivateKey) -> PrivateKeyShare {
+        PrivateKeyShare { index, key }
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn key(&self) -> &PrivateKey {
+        &self.key
+    }
+}
+
+/// The part of the decryption of an `EncryptedValue` computed with one `PrivateKeyShare`, from
+/// `ThresholdOps::decrypt_partial`.
+///
+/// It holds the points of the value that decryption multiplies by the private key, multiplied by the share instead:
+/// one point for a value that has not been transformed, and two for one that has.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PartialDecryption {
+    index: u32,
+    points: Vec<PublicKey>,
+}
+
+impl PartialDecryption {
+    pub fn new(index: u32, points: Vec<PublicKey>) -> PartialDecryption {
+        PartialDecryption { index, points }
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn points(&self) -> &[PublicKey] {
+        &self.points
+    }
+}
+
+/// An `EncryptedValue` bound to the recrypt `PublicKey` of its sender, from `SenderAuthOps::encrypt_authenticated`.
+///
+/// `sender_signature` is a Schnorr signature by `sender_public_key` over `sender_public_key` and the fields of the
+/// value that transforms leave alone, so it is the same for the value and for every transform of it.
+/// `ephemeral_signature` signs the same bytes under the ephemeral public key of the value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuthenticatedEncryptedValue {
+    encrypted_value: EncryptedValue,
+    sender_public_key: PublicKey,
+    sender_signature: SchnorrSignature,
+    ephemeral_signature: SchnorrSignature,
+}
+
+impl AuthenticatedEncryptedValue {
+    pub fn new(
+        encrypted_value: EncryptedValue,
+        sender_public_key: PublicKey,
+        sender_signature: SchnorrSignature,
+        ephemeral_signature: SchnorrSignature,
+    ) -> AuthenticatedEncryptedValue {
+        AuthenticatedEncryptedValue {
+            encrypted_value,
+            sender_public_key,
+            sender_signature,
+            ephemeral_signature,
+        }
+    }
+
+    pub fn encrypted_value(&self) -> &EncryptedValue {
+        &self.encrypted_value
+    }
+
+    pub fn sender_public_key(&self) -> &PublicKey {
+        &self.sender_public_key
+    }
+
+    pub fn sender_signature(&self) -> SchnorrSignature {
+        self.sender_signature
+    }
+
+    pub fn ephemeral_signature(&self) -> SchnorrSignature {
+        self.ephemeral_signature
+    }
+}
+
 impl Hashable32 for PrivateKey {
     fn to_bytes_32(&self) -> [u8; 32] {
         self.bytes
@@ -1093,6 +2173,8 @@ pub(crate) mod test {
 
     pub struct DummyEd25519;
     impl Ed25519Signing for DummyEd25519 {
+        type Key = SigningKeypair;
+
         fn sign<T: Hashable>(&self, _t: &T, _signing_keypair: &SigningKeypair) -> Ed25519Signature {
             Ed25519Signature::new([0; 64])
         }
@@ -1126,14 +2208,78 @@ pub(crate) mod test {
         let api = Recrypt::default();
         Recrypt::<Sha256, S, R> {
             random_bytes: random_bytes.unwrap_or_default(),
-            schnorr_signing: internal::schnorr::SchnorrSign::new_256(),
+            schnorr_signing: internal::schnorr::SchnorrSign::new_256(Sha256),
             sha_256: api.sha_256,
             ed25519,
             pairing: api.pairing,
             curve_points: api.curve_points,
+            protocol_version: ProtocolVersion::Legacy,
+            transform_policy: TransformPolicy::default(),
+        }
+    }
+
+    /// Signs with a keypair it holds itself, the way a client for an HSM or a remote signing service would. It is
+    /// only given the public key to sign for.
+    struct RemoteEd25519 {
+        remote_keypair: SigningKeypair,
+    }
+    impl Ed25519Signing for RemoteEd25519 {
+        type Key = PublicSigningKey;
+
+        fn sign<T: Hashable>(&self, t: &T, public_key: &PublicSigningKey) -> Ed25519Signature {
+            assert_eq!(*public_key, self.remote_keypair.public_key());
+            Ed25519.sign(t, &self.remote_keypair)
+        }
+
+        fn verify<T: Hashable>(
+            &self,
+            t: &T,
+            signature: &Ed25519Signature,
+            public_key: &PublicSigningKey,
+        ) -> bool {
+            Ed25519.verify(t, signature, public_key)
         }
     }
 
+    #[test]
+    fn roundtrip_with_other_hash_and_remote_signer() {
+        let remote_keypair = Recrypt::new().generate_ed25519_key_pair();
+        let remote_public_key = remote_keypair.public_key();
+        let api = Recrypt::new_with_backends(
+            DigestHash::<sha2::Sha512_256>::with_domain(b"recrypt test suite"),
+            RemoteEd25519 { remote_keypair },
+        );
+        let pt = api.gen_plaintext();
+        let (private_key, public_key) = api.generate_key_pair().unwrap();
+        let (device_private_key, device_public_key) = api.generate_key_pair().unwrap();
+        let encrypted_msg = api.encrypt(&pt, &public_key, &remote_public_key).unwrap();
+        assert_eq!(
+            api.decrypt(encrypted_msg.clone(), &private_key).unwrap(),
+            pt
+        );
+        // the auth hash was computed with the other hash, so the default suite rejects it
+        assert!(
+            Recrypt::new()
+                .decrypt(encrypted_msg.clone(), &private_key)
+                .is_err()
+        );
+
+        let transform_key = api
+            .generate_transform_key(&private_key, &device_public_key, &remote_public_key)
+            .unwrap();
+        let transformed_msg = api
+            .transform(encrypted_msg, transform_key, &remote_public_key)
+            .unwrap();
+        assert_eq!(
+            api.decrypt(transformed_msg, &device_private_key).unwrap(),
+            pt
+        );
+
+        let message = vec![1u8, 2u8];
+        let sig = api.schnorr_sign(&private_key, &public_key, &message);
+        assert!(api.schnorr_verify(&public_key, None, &message, sig));
+    }
+
     #[test]
     fn schnorr_signing_roundtrip_augmented() {
         let api = Recrypt::new();
@@ -1155,6 +2301,18 @@ pub(crate) mod test {
         assert!(result);
     }
 
+    #[test]
+    fn v2_schnorr_verification_accepts_legacy_signatures_only_when_asked() {
+        let legacy_api = Recrypt::new();
+        let v2_api = Recrypt::new().with_protocol_version(ProtocolVersion::V2);
+        let (private_key, pub_key) = legacy_api.generate_key_pair().unwrap();
+        let message = vec![1u8, 2u8, 3u8];
+        let legacy_sig = legacy_api.schnorr_sign(&private_key, &pub_key, &message);
+        assert!(!v2_api.schnorr_verify(&pub_key, None, &message, legacy_sig));
+        let v2_or_legacy_api = v2_api.with_legacy_schnorr_verification();
+        assert!(v2_or_legacy_api.schnorr_verify(&pub_key, None, &message, legacy_sig));
+    }
+
     #[test]
     fn public_key_roundtrip_with_internal() {
         let (_, pub_key_api) = Recrypt::new().generate_key_pair().unwrap();
@@ -1335,6 +2493,7 @@ pub(crate) mod test {
             signature: Ed25519Signature::new_from_slice(&hex::decode(
                 "312901e121e0637eb0814b1411ec6772147d5ab2063ae781ec2f227748059ac5d892a6eed7c66e1638649903fe3ecbb9c2b5674e87e9b9c39009a175f2177e0f",
             )?)?,
+            protocol_version: ProtocolVersion::Legacy,
         };
         let priv_key = PrivateKey::new_from_slice(&hex::decode(
             "3f79bb7b435b05321651daefd374cdc681dc06faa65e374e38337b88ca046dea",
@@ -1459,31 +2618,447 @@ pub(crate) mod test {
     }
 
     #[test]
-    fn two_level_transform_roundtrip() {
-        let api = api_with(Some(RandomBytes::default()), DummyEd25519);
+    fn rotated_transform_keys_decrypt_values_encrypted_before_rotation() {
+        let api = Recrypt::new();
         let signing_key = api.generate_ed25519_key_pair();
-
         let pt = api.gen_plaintext();
-        let (group_master_private_key, group_master_public_key) = api.generate_key_pair().unwrap();
-        let (user_master_private_key, user_master_public_key) = api.generate_key_pair().unwrap();
+        let (old_private_key, public_key) = api.generate_key_pair().unwrap();
         let (device_private_key, device_public_key) = api.generate_key_pair().unwrap();
-        let encrypted_msg = api
-            .encrypt(&pt, &group_master_public_key, &signing_key)
-            .unwrap();
+        let encrypted_msg = api.encrypt(&pt, &public_key, &signing_key).unwrap();
 
-        // now create two transform keys. Group -> User -> Device (arrows are the transform keys)
-        let group_to_user_transform_key = api
-            .generate_transform_key(
-                &group_master_private_key,
-                &user_master_public_key,
-                &signing_key,
-            )
+        let rotation = api.rotate_private_key(&old_private_key);
+        assert_ne!(rotation.rotated_private_key(), &old_private_key);
+        let rotated_public_key = api
+            .compute_public_key(rotation.rotated_private_key())
             .unwrap();
-
-        let user_to_device_transform_key = api
-            .generate_transform_key(&user_master_private_key, &device_public_key, &signing_key)
+        let factor_public_key = api
+            .compute_public_key(rotation.augmentation_factor())
             .unwrap();
-
+        assert_eq!(
+            rotated_public_key.augment(&factor_public_key).unwrap(),
+            public_key
+        );
+        // the rotated key on its own can't decrypt what was encrypted before the rotation
+        assert!(
+            api.decrypt(encrypted_msg.clone(), rotation.rotated_private_key())
+                .is_err()
+        );
+
+        let transform_keys = api
+            .rotate_transform_keys(&rotation, &[device_public_key], &signing_key)
+            .unwrap();
+        assert_eq!(transform_keys.len(), 1);
+        let transformed_msg = api
+            .transform(encrypted_msg, transform_keys[0].clone(), &signing_key)
+            .unwrap();
+        let decrypted_pt = api.decrypt(transformed_msg, &device_private_key).unwrap();
+
+        assert_eq!(pt, decrypted_pt)
+    }
+
+    #[test]
+    fn rotate_client_share_of_augmented_keys() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let pt = api.gen_plaintext();
+        let (client_private_key, client_generated_pub) = api.generate_key_pair().unwrap();
+        let (device_private_key, device_public_key) = api.generate_key_pair().unwrap();
+        let (server_private, server_public) = api.generate_key_pair().unwrap();
+        let master_public_key = client_generated_pub.augment(&server_public).unwrap();
+        let encrypted_msg = api.encrypt(&pt, &master_public_key, &signing_key).unwrap();
+
+        // the client's share is replaced and the server folds the factor into its own share
+        let new_client_private_key = api.random_private_key();
+        let rotation = KeyRotation::new(&client_private_key, &new_client_private_key);
+        assert_eq!(rotation.rotated_private_key(), &new_client_private_key);
+        let rotated_server_private = server_private.augment_plus(rotation.augmentation_factor());
+        let rotated_master_public_key = api
+            .compute_public_key(&new_client_private_key)
+            .unwrap()
+            .augment(&api.compute_public_key(&rotated_server_private).unwrap())
+            .unwrap();
+        assert_eq!(rotated_master_public_key, master_public_key);
+
+        let client_to_device_transform_key = api
+            .generate_transform_key(&new_client_private_key, &device_public_key, &signing_key)
+            .unwrap();
+        let augmented_transform_key = client_to_device_transform_key
+            .augment(&rotated_server_private)
+            .unwrap();
+        let transformed_msg = api
+            .transform(encrypted_msg, augmented_transform_key, &signing_key)
+            .unwrap();
+        let decrypted_pt = api.decrypt(transformed_msg, &device_private_key).unwrap();
+
+        assert_eq!(pt, decrypted_pt)
+    }
+
+    #[test]
+    fn decrypt_from_accepts_only_the_sender() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let pt = api.gen_plaintext();
+        let (sender_private_key, sender_public_key) = api.generate_key_pair().unwrap();
+        let (_, other_public_key) = api.generate_key_pair().unwrap();
+        let (recipient_private_key, recipient_public_key) = api.generate_key_pair().unwrap();
+        let authenticated_value = api
+            .encrypt_authenticated(
+                &pt,
+                &recipient_public_key,
+                &sender_private_key,
+                &signing_key,
+            )
+            .unwrap();
+        assert_eq!(authenticated_value.sender_public_key(), &sender_public_key);
+
+        assert_eq!(
+            api.decrypt_from(
+                authenticated_value.clone(),
+                &recipient_private_key,
+                &other_public_key
+            ),
+            Err(RecryptErr::InvalidSenderSignature)
+        );
+        let decrypted_pt = api
+            .decrypt_from(
+                authenticated_value,
+                &recipient_private_key,
+                &sender_public_key,
+            )
+            .unwrap();
+        assert_eq!(pt, decrypted_pt)
+    }
+
+    #[test]
+    fn sender_binding_survives_transforms() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let pt = api.gen_plaintext();
+        let (sender_private_key, sender_public_key) = api.generate_key_pair().unwrap();
+        let (group_private_key, group_public_key) = api.generate_key_pair().unwrap();
+        let (user_private_key, user_public_key) = api.generate_key_pair().unwrap();
+        let (device_private_key, device_public_key) = api.generate_key_pair().unwrap();
+        let authenticated_value = api
+            .encrypt_authenticated(&pt, &group_public_key, &sender_private_key, &signing_key)
+            .unwrap();
+        let group_to_user_transform_key = api
+            .generate_transform_key(&group_private_key, &user_public_key, &signing_key)
+            .unwrap();
+        let user_to_device_transform_key = api
+            .generate_transform_key(&user_private_key, &device_public_key, &signing_key)
+            .unwrap();
+
+        let transformed_to_user = api
+            .transform_authenticated(
+                authenticated_value.clone(),
+                group_to_user_transform_key,
+                &signing_key,
+            )
+            .unwrap();
+        let decrypted_pt = api
+            .decrypt_from(
+                transformed_to_user.clone(),
+                &user_private_key,
+                &sender_public_key,
+            )
+            .unwrap();
+        assert_eq!(pt, decrypted_pt);
+        let transformed_to_device = api
+            .transform_authenticated(
+                transformed_to_user,
+                user_to_device_transform_key,
+                &signing_key,
+            )
+            .unwrap();
+        assert_eq!(
+            transformed_to_device.sender_signature(),
+            authenticated_value.sender_signature()
+        );
+        let decrypted_pt = api
+            .decrypt_from(
+                transformed_to_device,
+                &device_private_key,
+                &sender_public_key,
+            )
+            .unwrap();
+        assert_eq!(pt, decrypted_pt)
+    }
+
+    #[test]
+    fn copied_value_cannot_be_claimed_by_another_sender() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let pt = api.gen_plaintext();
+        let (sender_private_key, _) = api.generate_key_pair().unwrap();
+        let (other_private_key, other_public_key) = api.generate_key_pair().unwrap();
+        let (recipient_private_key, recipient_public_key) = api.generate_key_pair().unwrap();
+        let authenticated_value = api
+            .encrypt_authenticated(
+                &pt,
+                &recipient_public_key,
+                &sender_private_key,
+                &signing_key,
+            )
+            .unwrap();
+
+        // without the ephemeral private key the copier can sign with their own key, but has to keep the ephemeral
+        // signature, which covers the real sender's public key
+        let encrypted_value = authenticated_value.encrypted_value().clone();
+        let copier_signature = api.schnorr_sign(
+            &other_private_key,
+            &other_public_key,
+            &(&other_public_key, &encrypted_value.sender_bound_bytes()),
+        );
+        let claimed_value = AuthenticatedEncryptedValue::new(
+            encrypted_value,
+            other_public_key,
+            copier_signature,
+            authenticated_value.ephemeral_signature(),
+        );
+        assert_eq!(
+            api.decrypt_from(claimed_value, &recipient_private_key, &other_public_key),
+            Err(RecryptErr::InvalidSenderSignature)
+        );
+    }
+
+    #[test]
+    fn ephemeral_key_chosen_to_cancel_the_sender_key_is_rejected() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let (_, sender_public_key) = api.generate_key_pair().unwrap();
+        let (forger_private_key, forger_public_key) = api.generate_key_pair().unwrap();
+        let (recipient_private_key, recipient_public_key) = api.generate_key_pair().unwrap();
+        let (user_private_key, user_public_key) = api.generate_key_pair().unwrap();
+
+        // E = x·G - sender_pk, so sender_pk + E = x·G, whose private key the forger knows
+        let ephemeral_public_key = PublicKey::try_from(&internal::PublicKey::new(
+            forger_public_key._internal_key.value - sender_public_key._internal_key.value,
+        ))
+        .unwrap();
+        let encrypted_value = match api
+            .encrypt(&api.gen_plaintext(), &recipient_public_key, &signing_key)
+            .unwrap()
+        {
+            EncryptedValue::EncryptedOnceValue {
+                encrypted_message,
+                auth_hash,
+                public_signing_key,
+                protocol_version,
+                ..
+            } => {
+                let payload =
+                    internal::EncryptedValue::EncryptedOnce(internal::EncryptedOnceValue {
+                        ephemeral_public_key: ephemeral_public_key._internal_key,
+                        encrypted_message: Fp12Elem::decode(encrypted_message.bytes.to_vec())
+                            .unwrap(),
+                        auth_hash: internal::AuthHash {
+                            bytes: auth_hash.bytes,
+                        },
+                        version: protocol_version,
+                    });
+                let signature = api
+                    .ed25519
+                    .sign(&(&public_signing_key, &payload), &signing_key);
+                EncryptedValue::EncryptedOnceValue {
+                    ephemeral_public_key,
+                    encrypted_message,
+                    auth_hash,
+                    public_signing_key,
+                    signature,
+                    protocol_version,
+                }
+            }
+            _ => panic!("encrypt should produce an EncryptedOnceValue"),
+        };
+        let message = (&sender_public_key, &encrypted_value.sender_bound_bytes());
+        let forged_signature = api.schnorr_sign(&forger_private_key, &forger_public_key, &message);
+        let forged_value = AuthenticatedEncryptedValue::new(
+            encrypted_value,
+            sender_public_key,
+            forged_signature,
+            forged_signature,
+        );
+        let recipient_to_user_transform_key = api
+            .generate_transform_key(&recipient_private_key, &user_public_key, &signing_key)
+            .unwrap();
+        let transformed_value = api
+            .transform_authenticated(forged_value, recipient_to_user_transform_key, &signing_key)
+            .unwrap();
+        assert_eq!(
+            api.decrypt_from(transformed_value, &user_private_key, &sender_public_key),
+            Err(RecryptErr::InvalidSenderSignature)
+        );
+    }
+
+    #[test]
+    fn transform_policy_limits_hops_and_signers() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let other_signing_key = api.generate_ed25519_key_pair();
+        let policy = TransformPolicy::new()
+            .with_max_hops(1)
+            .with_allowed_signing_keys(1, vec![signing_key.public_key()]);
+        let policy_api = Recrypt::new().with_transform_policy(policy.clone());
+        assert_eq!(policy_api.transform_policy(), &policy);
+        let pt = api.gen_plaintext();
+        let (group_private_key, group_public_key) = api.generate_key_pair().unwrap();
+        let (user_private_key, user_public_key) = api.generate_key_pair().unwrap();
+        let (device_private_key, device_public_key) = api.generate_key_pair().unwrap();
+        let encrypted_msg = api.encrypt(&pt, &group_public_key, &signing_key).unwrap();
+        let group_to_user_transform_key = api
+            .generate_transform_key(&group_private_key, &user_public_key, &signing_key)
+            .unwrap();
+        let user_to_device_transform_key = api
+            .generate_transform_key(&user_private_key, &device_public_key, &signing_key)
+            .unwrap();
+
+        assert_eq!(
+            policy_api.transform(
+                encrypted_msg.clone(),
+                group_to_user_transform_key.clone(),
+                &other_signing_key
+            ),
+            Err(RecryptErr::SigningKeyNotAllowed(1))
+        );
+        let transformed_to_user = policy_api
+            .transform(encrypted_msg, group_to_user_transform_key, &signing_key)
+            .unwrap();
+        assert_eq!(
+            policy_api.decrypt(transformed_to_user.clone(), &user_private_key),
+            Ok(pt.clone())
+        );
+        assert_eq!(
+            policy_api.transform(
+                transformed_to_user.clone(),
+                user_to_device_transform_key.clone(),
+                &signing_key
+            ),
+            Err(RecryptErr::TooManyTransforms(2, 1))
+        );
+
+        // a transform that doesn't enforce the policy can go further, but decrypting under the policy fails
+        let transformed_to_device = api
+            .transform(
+                transformed_to_user,
+                user_to_device_transform_key,
+                &signing_key,
+            )
+            .unwrap();
+        assert_eq!(
+            policy_api.decrypt(transformed_to_device.clone(), &device_private_key),
+            Err(RecryptErr::TooManyTransforms(2, 1))
+        );
+        assert_eq!(
+            api.decrypt(transformed_to_device, &device_private_key),
+            Ok(pt)
+        );
+    }
+
+    #[test]
+    fn transform_policy_is_checked_before_transforming() {
+        let api = Recrypt::new().with_protocol_version(ProtocolVersion::V2);
+        let signing_key = api.generate_ed25519_key_pair();
+        let other_signing_key = api.generate_ed25519_key_pair();
+        let (master_priv, master_pub) = api.generate_key_pair().unwrap();
+        let (_, user_pub) = api.generate_key_pair().unwrap();
+        // transforming would fail, since the key and the value have different versions
+        let legacy_value = Recrypt::new()
+            .encrypt(&api.gen_plaintext(), &master_pub, &signing_key)
+            .unwrap();
+        let master_to_user = api
+            .generate_transform_key(&master_priv, &user_pub, &signing_key)
+            .unwrap();
+
+        let hops_api =
+            Recrypt::new().with_transform_policy(TransformPolicy::new().with_max_hops(0));
+        assert_eq!(
+            hops_api.transform(legacy_value.clone(), master_to_user.clone(), &signing_key),
+            Err(RecryptErr::TooManyTransforms(1, 0))
+        );
+        let signers_api = Recrypt::new().with_transform_policy(
+            TransformPolicy::new().with_allowed_signing_keys(1, vec![signing_key.public_key()]),
+        );
+        assert_eq!(
+            signers_api
+                .transform_batch(
+                    std::slice::from_ref(&legacy_value),
+                    master_to_user.clone(),
+                    &other_signing_key
+                )
+                .unwrap(),
+            vec![Err(RecryptErr::SigningKeyNotAllowed(1))]
+        );
+        assert!(matches!(
+            signers_api.transform(legacy_value, master_to_user, &signing_key),
+            Err(RecryptErr::ProtocolVersionMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn transform_policy_rejects_cycles() {
+        let api = Recrypt::new();
+        let policy_api =
+            Recrypt::new().with_transform_policy(TransformPolicy::new().with_cycles_rejected());
+        let signing_key = api.generate_ed25519_key_pair();
+        let pt = api.gen_plaintext();
+        let (first_private_key, first_public_key) = api.generate_key_pair().unwrap();
+        let (second_private_key, second_public_key) = api.generate_key_pair().unwrap();
+        let encrypted_msg = api.encrypt(&pt, &first_public_key, &signing_key).unwrap();
+        let first_to_second_transform_key = api
+            .generate_transform_key(&first_private_key, &second_public_key, &signing_key)
+            .unwrap();
+        let second_to_first_transform_key = api
+            .generate_transform_key(&second_private_key, &first_public_key, &signing_key)
+            .unwrap();
+
+        // around the cycle once, back to the first key
+        let transformed_msg = [
+            first_to_second_transform_key.clone(),
+            second_to_first_transform_key,
+        ]
+        .into_iter()
+        .fold(encrypted_msg, |value, transform_key| {
+            policy_api
+                .transform(value, transform_key, &signing_key)
+                .unwrap()
+        });
+        assert_eq!(
+            policy_api.decrypt(transformed_msg.clone(), &first_private_key),
+            Ok(pt)
+        );
+        // the second lap repeats the first transform key
+        assert_eq!(
+            policy_api.transform(transformed_msg, first_to_second_transform_key, &signing_key),
+            Err(RecryptErr::TransformCycle)
+        );
+    }
+
+    #[test]
+    fn two_level_transform_roundtrip() {
+        let api = api_with(Some(RandomBytes::default()), DummyEd25519);
+        let signing_key = api.generate_ed25519_key_pair();
+
+        let pt = api.gen_plaintext();
+        let (group_master_private_key, group_master_public_key) = api.generate_key_pair().unwrap();
+        let (user_master_private_key, user_master_public_key) = api.generate_key_pair().unwrap();
+        let (device_private_key, device_public_key) = api.generate_key_pair().unwrap();
+        let encrypted_msg = api
+            .encrypt(&pt, &group_master_public_key, &signing_key)
+            .unwrap();
+
+        // now create two transform keys. Group -> User -> Device (arrows are the transform keys)
+        let group_to_user_transform_key = api
+            .generate_transform_key(
+                &group_master_private_key,
+                &user_master_public_key,
+                &signing_key,
+            )
+            .unwrap();
+
+        let user_to_device_transform_key = api
+            .generate_transform_key(&user_master_private_key, &device_public_key, &signing_key)
+            .unwrap();
+
         let transformed_to_user = api
             .transform(encrypted_msg, group_to_user_transform_key, &signing_key)
             .unwrap();
@@ -1623,7 +3198,7 @@ pub(crate) mod test {
 
     #[test]
     fn private_key_roundtrip_json_bytes() {
-        // we don't have full serde support today for `PrivateKey`, but here's an example of use serde_json directly on the bytes
+        // without the `serde` feature, serde_json can still be used directly on the bytes of a `PrivateKey`
         let r = Recrypt::new();
         let (privk, _) = r.generate_key_pair().unwrap();
         // serialize to string and json "bytes"
@@ -1648,7 +3223,7 @@ pub(crate) mod test {
 
     #[test]
     fn public_key_roundtrip_json_bytes() {
-        // we don't have full serde support today for `PublicKey`, but here's an example of use serde_json directly on the bytes
+        // without the `serde` feature, serde_json can still be used directly on the bytes of a `PublicKey`
         let r = Recrypt::new();
         let (_, pubk) = r.generate_key_pair().unwrap();
         // serialize to json "bytes"
@@ -1707,6 +3282,602 @@ pub(crate) mod test {
         Ok(())
     }
 
+    #[test]
+    fn encrypted_value_wire_roundtrip() {
+        let api = api_with(Some(RandomBytes::default()), DummyEd25519);
+        let signing_key = api.generate_ed25519_key_pair();
+        let pt = api.gen_plaintext();
+        let (master_priv, master_pub) = api.generate_key_pair().unwrap();
+        let (user_priv, user_pub) = api.generate_key_pair().unwrap();
+        let (_, device_pub) = api.generate_key_pair().unwrap();
+
+        let encrypted = api.encrypt(&pt, &master_pub, &signing_key).unwrap();
+        let bytes = encrypted.to_wire_bytes();
+        assert_eq!(
+            bytes[..2],
+            [wire::WIRE_VERSION, wire::TAG_ENCRYPTED_ONCE_VALUE_256]
+        );
+        assert_eq!(EncryptedValue::from_wire_bytes(&bytes).unwrap(), encrypted);
+
+        let master_to_user = api
+            .generate_transform_key(&master_priv, &user_pub, &signing_key)
+            .unwrap();
+        let user_to_device = api
+            .generate_transform_key(&user_priv, &device_pub, &signing_key)
+            .unwrap();
+        let transformed = api
+            .transform(encrypted, master_to_user, &signing_key)
+            .and_then(|value| api.transform(value, user_to_device, &signing_key))
+            .unwrap();
+        let bytes = transformed.to_wire_bytes();
+        assert_eq!(
+            bytes[..2],
+            [wire::WIRE_VERSION, wire::TAG_TRANSFORMED_VALUE_256]
+        );
+        assert_eq!(
+            EncryptedValue::from_wire_bytes(&bytes).unwrap(),
+            transformed
+        );
+    }
+
+    #[test]
+    fn encrypted_value_from_wire_bytes_is_strict() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let (_, pub_key) = api.generate_key_pair().unwrap();
+        let bytes = api
+            .encrypt(&api.gen_plaintext(), &pub_key, &signing_key)
+            .unwrap()
+            .to_wire_bytes();
+        let is_decode_failure = |bytes: &[u8]| {
+            matches!(
+                EncryptedValue::from_wire_bytes(bytes),
+                Err(RecryptErr::DecodeFailure(_))
+            )
+        };
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(is_decode_failure(&trailing));
+        assert!(is_decode_failure(&bytes[..bytes.len() - 1]));
+        assert!(is_decode_failure(&[]));
+
+        let mut bad_version = bytes.clone();
+        bad_version[0] = wire::WIRE_VERSION + 1;
+        assert!(is_decode_failure(&bad_version));
+
+        let mut bad_tag = bytes.clone();
+        bad_tag[1] = wire::TAG_TRANSFORM_KEY_256;
+        assert!(is_decode_failure(&bad_tag));
+
+        // a 480-bit value is never accepted by the 256-bit API
+        bad_tag[1] = wire::TAG_ENCRYPTED_ONCE_VALUE_480;
+        assert!(is_decode_failure(&bad_tag));
+
+        // shrink the length prefix of the public key field
+        let mut bad_length = bytes;
+        bad_length[5] -= 1;
+        assert!(is_decode_failure(&bad_length));
+    }
+
+    #[test]
+    fn transformed_value_without_blocks_is_rejected() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let (_, pub_key) = api.generate_key_pair().unwrap();
+        let encrypted = api
+            .encrypt(&api.gen_plaintext(), &pub_key, &signing_key)
+            .unwrap();
+        if let EncryptedValue::EncryptedOnceValue {
+            ephemeral_public_key,
+            encrypted_message,
+            auth_hash,
+            public_signing_key,
+            signature,
+            ..
+        } = encrypted
+        {
+            let (x, y) = ephemeral_public_key.bytes_x_y();
+            let bytes = WireWriter::new(wire::TAG_TRANSFORMED_VALUE_256)
+                .field_pair(x, y)
+                .field(encrypted_message.bytes())
+                .field(auth_hash.bytes())
+                .count(0)
+                .field(public_signing_key.bytes())
+                .field(signature.bytes())
+                .finish();
+            assert!(matches!(
+                EncryptedValue::from_wire_bytes(&bytes),
+                Err(RecryptErr::DecodeFailure(DecodeErr::BytesInvalid { .. }))
+            ));
+        } else {
+            panic!("encrypt should produce an EncryptedOnceValue")
+        }
+    }
+
+    #[test]
+    fn v2_values_transform_and_decrypt_with_either_version() {
+        let api = Recrypt::new().with_protocol_version(ProtocolVersion::V2);
+        let legacy_api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let (master_priv, master_pub) = api.generate_key_pair().unwrap();
+        let (user_priv, user_pub) = api.generate_key_pair().unwrap();
+        let pt = api.gen_plaintext();
+        let encrypted = api.encrypt(&pt, &master_pub, &signing_key).unwrap();
+        let master_to_user = api
+            .generate_transform_key(&master_priv, &user_pub, &signing_key)
+            .unwrap();
+        assert_eq!(master_to_user.protocol_version(), ProtocolVersion::V2);
+        let transformed = api
+            .transform(encrypted.clone(), master_to_user, &signing_key)
+            .unwrap();
+        assert!(matches!(
+            transformed,
+            EncryptedValue::TransformedValue {
+                protocol_version: ProtocolVersion::V2,
+                ..
+            }
+        ));
+        // decryption follows the version of the value, not of the Recrypt
+        assert_eq!(legacy_api.decrypt(encrypted, &master_priv).unwrap(), pt);
+        assert_eq!(legacy_api.decrypt(transformed, &user_priv).unwrap(), pt);
+        assert_ne!(
+            api.derive_symmetric_key(&pt).bytes(),
+            legacy_api.derive_symmetric_key(&pt).bytes()
+        );
+    }
+
+    #[test]
+    fn transform_keys_only_transform_values_of_their_version() {
+        let api = Recrypt::new().with_protocol_version(ProtocolVersion::V2);
+        let legacy_api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let (master_priv, master_pub) = api.generate_key_pair().unwrap();
+        let (_, user_pub) = api.generate_key_pair().unwrap();
+        let legacy_value = legacy_api
+            .encrypt(&api.gen_plaintext(), &master_pub, &signing_key)
+            .unwrap();
+        let master_to_user = api
+            .generate_transform_key(&master_priv, &user_pub, &signing_key)
+            .unwrap();
+        assert!(matches!(
+            api.transform(legacy_value, master_to_user, &signing_key),
+            Err(RecryptErr::ProtocolVersionMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn relabeling_the_version_breaks_the_signature() {
+        let api = Recrypt::new().with_protocol_version(ProtocolVersion::V2);
+        let signing_key = api.generate_ed25519_key_pair();
+        let (priv_key, pub_key) = api.generate_key_pair().unwrap();
+        let relabeled = match api
+            .encrypt(&api.gen_plaintext(), &pub_key, &signing_key)
+            .unwrap()
+        {
+            EncryptedValue::EncryptedOnceValue {
+                ephemeral_public_key,
+                encrypted_message,
+                auth_hash,
+                public_signing_key,
+                signature,
+                ..
+            } => EncryptedValue::EncryptedOnceValue {
+                ephemeral_public_key,
+                encrypted_message,
+                auth_hash,
+                public_signing_key,
+                signature,
+                protocol_version: ProtocolVersion::Legacy,
+            },
+            _ => panic!("encrypt should produce an EncryptedOnceValue"),
+        };
+        assert!(matches!(
+            api.decrypt(relabeled, &priv_key),
+            Err(RecryptErr::InvalidEncryptedMessageSignature(_))
+        ));
+    }
+
+    #[test]
+    fn v2_wire_roundtrip() {
+        let api = Recrypt::new().with_protocol_version(ProtocolVersion::V2);
+        let signing_key = api.generate_ed25519_key_pair();
+        let (priv_key, pub_key) = api.generate_key_pair().unwrap();
+        let encrypted = api
+            .encrypt(&api.gen_plaintext(), &pub_key, &signing_key)
+            .unwrap();
+        let bytes = encrypted.to_compact_wire_bytes();
+        assert_eq!(
+            bytes[1],
+            wire::versioned_tag(
+                wire::TAG_ENCRYPTED_ONCE_VALUE_256,
+                true,
+                ProtocolVersion::V2
+            )
+        );
+        assert_eq!(EncryptedValue::from_wire_bytes(&bytes).unwrap(), encrypted);
+
+        let transform_key = api
+            .generate_transform_key(&priv_key, &pub_key, &signing_key)
+            .unwrap();
+        let decoded = TransformKey::from_wire_bytes(&transform_key.to_wire_bytes()).unwrap();
+        assert_eq!(decoded.protocol_version(), ProtocolVersion::V2);
+        assert_eq!(decoded, transform_key);
+    }
+
+    #[test]
+    fn transform_key_wire_roundtrip() {
+        let tk = good_transform_key();
+        let bytes = tk.to_wire_bytes();
+        assert_eq!(TransformKey::from_wire_bytes(&bytes).unwrap(), tk);
+
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(matches!(
+            TransformKey::from_wire_bytes(&trailing),
+            Err(RecryptErr::DecodeFailure(_))
+        ));
+    }
+
+    #[test]
+    fn decrypt_batch_returns_result_per_value() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let (master_priv, master_pub) = api.generate_key_pair().unwrap();
+        let (user_priv, user_pub) = api.generate_key_pair().unwrap();
+        let master_to_user = api
+            .generate_transform_key(&master_priv, &user_pub, &signing_key)
+            .unwrap();
+
+        let plaintexts = [
+            api.gen_plaintext(),
+            api.gen_plaintext(),
+            api.gen_plaintext(),
+        ];
+        let once = api
+            .encrypt(&plaintexts[0], &user_pub, &signing_key)
+            .unwrap();
+        let transformed = api
+            .transform(
+                api.encrypt(&plaintexts[2], &master_pub, &signing_key)
+                    .unwrap(),
+                master_to_user,
+                &signing_key,
+            )
+            .unwrap();
+        // a value whose signature doesn't verify, in the middle of the batch
+        let bad_value = match api
+            .encrypt(&plaintexts[1], &user_pub, &signing_key)
+            .unwrap()
+        {
+            EncryptedValue::EncryptedOnceValue {
+                ephemeral_public_key,
+                encrypted_message,
+                auth_hash,
+                public_signing_key,
+                ..
+            } => EncryptedValue::EncryptedOnceValue {
+                ephemeral_public_key,
+                encrypted_message,
+                auth_hash,
+                public_signing_key,
+                signature: Ed25519Signature::new([0; 64]),
+                protocol_version: ProtocolVersion::Legacy,
+            },
+            _ => panic!("encrypt should produce an EncryptedOnceValue"),
+        };
+        let values = vec![once.clone(), bad_value, transformed.clone()];
+
+        let results = api.decrypt_batch(values, &user_priv);
+        assert_eq!(results.len(), 3);
+        let mut results = results.into_iter();
+        assert_eq!(results.next().unwrap().unwrap(), plaintexts[0]);
+        assert!(matches!(
+            results.next().unwrap(),
+            Err(RecryptErr::InvalidEncryptedMessageSignature(_))
+        ));
+        assert_eq!(results.next().unwrap().unwrap(), plaintexts[2]);
+
+        // the wrong private key fails the same way decrypt does
+        let mut results = api
+            .decrypt_batch(vec![once.clone(), transformed], &master_priv)
+            .into_iter();
+        assert_eq!(
+            results.next().unwrap().unwrap_err(),
+            api.decrypt(once, &master_priv).unwrap_err()
+        );
+        assert!(results.next().unwrap().is_err());
+
+        assert!(api.decrypt_batch(vec![], &user_priv).is_empty());
+    }
+
+    #[test]
+    fn threshold_decrypt_with_any_threshold_shares() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let (priv_key, pub_key) = api.generate_key_pair().unwrap();
+        let shares = api.split_private_key(&priv_key, 3, 5).unwrap();
+        assert_eq!(
+            shares
+                .iter()
+                .map(|share| share.index())
+                .collect::<Vec<u32>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+
+        let plaintext = api.gen_plaintext();
+        let encrypted = api.encrypt(&plaintext, &pub_key, &signing_key).unwrap();
+        let partials: Vec<PartialDecryption> = shares
+            .iter()
+            .map(|share| api.decrypt_partial(&encrypted, share).unwrap())
+            .collect();
+        for chosen in [[0, 1, 2], [4, 1, 3]] {
+            let chosen_partials: Vec<PartialDecryption> =
+                chosen.iter().map(|i| partials[*i].clone()).collect();
+            assert_eq!(
+                api.combine_partial_decryptions(encrypted.clone(), &chosen_partials)
+                    .unwrap(),
+                plaintext
+            );
+        }
+        // below the threshold the interpolated key is wrong
+        assert!(matches!(
+            api.combine_partial_decryptions(encrypted, &partials[..2]),
+            Err(RecryptErr::DecryptFailed(_))
+        ));
+    }
+
+    #[test]
+    fn threshold_decrypt_transformed_value() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let (master_priv, master_pub) = api.generate_key_pair().unwrap();
+        let (user_priv, user_pub) = api.generate_key_pair().unwrap();
+        let master_to_user = api
+            .generate_transform_key(&master_priv, &user_pub, &signing_key)
+            .unwrap();
+        let plaintext = api.gen_plaintext();
+        let transformed = api
+            .transform(
+                api.encrypt(&plaintext, &master_pub, &signing_key).unwrap(),
+                master_to_user,
+                &signing_key,
+            )
+            .unwrap();
+
+        let shares = api.split_private_key(&user_priv, 2, 3).unwrap();
+        let partials: Vec<PartialDecryption> = shares[1..]
+            .iter()
+            .map(|share| api.decrypt_partial(&transformed, share).unwrap())
+            .collect();
+        assert_eq!(partials[0].points().len(), 2);
+        assert_eq!(
+            api.combine_partial_decryptions(transformed, &partials)
+                .unwrap(),
+            plaintext
+        );
+    }
+
+    #[test]
+    fn threshold_rejects_invalid_shares() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let (priv_key, pub_key) = api.generate_key_pair().unwrap();
+        assert!(matches!(
+            api.split_private_key(&priv_key, 0, 3),
+            Err(RecryptErr::InvalidKeyShares(_))
+        ));
+        assert!(matches!(
+            api.split_private_key(&priv_key, 4, 3),
+            Err(RecryptErr::InvalidKeyShares(_))
+        ));
+        // with a threshold of 1 the polynomial is constant, so the share is the key itself
+        let shares = api.split_private_key(&priv_key, 1, 1).unwrap();
+        assert_eq!(shares[0].key(), &priv_key);
+
+        let encrypted = api
+            .encrypt(&api.gen_plaintext(), &pub_key, &signing_key)
+            .unwrap();
+        let partial = api.decrypt_partial(&encrypted, &shares[0]).unwrap();
+        let invalid = [
+            vec![],
+            vec![partial.clone(), partial.clone()],
+            vec![PartialDecryption::new(1, vec![pub_key, pub_key])],
+        ];
+        for partials in invalid {
+            assert!(matches!(
+                api.combine_partial_decryptions(encrypted.clone(), &partials),
+                Err(RecryptErr::InvalidKeyShares(_))
+            ));
+        }
+        assert!(
+            api.combine_partial_decryptions(encrypted, &[partial])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn transform_batch_returns_result_per_value() {
+        let api = Recrypt::new();
+        let signing_key = api.generate_ed25519_key_pair();
+        let (master_priv, master_pub) = api.generate_key_pair().unwrap();
+        let (user_priv, user_pub) = api.generate_key_pair().unwrap();
+        let master_to_user = api
+            .generate_transform_key(&master_priv, &user_pub, &signing_key)
+            .unwrap();
+
+        let plaintexts = [api.gen_plaintext(), api.gen_plaintext()];
+        let mut values: Vec<EncryptedValue> = plaintexts
+            .iter()
+            .map(|pt| api.encrypt(pt, &master_pub, &signing_key).unwrap())
+            .collect();
+        // a value whose signature doesn't verify, in the middle of the batch
+        let bad_value = match values[0].clone() {
+            EncryptedValue::EncryptedOnceValue {
+                ephemeral_public_key,
+                encrypted_message,
+                auth_hash,
+                public_signing_key,
+                ..
+            } => EncryptedValue::EncryptedOnceValue {
+                ephemeral_public_key,
+                encrypted_message,
+                auth_hash,
+                public_signing_key,
+                signature: Ed25519Signature::new([0; 64]),
+                protocol_version: ProtocolVersion::Legacy,
+            },
+            _ => panic!("encrypt should produce an EncryptedOnceValue"),
+        };
+        values.insert(1, bad_value);
+
+        let results = api
+            .transform_batch(&values, master_to_user.clone(), &signing_key)
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        let mut results = results.into_iter();
+        let first = results.next().unwrap().unwrap();
+        assert!(matches!(
+            results.next().unwrap(),
+            Err(RecryptErr::InvalidEncryptedMessageSignature(_))
+        ));
+        let third = results.next().unwrap().unwrap();
+        assert_eq!(api.decrypt(first, &user_priv).unwrap(), plaintexts[0]);
+        assert_eq!(api.decrypt(third, &user_priv).unwrap(), plaintexts[1]);
+
+        let bad_transform_key = TransformKey::new(
+            *master_to_user.ephemeral_public_key(),
+            *master_to_user.to_public_key(),
+            *master_to_user.encrypted_temp_key(),
+            *master_to_user.hashed_temp_key(),
+            *master_to_user.public_signing_key(),
+            Ed25519Signature::new([0; 64]),
+            master_to_user.protocol_version(),
+        );
+        assert!(matches!(
+            api.transform_batch(&values, bad_transform_key, &signing_key),
+            Err(RecryptErr::InvalidTransformKey(_))
+        ));
+    }
+
+    #[test]
+    fn public_key_compressed_roundtrip() {
+        let api = Recrypt::new();
+        let (_, pub_key) = api.generate_key_pair().unwrap();
+        let compressed = pub_key.to_compressed();
+        let (x, y) = pub_key.bytes_x_y();
+        assert_eq!(&compressed[1..], &x[..]);
+        // the prefix records the parity of y
+        assert_eq!(compressed[0], 0x02 | (y[y.len() - 1] & 1));
+        assert_eq!(PublicKey::from_compressed(&compressed).unwrap(), pub_key);
+
+        assert!(matches!(
+            PublicKey::from_compressed(&compressed[1..]),
+            Err(RecryptErr::InputWrongSize(
+                _,
+                PublicKey::COMPRESSED_SIZE_BYTES
+            ))
+        ));
+        let mut bad_prefix = compressed;
+        bad_prefix[0] = 0x04;
+        assert!(matches!(
+            PublicKey::from_compressed(&bad_prefix),
+            Err(RecryptErr::DecodeFailure(_))
+        ));
+    }
+
+    #[test]
+    fn compact_wire_roundtrip() {
+        let api = api_with(Some(RandomBytes::default()), DummyEd25519);
+        let signing_key = api.generate_ed25519_key_pair();
+        let pt = api.gen_plaintext();
+        let (master_priv, master_pub) = api.generate_key_pair().unwrap();
+        let (user_priv, user_pub) = api.generate_key_pair().unwrap();
+
+        let encrypted = api.encrypt(&pt, &master_pub, &signing_key).unwrap();
+        let compact = encrypted.to_compact_wire_bytes();
+        assert_eq!(
+            compact[..2],
+            [
+                wire::WIRE_VERSION,
+                wire::TAG_ENCRYPTED_ONCE_VALUE_256 | wire::COMPACT
+            ]
+        );
+        assert_eq!(
+            compact.len() + PublicKey::ENCODED_SIZE_BYTES - PublicKey::COMPRESSED_SIZE_BYTES,
+            encrypted.to_wire_bytes().len()
+        );
+        assert_eq!(
+            EncryptedValue::from_wire_bytes(&compact).unwrap(),
+            encrypted
+        );
+
+        let master_to_user = api
+            .generate_transform_key(&master_priv, &user_pub, &signing_key)
+            .unwrap();
+        let compact_key = master_to_user.to_compact_wire_bytes();
+        assert!(compact_key.len() < master_to_user.to_wire_bytes().len());
+        assert_eq!(
+            TransformKey::from_wire_bytes(&compact_key).unwrap(),
+            master_to_user
+        );
+
+        let transformed = api
+            .transform(encrypted, master_to_user, &signing_key)
+            .unwrap();
+        let compact = transformed.to_compact_wire_bytes();
+        let decoded = EncryptedValue::from_wire_bytes(&compact).unwrap();
+        assert_eq!(decoded, transformed);
+        assert_eq!(api.decrypt(decoded, &user_priv).unwrap(), pt);
+    }
+
+    #[test]
+    fn compact_value_with_full_block_is_rejected() {
+        let api = api_with(Some(RandomBytes::default()), DummyEd25519);
+        let signing_key = api.generate_ed25519_key_pair();
+        let pt = api.gen_plaintext();
+        let (master_priv, master_pub) = api.generate_key_pair().unwrap();
+        let (_, user_pub) = api.generate_key_pair().unwrap();
+        let master_to_user = api
+            .generate_transform_key(&master_priv, &user_pub, &signing_key)
+            .unwrap();
+        let transformed = api
+            .encrypt(&pt, &master_pub, &signing_key)
+            .and_then(|value| api.transform(value, master_to_user, &signing_key))
+            .unwrap();
+
+        // only flip the outer tag, leaving the nested block in the full format
+        let mut mixed = transformed.to_wire_bytes();
+        mixed[1] |= wire::COMPACT;
+        assert!(matches!(
+            EncryptedValue::from_wire_bytes(&mixed),
+            Err(RecryptErr::DecodeFailure(_))
+        ));
+    }
+
+    #[test]
+    fn transform_block_wire_roundtrip() {
+        let api = Recrypt::new();
+        let pub_key1 = api.generate_key_pair().unwrap().1;
+        let pub_key2 = api.generate_key_pair().unwrap().1;
+        let ee1 = EncryptedTempKey::new(api.gen_plaintext().bytes);
+        let ee2 = EncryptedTempKey::new(api.gen_plaintext().bytes);
+        let tb = TransformBlock::new(&pub_key1, &ee1, &pub_key2, &ee2).unwrap();
+
+        let bytes = tb.to_wire_bytes();
+        assert_eq!(TransformBlock::from_wire_bytes(&bytes).unwrap(), tb);
+        let compact = tb.to_compact_wire_bytes();
+        assert_eq!(TransformBlock::from_wire_bytes(&compact).unwrap(), tb);
+
+        // a public key that isn't on the curve is a decode failure, not an invalid key
+        let mut bad_point = bytes;
+        bad_point[6] ^= 1;
+        assert!(matches!(
+            TransformBlock::from_wire_bytes(&bad_point),
+            Err(RecryptErr::DecodeFailure(_))
+        ));
+    }
+
     #[test]
     fn gen_random_fp12_not_same() {
         let recrypt = Recrypt::new();
@@ -1,7 +1,10 @@
 use crate::internal;
+use crate::internal::ed25519::PublicSigningKey;
+use cfg_if::cfg_if;
 use quick_error::quick_error;
 use rand::rngs::OsRng;
 use rand::rngs::adapter::ReseedingRng;
+use std::collections::BTreeMap;
 
 quick_error! {
     /// Errors generated by the API
@@ -32,12 +35,138 @@ quick_error! {
             source(err)
             from()
         }
+        ProtocolVersionMismatch(err: internal::InternalError){
+            display("The transform key and the encrypted value are for different protocol versions.")
+            source(err)
+        }
+        InvalidKeyShares(reason: &'static str){
+            display("The private key shares or partial decryptions could not be used: {}.", reason)
+        }
+        InvalidSenderSignature {
+            display("The encrypted value was not signed by the expected sender.")
+        }
+        TooManyTransforms(hops: usize, max_hops: usize){
+            display("The value has been transformed {} times, but the transform policy allows at most {}.", hops, max_hops)
+        }
+        SigningKeyNotAllowed(hop: usize){
+            display("The transform policy doesn't allow the signing key of hop {}.", hop)
+        }
+        TransformCycle {
+            display("The value was transformed with the same transform key more than once.")
+        }
     }
 }
 
 pub type DefaultRng = ReseedingRng<rand_chacha::ChaChaCore, OsRng>;
 pub type Result<T> = std::result::Result<T, RecryptErr>;
 
+cfg_if! {
+    // With the "parallel" feature `transform_batch` shares the hashing and signing implementations and
+    // the signing key between threads, so they need to be `Sync`. Without it any type will do.
+    if #[cfg(feature = "parallel")] {
+        /// Implemented by every type which is `Sync` when the `parallel` feature is enabled, and by every type otherwise.
+        pub trait MaybeSync: Sync {}
+        impl<T: Sync> MaybeSync for T {}
+    } else {
+        /// Implemented by every type which is `Sync` when the `parallel` feature is enabled, and by every type otherwise.
+        pub trait MaybeSync {}
+        impl<T> MaybeSync for T {}
+    }
+}
+
+/// Limits on the chain of transforms behind an `EncryptedValue`, which a Recrypt enforces in `transform` and
+/// `decrypt` and their batch versions (see `with_transform_policy`). The default policy has no limits.
+///
+/// Hop 0 is the encryption and hop `n` is the `n`th transform. A value only carries the `public_signing_key` of its
+/// latest hop, so `decrypt` can only check the signer of the last hop. `transform` checks the signer of the value it is
+/// given, and checks the hop it adds and its signer before transforming, so every hop of a chain transformed under the
+/// policy is checked.
+///
+/// The transform blocks of a value don't record who it was transformed to, but each records the `public_key` of the
+/// `TransformKey` that was used. A value that goes around a cycle of delegations uses the same transform key again on
+/// its second lap, which is what `with_cycles_rejected` rejects.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransformPolicy {
+    max_hops: Option<usize>,
+    allowed_signing_keys: BTreeMap<usize, Vec<PublicSigningKey>>,
+    reject_cycles: bool,
+}
+
+impl TransformPolicy {
+    /// A policy with no limits.
+    pub fn new() -> TransformPolicy {
+        TransformPolicy::default()
+    }
+
+    /// Reject values that have been transformed more than `max_hops` times.
+    pub fn with_max_hops(self, max_hops: usize) -> TransformPolicy {
+        TransformPolicy {
+            max_hops: Some(max_hops),
+            ..self
+        }
+    }
+
+    /// Only accept values at hop `hop` whose `public_signing_key` is one of `signing_keys`. Hops without an
+    /// allow-list accept any signing key.
+    pub fn with_allowed_signing_keys(
+        mut self,
+        hop: usize,
+        signing_keys: Vec<PublicSigningKey>,
+    ) -> TransformPolicy {
+        self.allowed_signing_keys.insert(hop, signing_keys);
+        self
+    }
+
+    /// Reject values whose transform blocks repeat a public key.
+    pub fn with_cycles_rejected(self) -> TransformPolicy {
+        TransformPolicy {
+            reject_cycles: true,
+            ..self
+        }
+    }
+
+    /// Check a value with the transform blocks whose public keys are `block_public_keys`, in order, and which was
+    /// last signed by `public_signing_key`.
+    pub(crate) fn check<K: PartialEq>(
+        &self,
+        block_public_keys: &[K],
+        public_signing_key: &PublicSigningKey,
+    ) -> Result<()> {
+        self.check_hop(block_public_keys.len(), public_signing_key)?;
+        self.check_cycles(block_public_keys)
+    }
+
+    /// Check that a value may be at hop `hops` and signed there by `public_signing_key`.
+    pub(crate) fn check_hop(
+        &self,
+        hops: usize,
+        public_signing_key: &PublicSigningKey,
+    ) -> Result<()> {
+        let signer_not_allowed = self
+            .allowed_signing_keys
+            .get(&hops)
+            .is_some_and(|signing_keys| !signing_keys.contains(public_signing_key));
+        match self.max_hops {
+            Some(max_hops) if hops > max_hops => Err(RecryptErr::TooManyTransforms(hops, max_hops)),
+            _ if signer_not_allowed => Err(RecryptErr::SigningKeyNotAllowed(hops)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the transform blocks whose public keys are `block_public_keys` don't go around a cycle.
+    pub(crate) fn check_cycles<K: PartialEq>(&self, block_public_keys: &[K]) -> Result<()> {
+        let repeats_a_key = block_public_keys
+            .iter()
+            .enumerate()
+            .any(|(i, key)| block_public_keys[..i].contains(key));
+        if self.reject_cycles && repeats_a_key {
+            Err(RecryptErr::TransformCycle)
+        } else {
+            Ok(())
+        }
+    }
+}
+
 impl From<internal::InternalError> for RecryptErr {
     fn from(err: internal::InternalError) -> Self {
         match err {
@@ -47,6 +176,9 @@ impl From<internal::InternalError> for RecryptErr {
             }
             internal::InternalError::PointInvalid(p_err) => RecryptErr::InvalidPublicKey(p_err),
             internal::InternalError::CorruptReencryptionKey => RecryptErr::InvalidTransformKey(err),
+            internal::InternalError::ProtocolVersionMismatch => {
+                RecryptErr::ProtocolVersionMismatch(err)
+            }
         }
     }
 }
@@ -0,0 +1,255 @@
+//! `Serialize` and `Deserialize` implementations for the public API types, enabled by the `serde` feature.
+//!
+//! Human-readable formats (JSON, TOML, ...) get a lowercase hex string; binary formats get the raw bytes.
+//! The bytes are the same ones exposed by the types themselves:
+//! - `PublicKey`                              - `x` followed by `y`, see `PublicKey::bytes_x_y`
+//! - `PrivateKey`, `Plaintext`, `SchnorrSignature` - `bytes()`
+//! - `EncryptedValue`, `TransformKey`         - the canonical wire format, see `EncryptedValue::to_wire_bytes`
+//!
+//! Deserializing goes through the same constructors as the rest of the API, so public keys are
+//! checked to be valid curve points and wire encodings are decoded strictly.
+use crate::api_common::{RecryptErr, Result};
+use crate::{api, api_480};
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Accepts a hex string from human-readable formats and raw bytes (or a sequence of bytes) otherwise.
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a hex string or a byte array")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Vec<u8>, E> {
+        hex::decode(v).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<Vec<u8>, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> std::result::Result<Vec<u8>, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+fn serialize_bytes<S: Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&hex::encode(bytes))
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+fn deserialize_bytes<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<u8>, D::Error> {
+    if deserializer.is_human_readable() {
+        // a hex string or a byte array, whichever the input holds
+        deserializer.deserialize_any(BytesVisitor)
+    } else {
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+/// Implement `Serialize`/`Deserialize` for `$t` given how to turn `$value` into bytes and how to build a `$t`
+/// from `$bytes`.
+macro_rules! serde_via_bytes {
+    ($t: ty, |$value: ident| $to_bytes: expr, |$bytes: ident| $from_bytes: expr) => {
+        impl Serialize for $t {
+            fn serialize<S: Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                let $value = self;
+                serialize_bytes(&$to_bytes, serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $t {
+            fn deserialize<D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                let $bytes = &deserialize_bytes(deserializer)?[..];
+                let result: Result<$t> = $from_bytes;
+                result.map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+/// Implement serde for every public API type of one curve.
+macro_rules! serde_for_api {
+    ($api: ident) => {
+        serde_via_bytes!(
+            $api::PublicKey,
+            |key| {
+                let (x, y) = key.bytes_x_y();
+                [&x[..], &y[..]].concat()
+            },
+            |bytes| {
+                if bytes.len() == $api::PublicKey::ENCODED_SIZE_BYTES {
+                    $api::PublicKey::new_from_slice(bytes.split_at(bytes.len() / 2))
+                } else {
+                    Err(RecryptErr::InputWrongSize(
+                        "PublicKey",
+                        $api::PublicKey::ENCODED_SIZE_BYTES,
+                    ))
+                }
+            }
+        );
+        serde_via_bytes!($api::PrivateKey, |key| key.bytes().to_vec(), |bytes| {
+            $api::PrivateKey::new_from_slice(bytes)
+        });
+        serde_via_bytes!($api::Plaintext, |pt| pt.bytes().to_vec(), |bytes| {
+            $api::Plaintext::new_from_slice(bytes)
+        });
+        serde_via_bytes!(
+            $api::SchnorrSignature,
+            |sig| sig.bytes().to_vec(),
+            |bytes| { $api::SchnorrSignature::new_from_slice(bytes) }
+        );
+        serde_via_bytes!(
+            $api::EncryptedValue,
+            |value| value.to_wire_bytes(),
+            |bytes| $api::EncryptedValue::from_wire_bytes(bytes)
+        );
+        serde_via_bytes!($api::TransformKey, |key| key.to_wire_bytes(), |bytes| {
+            $api::TransformKey::from_wire_bytes(bytes)
+        });
+    };
+}
+
+serde_for_api!(api);
+serde_for_api!(api_480);
+
+#[cfg(test)]
+mod test {
+    use crate::api::*;
+    use crate::api_480::{self, CryptoOps as _, Ed25519Ops as _, KeyGenOps as _, Recrypt480};
+
+    #[test]
+    fn public_key_roundtrip_json() {
+        let recrypt = Recrypt::new();
+        let (_, pub_key) = recrypt.generate_key_pair().unwrap();
+        let json = serde_json::to_string(&pub_key).unwrap();
+        let (x, y) = pub_key.bytes_x_y();
+        assert_eq!(json, format!("\"{}{}\"", hex::encode(x), hex::encode(y)));
+        assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), pub_key);
+    }
+
+    #[test]
+    fn public_key_not_on_curve_is_rejected() {
+        let recrypt = Recrypt::new();
+        let (_, pub_key) = recrypt.generate_key_pair().unwrap();
+        let (x, y) = pub_key.bytes_x_y();
+        let mut bad_y = *y;
+        bad_y[31] ^= 1;
+        let json = format!("\"{}{}\"", hex::encode(x), hex::encode(bad_y));
+        assert!(serde_json::from_str::<PublicKey>(&json).is_err());
+
+        let short = format!("\"{}\"", hex::encode(x));
+        assert!(serde_json::from_str::<PublicKey>(&short).is_err());
+    }
+
+    #[test]
+    fn private_key_plaintext_and_signature_roundtrip_json() {
+        let recrypt = Recrypt::new();
+        let (priv_key, pub_key) = recrypt.generate_key_pair().unwrap();
+        let json = serde_json::to_string(&priv_key).unwrap();
+        assert_eq!(serde_json::from_str::<PrivateKey>(&json).unwrap(), priv_key);
+
+        let pt = recrypt.gen_plaintext();
+        let json = serde_json::to_string(&pt).unwrap();
+        assert_eq!(serde_json::from_str::<Plaintext>(&json).unwrap(), pt);
+
+        let sig = recrypt.schnorr_sign(&priv_key, &pub_key, &vec![1u8, 2, 3]);
+        let json = serde_json::to_string(&sig).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SchnorrSignature>(&json).unwrap(),
+            sig
+        );
+    }
+
+    #[test]
+    fn encrypted_value_and_transform_key_roundtrip_json() {
+        let recrypt = Recrypt::new();
+        let signing_keypair = recrypt.generate_ed25519_key_pair();
+        let (priv_key, pub_key) = recrypt.generate_key_pair().unwrap();
+        let (target_priv_key, target_pub_key) = recrypt.generate_key_pair().unwrap();
+        let pt = recrypt.gen_plaintext();
+
+        let encrypted = recrypt.encrypt(&pt, &pub_key, &signing_keypair).unwrap();
+        let json = serde_json::to_string(&encrypted).unwrap();
+        assert_eq!(
+            serde_json::from_str::<EncryptedValue>(&json).unwrap(),
+            encrypted
+        );
+
+        let transform_key = recrypt
+            .generate_transform_key(&priv_key, &target_pub_key, &signing_keypair)
+            .unwrap();
+        let json = serde_json::to_string(&transform_key).unwrap();
+        let transform_key: TransformKey = serde_json::from_str(&json).unwrap();
+
+        let transformed = recrypt
+            .transform(encrypted, transform_key, &signing_keypair)
+            .unwrap();
+        let json = serde_json::to_string(&transformed).unwrap();
+        let transformed: EncryptedValue = serde_json::from_str(&json).unwrap();
+        assert_eq!(recrypt.decrypt(transformed, &target_priv_key).unwrap(), pt);
+    }
+
+    #[test]
+    fn byte_arrays_are_accepted() {
+        let recrypt = Recrypt::new();
+        let (priv_key, _) = recrypt.generate_key_pair().unwrap();
+        let json = serde_json::to_string(&priv_key.bytes()).unwrap();
+        assert_eq!(serde_json::from_str::<PrivateKey>(&json).unwrap(), priv_key);
+    }
+
+    #[test]
+    fn roundtrip_json_480() {
+        let recrypt = Recrypt480::new();
+        let signing_keypair = recrypt.generate_ed25519_key_pair();
+        let (priv_key, pub_key) = recrypt.generate_key_pair().unwrap();
+        let pt = recrypt.gen_plaintext();
+
+        let json = serde_json::to_string(&pub_key).unwrap();
+        assert_eq!(
+            serde_json::from_str::<api_480::PublicKey>(&json).unwrap(),
+            pub_key
+        );
+        let json = serde_json::to_string(&priv_key).unwrap();
+        assert_eq!(
+            serde_json::from_str::<api_480::PrivateKey>(&json).unwrap(),
+            priv_key
+        );
+
+        let encrypted = recrypt.encrypt(&pt, &pub_key, &signing_keypair).unwrap();
+        let json = serde_json::to_string(&encrypted).unwrap();
+        let encrypted: api_480::EncryptedValue = serde_json::from_str(&json).unwrap();
+        assert_eq!(recrypt.decrypt(encrypted, &priv_key).unwrap(), pt);
+
+        // a 256-bit key is not a valid 480-bit key
+        let (_, pub_key_256) = Recrypt::new().generate_key_pair().unwrap();
+        let json = serde_json::to_string(&pub_key_256).unwrap();
+        assert!(serde_json::from_str::<api_480::PublicKey>(&json).is_err());
+    }
+}
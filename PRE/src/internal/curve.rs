@@ -1,7 +1,13 @@
+use crate::internal::bit_repr::BitRepr;
+use crate::internal::field::{ExtensionField, SquareRoot};
+use crate::internal::fixedbase::FixedBaseTable;
 use crate::internal::fp::{fp256_unsafe_from, fp480_unsafe_from};
 use crate::internal::fp2elem::Fp2Elem;
+use crate::internal::hashable::Hashable;
 use crate::internal::homogeneouspoint::HomogeneousPoint;
-use crate::internal::homogeneouspoint::TwistedHPoint;
+use crate::internal::homogeneouspoint::{TwistHasher, TwistedHPoint};
+use crate::internal::pairing::{Pairing, PairingConfig, PreparedG2};
+use gridiron::digits::constant_time_primitives::ConstantSwap;
 use gridiron::fp_256::Monty as Monty256;
 use gridiron::fp_480::Monty as Monty480;
 use lazy_static::lazy_static;
@@ -13,22 +19,63 @@ use num_traits::identities::Zero;
 /// `g1`            - the point which is in Fp2 and is used in the pairing.
 /// `hash_element`  - another point in Fp2 that is used for hashing.
 /// `generator`     - the generator point over FP.
+///
+/// Each point also has a `FixedBaseTable`, which multiplies it by a scalar much faster than the `Mul` impl,
+/// and `g1` has its Miller loop lines prepared, since it is the second argument of most pairings.
+/// These are built along with the points, the first time the `lazy_static` below is used.
+///
+/// `twist_hasher` hashes to points of the same group as `g1` (see `TwistHasher`).
 #[derive(Debug)]
 pub struct CurvePoints<FP> {
     pub generator: HomogeneousPoint<FP>,
+    #[cfg(test)]
     pub g1: TwistedHPoint<FP>,
+    #[cfg(test)]
     pub hash_element: TwistedHPoint<FP>,
+    pub generator_table: FixedBaseTable<HomogeneousPoint<FP>>,
+    pub g1_table: FixedBaseTable<TwistedHPoint<FP>>,
+    pub hash_element_table: FixedBaseTable<TwistedHPoint<FP>>,
+    pub g1_prepared: PreparedG2<FP>,
+    pub twist_hasher: TwistHasher<FP>,
+}
+
+impl<FP> CurvePoints<FP>
+where
+    FP: ExtensionField + PairingConfig + BitRepr + ConstantSwap + SquareRoot + Hashable,
+{
+    fn new(
+        generator: HomogeneousPoint<FP>,
+        g1: TwistedHPoint<FP>,
+        hash_element: TwistedHPoint<FP>,
+        twist_hasher: TwistHasher<FP>,
+    ) -> CurvePoints<FP> {
+        // scalars are elements of FP, so the tables need to cover as many bits as FP has
+        let scalar_bits = FP::zero().to_bits().len();
+        CurvePoints {
+            generator,
+            #[cfg(test)]
+            g1,
+            #[cfg(test)]
+            hash_element,
+            generator_table: FixedBaseTable::new(generator, scalar_bits),
+            g1_table: FixedBaseTable::new(g1, scalar_bits),
+            hash_element_table: FixedBaseTable::new(hash_element, scalar_bits),
+            g1_prepared: Pairing::new().prepare(g1),
+            twist_hasher,
+        }
+    }
 }
 
 lazy_static! {
-    pub static ref FP_256_CURVE_POINTS: CurvePoints<Monty256> = CurvePoints {
+    pub static ref FP_256_CURVE_POINTS: CurvePoints<Monty256> = CurvePoints::new(
+        // generator
+        HomogeneousPoint::new(One::one(), Monty256::from(2u8),),
         // Fixed point in cyclic group G1 (the trace zero subgroup).
         // Start with a point that is on the twisted curve y^2 = x^3 + (3 / (u + 3)).
         // Turns out u + 1 is a valid x, with y = sqrt(x^3 + (3 / (u + 3)).
         // Take (x,y) and multiply by (p + p - r) to get an r-torsion element of the twisted curve over FP2.
         // Compute the anti-trace map of that r-torsion element to get a point in the trace-zero subgroup.
-        generator: HomogeneousPoint::new(One::one(), Monty256::from(2u8),),
-        g1: TwistedHPoint {
+        TwistedHPoint {
             x: Fp2Elem {
                 //"25743265030535080187440590897139396943782163562799308681850377411492232521347",
                 elem1: fp256_unsafe_from("38ea2bf1e67ebb77fae32f89096b96cc8912bf8b02baccca22ccdd9bf6536683").to_monty(),
@@ -52,7 +99,7 @@ lazy_static! {
         // Used to hash integers to a point in FP2
         // Generated by multiplying g1 by the SHA256 hash of the date/time "Mon Feb 19 16:30:21 MST 2018\n",
         // encoded in ASCII/UTF-8, converted to a BigInt.
-        hash_element: TwistedHPoint {
+        TwistedHPoint {
             x: Fp2Elem {
                 //26115920809144023111516349163868890892335785984202627188956566235163006540541
                 elem1: fp256_unsafe_from("39bd165cf62008931544afcc46e7c4067a9c36f3bf6da3f60824042670471afd").to_monty(),
@@ -71,17 +118,29 @@ lazy_static! {
                 //20191379131685497308054970475671582162258136917730106438050079114233947942452
                 elem2: fp256_unsafe_from("2ca3ea64a93d8451fb631c6923796388179f2ef65d72a05dd36af235146cee34").to_monty()
             }
-        }
-    };
+        },
+
+        // Z = 1 is the first candidate that meets the requirements of the SvdW map in section H.1 of RFC 9380.
+        // Each coordinate is reduced from 48 bytes, which is ceil((256 + 128) / 8) for 128 bit security.
+        TwistHasher::new(
+            One::one(),
+            48,
+            // p - r = 254952053719217181996082057820017271814
+            fp256_unsafe_from("00000000000000000000000000000000bfcdfabe288a7c79fe2db811065c2406").to_monty(),
+            // r = 65000549695646603732796438742359905742570406053903786389881062969044166799969
+            fp256_unsafe_from("8fb501e34aa387f9aa6fecb86184dc212e8d8e12f82b39241a2ef45b57ac7261").to_monty()
+        )
+    );
     pub static ref FP_480_CURVE_POINTS: CurvePoints<Monty480> =
-    CurvePoints {
+    CurvePoints::new(
+        // generator
+        HomogeneousPoint::new(Monty480::from(1u8), Monty480::from(2u8),),
         // Fixed point in cyclic group G1 (the trace zero subgroup).
         // Start with a point that is on the twisted curve y^2 = x^3 + (3 / (u + 3)).
         // Turns out u + 1 is a valid x, with y = sqrt(x^3 + (3 / (u + 3)).
         // Take (x,y) and multiply by (p + p - r) to get an r-torsion element of the twisted curve over FP2.
         // Compute the anti-trace map of that r-torsion element to get a point in the trace-zero subgroup.
-        generator: HomogeneousPoint::new(Monty480::from(1u8), Monty480::from(2u8),),
-        g1: TwistedHPoint {
+        TwistedHPoint {
             x: Fp2Elem {
                 // 2836796539847730496121374298065944583953504150765508351672461175175719456840753019328265331693934514908570706456436537314841014056269083482678066
                 elem1: fp480_unsafe_from("e8a1e6285c4061a07c584136417db3e867cf77b12288380e518dab8538228e96abad778e1fe576deff39e638e030933cb8fa5c388fa8b06991ea6332"),
@@ -105,7 +164,7 @@ lazy_static! {
         // Used to hash integers to a point in FP2
         // Generated by multiplying g1 by the SHA256 hash of the date/time "Mon Mar 26 13:33:43 MDT 2018\n",
         // encoded in ASCII/UTF-8, converted to a BigInt.
-        hash_element: TwistedHPoint {
+        TwistedHPoint {
             x: Fp2Elem {
                 // 2755895806273995492284787079187941247738254659153318682910850469784894541505170101984499629579667341065183727568252238059654392233777140703184622
                 elem1: fp480_unsafe_from("e1ff8546060eb7ea879ff685d3f32a39f8e13a3315a48563eb407ccb14d92272f00ba5071e8ec873c585524a6f79bb14025b61046d2371c3fd1846ee"),
@@ -124,8 +183,19 @@ lazy_static! {
                 // 2520811096583992837832885181589663735998061148584558864774875442611283633943850563303274829680791759077833709816521531207043963496532513260928366
                 elem2: fp480_unsafe_from("ceb84d4f5892e3ba709eb41979576c04d7897ce6dc763e867cfe34deecacde57d435ca92c15ecbecebf12c316746b8022da2c4df6ec685a261657d6e")
             }
-        }.map(&|fp| fp.to_monty())
-    };
+        }.map(&|fp| fp.to_monty()),
+
+        // Z = -2 is the first candidate that meets the requirements of the SvdW map in section H.1 of RFC 9380.
+        // Each coordinate is reduced from 84 bytes, which is ceil((480 + 192) / 8) for 192 bit security.
+        TwistHasher::new(
+            -Monty480::from(2u8),
+            84,
+            // p - r = 1766798535725634680378694043542149475661622149011716775599073681340567974
+            fp480_unsafe_from("000000000000000000000000000000000000000000000000000000000000fffe33306854f61cbdae74af385fbe16d0947bdf876847761a73acd311a6").to_monty(),
+            // r = 3121577065842246806003085452055281276803074876175537384188619957989004525299611739143164276204220965332554591187396064132658995685351714167608049
+            fp480_unsafe_from("fffc66640e249d9ec75ad5290b81a85d415797b931258da0d78b58a21c425cdf7cfda2886d3d45c4fd22b1444c47f1427183f46b21df15f4921652f1").to_monty()
+        )
+    );
 }
 
 #[cfg(test)]
@@ -1,7 +1,9 @@
 use crate::api_common::RecryptErr;
 use crate::internal::ByteVector;
+use crate::internal::bytedecoder::DecodeErr;
 use crate::internal::hashable::Hashable;
 use crate::internal::memlock;
+use crate::internal::wire::{self, WireReader, WireWriter};
 use crate::internal::{array_split_64, take_lock};
 use clear_on_drop::clear::Clear;
 use ed25519_dalek;
@@ -120,6 +122,34 @@ impl SigningKeypair {
         &self.bytes
     }
 
+    ///
+    ///Encode the keypair in the canonical, versioned wire format. The result contains the secret key,
+    ///so it should be handled with the same care as the keypair itself.
+    ///
+    pub fn to_wire_bytes(&self) -> ByteVector {
+        WireWriter::new(wire::TAG_SIGNING_KEYPAIR)
+            .field(&self.bytes)
+            .finish()
+    }
+
+    ///
+    ///Decode a keypair produced by `to_wire_bytes`. Fails with `RecryptErr::DecodeFailure` if the encoding
+    ///is malformed or if the public key doesn't match the private key.
+    ///
+    pub fn from_wire_bytes(bytes: &[u8]) -> Result<SigningKeypair, RecryptErr> {
+        let (_, mut reader) = WireReader::new(bytes, &[wire::TAG_SIGNING_KEYPAIR])?;
+        let keypair_bytes = reader.field()?;
+        let keypair = SigningKeypair::from_byte_slice(keypair_bytes).map_err(|err| match err {
+            Ed25519Error::InputWrongSize(required_length, _) => DecodeErr::BytesNotCorrectLength {
+                required_length,
+                bad_bytes: keypair_bytes.to_vec(),
+            },
+            Ed25519Error::PublicKeyInvalid(_) => reader.invalid(err.to_string()),
+        })?;
+        reader.finish()?;
+        Ok(keypair)
+    }
+
     ///
     ///Create a signature by signing over the bytes produced by the hashable instance of `message`.
     ///
@@ -137,6 +167,26 @@ impl<'a> From<&'a SigningKeypair> for PublicSigningKey {
     }
 }
 
+///
+/// A key that an `Ed25519Signing` signs with. It doesn't have to hold the private key, only to know the public key
+/// the signatures verify with.
+///
+pub trait Ed25519SigningKey {
+    fn public_key(&self) -> PublicSigningKey;
+}
+
+impl Ed25519SigningKey for SigningKeypair {
+    fn public_key(&self) -> PublicSigningKey {
+        self.into()
+    }
+}
+
+impl Ed25519SigningKey for PublicSigningKey {
+    fn public_key(&self) -> PublicSigningKey {
+        *self
+    }
+}
+
 impl Drop for SigningKeypair {
     fn drop(&mut self) {
         self.bytes.clear();
@@ -148,6 +198,8 @@ new_bytes_type!(Ed25519Signature, 64);
 pub struct Ed25519;
 
 impl Ed25519Signing for Ed25519 {
+    type Key = SigningKeypair;
+
     fn sign<T: Hashable>(&self, t: &T, signing_key: &SigningKeypair) -> Ed25519Signature {
         use ed25519_dalek::Signer;
         let (priv_key, _) = array_split_64(&signing_key.bytes);
@@ -172,12 +224,74 @@ impl Ed25519Signing for Ed25519 {
             .map(|_| true)
             .unwrap_or(false)
     }
+    fn verify_batch<T: Hashable>(
+        &self,
+        items: &[(&T, &Ed25519Signature, &PublicSigningKey)],
+    ) -> Vec<bool> {
+        if items.is_empty() {
+            return vec![];
+        }
+        // The batch equation only agrees with `verify` when every point is in the prime order subgroup, so
+        // only those items go in the batch and the rest are checked one at a time.
+        let (batchable, single): (Vec<_>, Vec<_>) = items
+            .iter()
+            .enumerate()
+            .partition(|(_, (_, sig, pk))| has_prime_order_points(sig, pk));
+        let messages: Vec<ByteVector> = batchable
+            .iter()
+            .map(|(_, (t, _, _))| t.to_bytes())
+            .collect();
+        let message_slices: Vec<&[u8]> = messages.iter().map(|m| &m[..]).collect();
+        let signatures: Vec<ed25519_dalek::Signature> = batchable
+            .iter()
+            .map(|(_, (_, sig, _))| ed25519_dalek::Signature::from_bytes(&sig.bytes))
+            .collect();
+        let public_keys: Option<Vec<ed25519_dalek::VerifyingKey>> = batchable
+            .iter()
+            .map(|(_, (_, _, pk))| ed25519_dalek::VerifyingKey::from_bytes(&pk.bytes).ok())
+            .collect();
+        let batch_valid = !batchable.is_empty()
+            && public_keys
+                .map(|pks| ed25519_dalek::verify_batch(&message_slices, &signatures, &pks).is_ok())
+                .unwrap_or(false);
+
+        let mut results = vec![false; items.len()];
+        if batch_valid {
+            batchable.iter().for_each(|(i, _)| results[*i] = true);
+        } else {
+            // The batch only tells us that something failed, so find out which ones did.
+            for (i, (t, sig, pk)) in &batchable {
+                results[*i] = self.verify(*t, sig, pk);
+            }
+        }
+        for (i, (t, sig, pk)) in single {
+            results[i] = self.verify(*t, sig, pk);
+        }
+        results
+    }
+}
+
+/// Whether the signature's R and the public key are both points of the prime order subgroup, other than the
+/// identity. The batch equation and `verify` can only disagree on points with a small order component.
+fn has_prime_order_points(signature: &Ed25519Signature, public_key: &PublicSigningKey) -> bool {
+    let (r_bytes, _) = array_split_64(&signature.bytes);
+    [r_bytes, public_key.bytes].iter().all(|bytes| {
+        ed25519_dalek::VerifyingKey::from_bytes(bytes)
+            .map(|point| !point.is_weak() && point.to_edwards().is_torsion_free())
+            .unwrap_or(false)
+    })
 }
 pub trait Ed25519Signing {
+    ///
+    /// The key `sign` signs with. `Ed25519` signs with a `SigningKeypair`, while a signer whose private key is held
+    /// elsewhere, such as in an HSM or a remote signing service, can sign with just the `PublicSigningKey`.
+    ///
+    type Key: Ed25519SigningKey;
+
     ///
     ///Create a signature by signing over the bytes produced by the hashable instance of `t`.
     ///
-    fn sign<T: Hashable>(&self, t: &T, signing_key: &SigningKeypair) -> Ed25519Signature;
+    fn sign<T: Hashable>(&self, t: &T, signing_key: &Self::Key) -> Ed25519Signature;
 
     ///
     /// Use the public_key to verify that the signature was signed by its private key over the hashable bytes of
@@ -189,6 +303,20 @@ pub trait Ed25519Signing {
         signature: &Ed25519Signature,
         public_key: &PublicSigningKey,
     ) -> bool;
+
+    ///
+    /// Verify many `(t, signature, public_key)` triples at once. The result has one entry per triple,
+    /// in the same order, that is true if and only if `verify` would return true for it.
+    ///
+    fn verify_batch<T: Hashable>(
+        &self,
+        items: &[(&T, &Ed25519Signature, &PublicSigningKey)],
+    ) -> Vec<bool> {
+        items
+            .iter()
+            .map(|(t, signature, public_key)| self.verify(*t, signature, public_key))
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -216,6 +344,50 @@ pub(crate) mod test {
         assert!(verify_result);
     }
 
+    #[test]
+    fn verify_batch_reports_each_signature() {
+        let keypair = good_signing_keypair();
+        let public_key = keypair.public_key();
+        let messages = [vec![1u8], vec![2u8], vec![3u8]];
+        let signatures: Vec<Ed25519Signature> =
+            messages.iter().map(|m| Ed25519.sign(m, &keypair)).collect();
+        let items: Vec<(&ByteVector, &Ed25519Signature, &PublicSigningKey)> = messages
+            .iter()
+            .zip(signatures.iter())
+            .map(|(m, sig)| (m, sig, &public_key))
+            .collect();
+        assert_eq!(Ed25519.verify_batch(&items), vec![true, true, true]);
+
+        // swap two signatures so the middle one no longer matches its message
+        let mut bad_items = items.clone();
+        bad_items[1].1 = &signatures[2];
+        assert_eq!(Ed25519.verify_batch(&bad_items), vec![true, false, true]);
+
+        let no_items: [(&ByteVector, &Ed25519Signature, &PublicSigningKey); 0] = [];
+        assert!(Ed25519.verify_batch(&no_items).is_empty());
+    }
+
+    #[test]
+    fn verify_batch_agrees_with_verify_on_small_order_points() {
+        // the identity as both key and R, with s = 0, satisfies the equation for every message
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        let public_key = PublicSigningKey::new(identity);
+        let signature = Ed25519Signature::new(array_concat_32(&identity, &[0u8; 32]));
+        let message = vec![1u8];
+        let single = Ed25519.verify(&message, &signature, &public_key);
+
+        let keypair = good_signing_keypair();
+        let good_public_key = keypair.public_key();
+        let good_signature = Ed25519.sign(&message, &keypair);
+        let items = [
+            (&message, &good_signature, &good_public_key),
+            (&message, &signature, &public_key),
+        ];
+        assert_eq!(Ed25519.verify_batch(&items), vec![true, single]);
+        assert_eq!(Ed25519.verify_batch(&items[1..]), vec![single]);
+    }
+
     #[test]
     fn signing_keypair_from_bytes() {
         let bytes = [0u8; 63];
@@ -228,6 +400,31 @@ pub(crate) mod test {
         assert_eq!(error2, Ed25519Error::PublicKeyInvalid([0u8; 32]))
     }
 
+    #[test]
+    fn signing_keypair_wire_roundtrip() {
+        let keypair = good_signing_keypair();
+        let bytes = keypair.to_wire_bytes();
+        assert_eq!(SigningKeypair::from_wire_bytes(&bytes).unwrap(), keypair);
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(
+            SigningKeypair::from_wire_bytes(&trailing),
+            Err(RecryptErr::DecodeFailure(_))
+        ));
+    }
+
+    #[test]
+    fn signing_keypair_from_wire_bytes_rejects_mismatched_public_key() {
+        let mut bytes = good_signing_keypair().to_wire_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(matches!(
+            SigningKeypair::from_wire_bytes(&bytes),
+            Err(RecryptErr::DecodeFailure(DecodeErr::BytesInvalid { .. }))
+        ));
+    }
+
     #[test]
     fn signing_keypair_into_bytes() {
         let dalek_pub_key = ed25519_dalek::VerifyingKey::from_bytes(&[1u8; 32]).unwrap();
@@ -3,6 +3,8 @@ use crate::internal::fp::fr_256::Fr256;
 use crate::internal::fp::fr_480::Fr480;
 use crate::internal::fp2elem::Fp2Elem;
 use crate::internal::fp6elem::Fp6Elem;
+use crate::internal::hashable::{Hashable32, Hashable60};
+use crate::internal::pow_vartime;
 use gridiron::fp_256;
 use gridiron::fp_480;
 use num_traits::{Inv, Pow};
@@ -115,6 +117,48 @@ impl Field for fp_480::Monty {}
 impl Field for Fr256 {}
 impl Field for Fr480 {}
 
+/// Square roots, used to decompress points from their x coordinate.
+///
+/// Constant Time Evaluation:
+/// These are not constant time. They are only applied to public values such as public keys.
+pub trait SquareRoot: Field {
+    /// A square root of self, or None if self is not a square.
+    fn sqrt(&self) -> Option<Self>;
+
+    /// The sign of self as defined by `sgn0` in RFC 9380, which picks one of the two square roots.
+    fn sgn0(&self) -> bool;
+}
+
+/// Square root for primes congruent to 3 mod 4: a^((p+1)/4) == a^((p-3)/4) * a
+fn sqrt_3_mod_4<T: ExtensionField>(a: T) -> Option<T> {
+    let candidate = pow_vartime(a, T::p_minus_3_div_4()) * a;
+    if candidate.square() == a {
+        Some(candidate)
+    } else {
+        None
+    }
+}
+
+impl SquareRoot for fp_256::Monty {
+    fn sqrt(&self) -> Option<Self> {
+        sqrt_3_mod_4(*self)
+    }
+
+    fn sgn0(&self) -> bool {
+        self.to_bytes_32()[31] & 1 == 1
+    }
+}
+
+impl SquareRoot for fp_480::Monty {
+    fn sqrt(&self) -> Option<Self> {
+        sqrt_3_mod_4(*self)
+    }
+
+    fn sgn0(&self) -> bool {
+        self.to_bytes_60()[59] & 1 == 1
+    }
+}
+
 /// Contains the values needed to configure a new Fp type to be used as an extension field
 /// (FP2Elem, FP6Elem, FP12Elem)
 /// All `ExtensionField`s are `Field`s
@@ -152,6 +196,12 @@ where
     /// pre-calculate as an optimization
     /// ExtensionField::xi().inv() * 3;
     fn twisted_curve_const_coeff() -> Fp2Elem<Self>;
+
+    /// (p-3)/4 as big-endian bytes, used for square roots since p is congruent to 3 mod 4
+    fn p_minus_3_div_4() -> &'static [u8];
+
+    /// (p-1)/2 as big-endian bytes, used for square roots in Fp2
+    fn p_minus_1_div_2() -> &'static [u8];
 }
 
 impl ExtensionField for fp_256::Monty {
@@ -232,6 +282,22 @@ impl ExtensionField for fp_256::Monty {
             ]),
         }
     }
+
+    fn p_minus_3_div_4() -> &'static [u8] {
+        // 16250137423911650933199109685589976435706339526905750892969286256716046017945
+        &[
+            35, 237, 64, 120, 210, 168, 225, 254, 106, 155, 251, 46, 24, 97, 55, 8, 123, 150, 226,
+            52, 72, 45, 109, 103, 134, 23, 43, 27, 23, 130, 37, 153,
+        ]
+    }
+
+    fn p_minus_1_div_2() -> &'static [u8] {
+        // 32500274847823301866398219371179952871412679053811501785938572513432092035891
+        &[
+            71, 218, 128, 241, 165, 81, 195, 252, 213, 55, 246, 92, 48, 194, 110, 16, 247, 45, 196,
+            104, 144, 90, 218, 207, 12, 46, 86, 54, 47, 4, 75, 51,
+        ]
+    }
 }
 
 impl ExtensionField for fp_480::Monty {
@@ -318,4 +384,59 @@ impl ExtensionField for fp_480::Monty {
             ]),
         }
     }
+
+    fn p_minus_3_div_4() -> &'static [u8] {
+        // 780394266460561701500771363013820319200768719043884346047154989497251131766602568717199739145728752218676016712254553286093942821106348877044005
+        &[
+            63, 255, 25, 153, 3, 137, 39, 103, 177, 214, 181, 74, 66, 224, 106, 23, 80, 85, 229,
+            238, 76, 73, 99, 104, 53, 226, 214, 40, 135, 16, 215, 55, 108, 11, 130, 183, 88, 214,
+            128, 220, 220, 116, 122, 105, 2, 151, 176, 117, 187, 88, 222, 244, 218, 85, 76, 26, 15,
+            186, 89, 37,
+        ]
+    }
+
+    fn p_minus_1_div_2() -> &'static [u8] {
+        // 1560788532921123403001542726027640638401537438087768692094309978994502263533205137434399478291457504437352033424509106572187885642212697754088011
+        &[
+            127, 254, 51, 50, 7, 18, 78, 207, 99, 173, 106, 148, 133, 192, 212, 46, 160, 171, 203,
+            220, 152, 146, 198, 208, 107, 197, 172, 81, 14, 33, 174, 110, 216, 23, 5, 110, 177,
+            173, 1, 185, 184, 232, 244, 210, 5, 47, 96, 235, 118, 177, 189, 233, 180, 170, 152, 52,
+            31, 116, 178, 75,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internal::test::{arb_fp256, arb_fp480};
+    use proptest::prelude::*;
+
+    #[test]
+    fn sqrt_of_non_square_is_none() {
+        // -1 is not a square since p is congruent to 3 mod 4
+        assert_eq!((-fp_256::Monty::one()).sqrt(), None);
+        assert_eq!((-fp_480::Monty::one()).sqrt(), None);
+        assert_eq!(fp_256::Monty::zero().sqrt(), Some(fp_256::Monty::zero()));
+    }
+
+    proptest! {
+        #[test]
+        fn sqrt_of_square_256(a in arb_fp256()) {
+            let root = a.square().sqrt().unwrap();
+            prop_assert!(root == a || root == -a);
+        }
+
+        #[test]
+        fn sqrt_of_square_480(a in arb_fp480()) {
+            let root = a.square().sqrt().unwrap();
+            prop_assert!(root == a || root == -a);
+        }
+
+        #[test]
+        fn sgn0_differs_for_negation(a in arb_fp256()) {
+            prop_assume!(!a.is_zero());
+            prop_assert_ne!(a.sgn0(), (-a).sgn0());
+        }
+    }
 }
@@ -0,0 +1,145 @@
+//! Precomputed tables for multiplying a fixed point by a scalar.
+//!
+//! The scalar is split into 4 bit windows. For window `i` the table holds `j * 16^i * base` for every
+//! `j` in `0..16`, so a multiplication is one lookup and one point addition per window and needs no
+//! doublings. A lookup reads every entry of its window and picks one with `ConstantSwap`, so like the
+//! double-and-add `Mul` impls it never branches on, or indexes by, the bits of the scalar.
+use crate::internal::bit_repr::BitRepr;
+use crate::internal::homogeneouspoint::Double;
+use gridiron::digits::constant_bool::ConstantBool;
+use gridiron::digits::constant_time_primitives::ConstantSwap;
+use num_traits::Zero;
+use std::fmt;
+
+const WINDOW_BITS: usize = 4;
+const WINDOW_SIZE: usize = 1 << WINDOW_BITS;
+
+pub struct FixedBaseTable<P> {
+    windows: Vec<[P; WINDOW_SIZE]>,
+}
+
+impl<P> FixedBaseTable<P>
+where
+    P: Zero + Double + ConstantSwap + Copy,
+{
+    /// Build the table for multiplying `base` by scalars of up to `scalar_bits` bits.
+    pub fn new(base: P, scalar_bits: usize) -> FixedBaseTable<P> {
+        let mut window_base = base;
+        let windows = (0..scalar_bits.div_ceil(WINDOW_BITS))
+            .map(|_| {
+                let mut entry = P::zero();
+                let window = std::array::from_fn(|_| {
+                    let current = entry;
+                    entry = entry + window_base;
+                    current
+                });
+                (0..WINDOW_BITS).for_each(|_| window_base = window_base.double());
+                window
+            })
+            .collect();
+        FixedBaseTable { windows }
+    }
+
+    /// Compute `base * k`. This gives the same point as `base * k` with the `Mul` impl for `P`.
+    pub fn mul<U: BitRepr>(&self, k: U) -> P {
+        let bits = k.to_bits();
+        assert!(
+            bits.len() <= self.windows.len() * WINDOW_BITS,
+            "Developer error: scalar has more bits than the fixed-base table was built for"
+        );
+        bits.chunks(WINDOW_BITS)
+            .zip(self.windows.iter())
+            .fold(P::zero(), |acc, (digit, window)| {
+                acc + select(window, digit)
+            })
+    }
+}
+
+/// The entry of `window` at `digit`, where `digit` holds the little-endian bits of the index.
+///
+/// Each bit halves the candidates by swapping the odd entry of every pair into the even position
+/// when the bit is set, so every entry is touched no matter what the bits are.
+fn select<P: ConstantSwap + Copy>(window: &[P; WINDOW_SIZE], digit: &[ConstantBool<u32>]) -> P {
+    let candidates = digit.iter().fold(window.to_vec(), |candidates, bit| {
+        candidates
+            .chunks(2)
+            .map(|pair| {
+                let (mut even, mut odd) = (pair[0], pair[1]);
+                even.swap_if(&mut odd, *bit);
+                even
+            })
+            .collect()
+    });
+    candidates[0]
+}
+
+impl<P> fmt::Debug for FixedBaseTable<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FixedBaseTable")
+            .field("windows", &self.windows.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internal::curve::{FP_256_CURVE_POINTS, FP_480_CURVE_POINTS};
+    use gridiron::fp_256::Monty as Monty256;
+    use gridiron::fp_480::Monty as Monty480;
+    use num_traits::{Inv, One};
+    use proptest::prelude::*;
+
+    #[test]
+    fn small_scalars_match_mul() {
+        let generator = FP_256_CURVE_POINTS.generator;
+        let table = FixedBaseTable::new(generator, 256);
+        for k in [0u32, 1, 2, 15, 16, 17, 255, 256, 65535] {
+            assert_eq!(table.mul(Monty256::from(k)), generator * Monty256::from(k));
+        }
+    }
+
+    #[test]
+    fn short_table_covers_short_scalars() {
+        let g1 = FP_256_CURVE_POINTS.g1;
+        // 6 bits leaves a final window of only 2 bits
+        let table = FixedBaseTable::new(g1, 6);
+        assert_eq!(table.windows.len(), 2);
+        let window = &table.windows[1];
+        for digit in 0..4u32 {
+            let bits = Monty256::from(digit).to_bits();
+            assert_eq!(select(window, &bits[..2]), g1 * Monty256::from(digit * 16));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn fp256_tables_match_mul(seed in any::<u32>()) {
+            // inverting spreads the scalar over all of the bits
+            let k = (Monty256::from(seed) + Monty256::one()).inv();
+            prop_assert_eq!(
+                FP_256_CURVE_POINTS.generator_table.mul(k),
+                FP_256_CURVE_POINTS.generator * k
+            );
+            prop_assert_eq!(FP_256_CURVE_POINTS.g1_table.mul(k), FP_256_CURVE_POINTS.g1 * k);
+            prop_assert_eq!(
+                FP_256_CURVE_POINTS.hash_element_table.mul(k),
+                FP_256_CURVE_POINTS.hash_element * k
+            );
+        }
+
+        #[test]
+        fn fp480_tables_match_mul(seed in any::<u32>()) {
+            let k = (Monty480::from(seed) + Monty480::one()).inv();
+            prop_assert_eq!(
+                FP_480_CURVE_POINTS.generator_table.mul(k),
+                FP_480_CURVE_POINTS.generator * k
+            );
+            prop_assert_eq!(FP_480_CURVE_POINTS.g1_table.mul(k), FP_480_CURVE_POINTS.g1 * k);
+            prop_assert_eq!(
+                FP_480_CURVE_POINTS.hash_element_table.mul(k),
+                FP_480_CURVE_POINTS.hash_element * k
+            );
+        }
+    }
+}
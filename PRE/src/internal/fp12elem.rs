@@ -1,10 +1,10 @@
 use crate::internal::ByteVector;
 use crate::internal::bytedecoder::{BytesDecoder, DecodeErr};
 use crate::internal::field::{ExtensionField, Field};
-use crate::internal::fp2elem::Fp2Elem;
+use crate::internal::fp2elem::{Fp2Elem, Xi};
 use crate::internal::fp6elem::Fp6Elem;
 use crate::internal::hashable::Hashable;
-use crate::internal::{Square, pow_for_square, sum_n};
+use crate::internal::{Square, batch_inv, pow_for_square, sum_n};
 use core::fmt;
 use gridiron::fp_256::Monty as Monty256;
 use gridiron::fp_480::Monty as Monty480;
@@ -265,6 +265,135 @@ where
             elem2: self.elem2,
         }
     }
+
+    /// Square an element of the cyclotomic subgroup, that is an element f with f^(p^4 - p^2 + 1) == 1.
+    /// Everything after the easy part of the final exponentiation is in this subgroup.
+    /// For other elements the result is not the square.
+    ///
+    /// Section 3.2 of Granger--Scott "Faster Squaring in the Cyclotomic Subgroup of Sixth Degree Extensions".
+    /// The element is viewed as g0 + g1 * w + g2 * w^2 with each gi in FP4 = FP2[s]/(s^2 - Xi), where s = w^3, and
+    /// the square is computed from the squares of the gi, which costs 3 FP4 squarings instead of a full FP12 squaring.
+    pub fn cyclotomic_square(&self) -> Fp12Elem<T> {
+        let [c0, c1, c2, c3, c4, c5] = self.w_coefficients();
+        let (a0, a1) = fp4_square(c0, c3);
+        let (b0, b1) = fp4_square(c1, c4);
+        let (d0, d1) = fp4_square(c2, c5);
+        Fp12Elem::create(
+            b1 * 3 + c5 * 2,
+            a1 * 3 + c3 * 2,
+            d1 * Xi * 3 + c1 * 2,
+            d0 * 3 - c4 * 2,
+            b0 * 3 - c2 * 2,
+            a0 * 3 - c0 * 2,
+        )
+    }
+
+    /// Compress an element of the cyclotomic subgroup for repeated squaring. See `CompressedFp12Elem`.
+    pub fn compress(&self) -> CompressedFp12Elem<T> {
+        let [_, c1, c2, _, c4, c5] = self.w_coefficients();
+        CompressedFp12Elem {
+            g2: c1,
+            g3: c4,
+            g4: c2,
+            g5: c5,
+        }
+    }
+
+    /// The coefficients of 1, w, w^2, w^3, w^4 and w^5, in that order.
+    fn w_coefficients(&self) -> [Fp2Elem<T>; 6] {
+        let Fp12Elem { elem1: b, elem2: a } = *self;
+        [a.elem3, b.elem3, a.elem2, b.elem2, a.elem1, b.elem1]
+    }
+}
+
+/// Square a + b * s in FP4 = FP2[s]/(s^2 - Xi), returning the coefficients of 1 and s.
+fn fp4_square<T: ExtensionField>(a: Fp2Elem<T>, b: Fp2Elem<T>) -> (Fp2Elem<T>, Fp2Elem<T>) {
+    let a2 = a.square();
+    let b2 = b.square();
+    (b2 * Xi + a2, (a + b).square() - a2 - b2)
+}
+
+/// Karabina's compressed representation of an element of the cyclotomic subgroup, for computing long runs of
+/// squarings such as those in the hard part of the final exponentiation.
+///
+/// Writing the element as g0 + g2 * w + g4 * w^2 + g1 * w^3 + g3 * w^4 + g5 * w^5, only g2, g3, g4 and g5 are
+/// kept. Squaring in this form is cheaper than `cyclotomic_square`, but getting back to an `Fp12Elem` needs an
+/// inversion, which `decompress_batch` shares between several elements.
+///
+/// Section 5.2 of Aranha--Karabina--Longa--Gebotys--Lopez "Faster Explicit Formulas for Computing Pairings over
+/// Ordinary Curves".
+#[derive(Clone, PartialEq, Eq, Copy, Debug)]
+pub struct CompressedFp12Elem<T> {
+    g2: Fp2Elem<T>,
+    g3: Fp2Elem<T>,
+    g4: Fp2Elem<T>,
+    g5: Fp2Elem<T>,
+}
+
+impl<T> Square for CompressedFp12Elem<T>
+where
+    T: ExtensionField,
+{
+    fn square(&self) -> Self {
+        let CompressedFp12Elem { g2, g3, g4, g5 } = *self;
+        let b23 = g2 * g3;
+        let b45 = g4 * g5;
+        // g2^2 + Xi * g3^2 and g4^2 + Xi * g5^2, each with a single multiplication
+        let s23 = (g2 + g3) * (g2 + g3 * Xi) - b23 * Xi - b23;
+        let s45 = (g4 + g5) * (g4 + g5 * Xi) - b45 * Xi - b45;
+        CompressedFp12Elem {
+            g2: (g2 + b45 * Xi * 3) * 2,
+            g3: s45 * 3 - g3 * 2,
+            g4: s23 * 3 - g4 * 2,
+            g5: (g5 + b23 * 3) * 2,
+        }
+    }
+}
+
+impl<T> CompressedFp12Elem<T>
+where
+    T: ExtensionField,
+{
+    pub fn decompress(&self) -> Fp12Elem<T> {
+        let (numerator, denominator) = self.g1_fraction();
+        self.decompress_with_g1(numerator / denominator)
+    }
+
+    /// `decompress` each element of `compressed`, using a single inversion for all of them.
+    pub fn decompress_batch(compressed: &[CompressedFp12Elem<T>]) -> Vec<Fp12Elem<T>> {
+        let (numerators, denominators): (Vec<_>, Vec<_>) =
+            compressed.iter().map(|c| c.g1_fraction()).unzip();
+        batch_inv(&denominators)
+            .into_iter()
+            .zip(numerators)
+            .zip(compressed)
+            .map(|((denominator_inv, numerator), c)| {
+                c.decompress_with_g1(numerator * denominator_inv)
+            })
+            .collect()
+    }
+
+    /// The numerator and denominator of g1. The denominator is never zero.
+    ///
+    /// This branches on whether g2 and g3 are zero. For anything but the identity g2 is only zero with
+    /// negligible probability, so this does not reveal anything about the element in practice.
+    fn g1_fraction(&self) -> (Fp2Elem<T>, Fp2Elem<T>) {
+        let CompressedFp12Elem { g2, g3, g4, g5 } = *self;
+        if !g2.is_zero() {
+            (g5.square() * Xi + g4.square() * 3 - g3 * 2, g2 * 4)
+        } else if !g3.is_zero() {
+            (g4 * g5 * 2, g3)
+        } else {
+            // only the identity has g2 == g3 == 0, and its g1 is 0
+            (Zero::zero(), One::one())
+        }
+    }
+
+    fn decompress_with_g1(&self, g1: Fp2Elem<T>) -> Fp12Elem<T> {
+        let CompressedFp12Elem { g2, g3, g4, g5 } = *self;
+        let g0 = (g1.square() * 2 + g2 * g5 - g3 * g4 * 3) * Xi + One::one();
+        Fp12Elem::create(g5, g1, g2, g3, g4, g0)
+    }
 }
 
 impl<T> Fp12Elem<T> {
@@ -453,6 +582,71 @@ pub mod test {
 
             prop_assert_eq!(left, right);
         }
+
+        #[test]
+        fn fp256_cyclotomic_squares_match_square(fp in arb_fp12()) {
+            prop_assume!(!fp.is_zero());
+            let f = to_cyclotomic(fp);
+            prop_assert_eq!(f.cyclotomic_square(), f.square());
+            prop_assert_eq!(f.compress().square().decompress(), f.square());
+        }
+
+        #[test]
+        fn fp480_cyclotomic_squares_match_square(fp in arb_fp12_480()) {
+            prop_assume!(!fp.is_zero());
+            let f = to_cyclotomic(fp);
+            prop_assert_eq!(f.cyclotomic_square(), f.square());
+            prop_assert_eq!(f.compress().square().decompress(), f.square());
+        }
+
+        #[test]
+        fn compressed_squarings_match_squarings(fp in arb_fp12(), n in 1..12usize) {
+            prop_assume!(!fp.is_zero());
+            let f = to_cyclotomic(fp);
+            let expected: Vec<Fp12Elem<fp_256::Monty>> = (0..n)
+                .scan(f, |x, _| {
+                    *x = x.square();
+                    Some(*x)
+                })
+                .collect();
+            let compressed: Vec<CompressedFp12Elem<fp_256::Monty>> = (0..n)
+                .scan(f.compress(), |x, _| {
+                    *x = x.square();
+                    Some(*x)
+                })
+                .collect();
+            prop_assert_eq!(CompressedFp12Elem::decompress_batch(&compressed), expected);
+        }
+    }
+
+    #[test]
+    fn compressed_identity_decompresses() {
+        let one = Fp12Elem::<fp_480::Monty>::one();
+        assert_eq!(one.compress().square().decompress(), one);
+        let f: Fp12Elem<fp_480::Monty> = to_cyclotomic(Fp12Elem::create_from_t(
+            1u8.into(),
+            2u8.into(),
+            3u8.into(),
+            4u8.into(),
+            5u8.into(),
+            6u8.into(),
+            7u8.into(),
+            8u8.into(),
+            9u8.into(),
+            10u8.into(),
+            11u8.into(),
+            12u8.into(),
+        ));
+        assert_eq!(
+            CompressedFp12Elem::decompress_batch(&[one.compress(), f.compress()]),
+            vec![one, f]
+        );
+    }
+
+    /// Map `fp` into the cyclotomic subgroup with the easy part of the final exponentiation.
+    fn to_cyclotomic<T: ExtensionField>(fp: Fp12Elem<T>) -> Fp12Elem<T> {
+        let f = fp.conjugate() / fp; // fp^(p^6 - 1)
+        f.frobenius().frobenius() * f // f^(p^2 + 1)
     }
 
     field_proptest!(arb_fp12, fp256, fp12);
@@ -1,9 +1,9 @@
 use crate::internal::ByteVector;
 use crate::internal::Square;
 use crate::internal::bytedecoder::{BytesDecoder, DecodeErr};
-use crate::internal::field::Field;
+use crate::internal::field::{ExtensionField, Field, SquareRoot};
 use crate::internal::hashable::Hashable;
-use crate::internal::pow_for_square;
+use crate::internal::{pow_for_square, pow_vartime};
 use core::fmt;
 use gridiron::digits::constant_bool::ConstantBool;
 use gridiron::digits::constant_time_primitives::ConstantSwap;
@@ -277,6 +277,43 @@ where
     }
 }
 
+impl<T> SquareRoot for Fp2Elem<T>
+where
+    T: ExtensionField + SquareRoot,
+{
+    /// Algorithm 9 of "Square root computation over even extension fields" (Adj, Rodríguez-Henríquez),
+    /// which applies because p is congruent to 3 mod 4.
+    fn sqrt(&self) -> Option<Self> {
+        let minus_one = -Fp2Elem::<T>::one();
+        let a1 = pow_vartime(*self, T::p_minus_3_div_4());
+        let alpha = a1 * (a1 * *self);
+        // alpha^(p+1) == -1 exactly when self is not a square
+        if alpha.frobenius() * alpha == minus_one {
+            return None;
+        }
+        let x0 = a1 * *self;
+        let candidate = if alpha == minus_one {
+            // u * x0
+            Fp2Elem {
+                elem1: x0.elem2,
+                elem2: -x0.elem1,
+            }
+        } else {
+            pow_vartime(alpha + One::one(), T::p_minus_1_div_2()) * x0
+        };
+        if candidate.square() == *self {
+            Some(candidate)
+        } else {
+            None
+        }
+    }
+
+    /// elem2 is the constant term, so it decides the sign unless it is zero.
+    fn sgn0(&self) -> bool {
+        self.elem2.sgn0() || (self.elem2.is_zero() && self.elem1.sgn0())
+    }
+}
+
 impl<T> Hashable for Fp2Elem<T>
 where
     T: Hashable + Copy,
@@ -317,6 +354,7 @@ pub mod test {
     use gridiron::fp_256;
     use gridiron::fp_256::Fp256;
     use gridiron::fp_480;
+    use proptest::prelude::*;
     use proptest::prop_compose;
     use proptest::proptest;
 
@@ -424,6 +462,39 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn sqrt_of_non_square_is_none() {
+        // 2u + 1 has norm 5, which is not a square mod p
+        let non_square = Fp2Elem {
+            elem1: fp_256::Monty::from(2u32),
+            elem2: fp_256::Monty::one(),
+        };
+        assert_eq!(non_square.sqrt(), None);
+        // -1 is a square in Fp2 since u * u == -1
+        let root = (-Fp2Elem::<fp_256::Monty>::one()).sqrt().unwrap();
+        assert_eq!(root.square(), -Fp2Elem::one());
+    }
+
+    proptest! {
+        #[test]
+        fn sqrt_of_square_256(a in arb_fp2()) {
+            let root = a.square().sqrt().unwrap();
+            prop_assert!(root == a || root == -a);
+        }
+
+        #[test]
+        fn sqrt_of_square_480(a in arb_fp2_480()) {
+            let root = a.square().sqrt().unwrap();
+            prop_assert!(root == a || root == -a);
+        }
+
+        #[test]
+        fn sgn0_differs_for_negation(a in arb_fp2()) {
+            prop_assume!(!a.is_zero());
+            prop_assert_ne!(a.sgn0(), (-a).sgn0());
+        }
+    }
+
     field_proptest!(arb_fp2, fp256, fp2);
     field_proptest!(arb_fp2_480, fp480, fp2);
 }
@@ -3,8 +3,11 @@ use crate::internal::bit_repr::BitRepr;
 use crate::internal::bytedecoder::{BytesDecoder, DecodeErr};
 use crate::internal::field::ExtensionField;
 use crate::internal::field::Field;
+use crate::internal::field::SquareRoot;
 use crate::internal::fp2elem::Fp2Elem;
 use crate::internal::hashable::Hashable;
+use crate::internal::sha256::Sha256Hashing;
+use crate::internal::{Square, pow_vartime};
 use gridiron::digits::constant_bool::ConstantBool;
 use gridiron::digits::constant_time_primitives::ConstantSwap;
 use num_traits::Inv;
@@ -15,6 +18,11 @@ use quick_error::quick_error;
 use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 use std::option::Option;
 
+/// SEC1 style prefix of a compressed point whose y has sgn0 == 0
+const COMPRESSED_SIGN_0: u8 = 0x02;
+/// SEC1 style prefix of a compressed point whose y has sgn0 == 1
+const COMPRESSED_SIGN_1: u8 = 0x03;
+
 quick_error! {
     #[derive(Clone, Debug, PartialEq, Eq)]
     pub enum PointErr {
@@ -204,6 +212,24 @@ where
     }
 }
 
+impl<T> HomogeneousPoint<T>
+where
+    T: SquareRoot + From<u32> + Hashable + BytesDecoder,
+{
+    /// Encode the point as a sign byte followed by the x coordinate, or None if the point is zero.
+    pub fn to_compressed(self) -> Option<ByteVector> {
+        self.normalize().map(compress)
+    }
+
+    /// Inverse of `to_compressed`, recovering y from the curve equation y^2 = x^3 + 3.
+    ///
+    /// Constant Time Evaluation:
+    /// This is not constant time, which is fine since it is only used on public keys.
+    pub fn from_compressed(bytes: &[u8]) -> Result<Self, DecodeErr> {
+        decompress(bytes, |x: T| x.pow(3) + T::from(3)).map(|(x, y)| HomogeneousPoint::new(x, y))
+    }
+}
+
 ///HomogeneousPoint on the twisted curve which is either Zero or an x,y coordinate which has a z it carries
 ///along. In order to get the real x,y you must call `normalize` which divides out by the z.
 ///
@@ -407,6 +433,284 @@ where
     }
 }
 
+impl<T> TwistedHPoint<T>
+where
+    T: ExtensionField + SquareRoot + Hashable + BytesDecoder,
+{
+    /// Encode the point as a sign byte followed by the x coordinate, or None if the point is zero.
+    pub fn to_compressed(self) -> Option<ByteVector> {
+        self.normalize().map(compress)
+    }
+
+    /// Inverse of `to_compressed`, recovering y from the twisted curve equation.
+    ///
+    /// Constant Time Evaluation:
+    /// This is not constant time, which is fine since it is only used on public values.
+    pub fn from_compressed(bytes: &[u8]) -> Result<Self, DecodeErr> {
+        decompress(bytes, |x: Fp2Elem<T>| {
+            x.pow(3) + ExtensionField::twisted_curve_const_coeff()
+        })
+        .map(|(x, y)| TwistedHPoint::new(x, y))
+    }
+}
+
+fn compress<T: SquareRoot + Hashable>((x, y): (T, T)) -> ByteVector {
+    let prefix = if y.sgn0() {
+        COMPRESSED_SIGN_1
+    } else {
+        COMPRESSED_SIGN_0
+    };
+    let mut bytes = vec![prefix];
+    bytes.extend(x.to_bytes());
+    bytes
+}
+
+/// Decode the x coordinate of a compressed point and pick the root of `curve_rhs(x)` that has the encoded sign.
+fn decompress<T, F>(bytes: &[u8], curve_rhs: F) -> Result<(T, T), DecodeErr>
+where
+    T: SquareRoot + Hashable + BytesDecoder,
+    F: FnOnce(T) -> T,
+{
+    let invalid = |message: &str| DecodeErr::BytesInvalid {
+        message: message.to_string(),
+        bad_bytes: bytes.to_vec(),
+    };
+    let (prefix, x_bytes) = match bytes.split_first() {
+        Some((prefix, x_bytes)) if x_bytes.len() == T::ENCODED_SIZE_BYTES => (*prefix, x_bytes),
+        _ => {
+            return Err(DecodeErr::BytesNotCorrectLength {
+                required_length: T::ENCODED_SIZE_BYTES + 1,
+                bad_bytes: bytes.to_vec(),
+            });
+        }
+    };
+    let sign = match prefix {
+        COMPRESSED_SIGN_0 => false,
+        COMPRESSED_SIGN_1 => true,
+        _ => return Err(invalid("Unknown compressed point prefix")),
+    };
+    let x = T::decode(x_bytes.to_vec())?;
+    // Only the canonical encoding of x is accepted, so each point has exactly one compressed form.
+    if x.to_bytes() != x_bytes {
+        return Err(invalid("x coordinate is not reduced"));
+    }
+    let y = curve_rhs(x)
+        .sqrt()
+        .ok_or_else(|| invalid("Point does not satisfy the curve equation"))?;
+    Ok((x, if y.sgn0() == sign { y } else { -y }))
+}
+
+/// Hashing to the twisted curve `y^2 = x^3 + b'` over `Fp2Elem<T>`, following the `hash_to_curve` of RFC 9380.
+///
+/// Messages are expanded with `expand_message_xmd` (SHA-256) into two elements of `Fp2Elem<T>`, each is mapped to
+/// the curve with the Shallue--van de Woestijne map (section 6.6.1), and the sum of the two points is multiplied by
+/// the cofactor `2p - r`, giving a point of order r that is indistinguishable from a random one.
+///
+/// Constant Time Evaluation:
+/// The map is the straight-line version of appendix F.1. Square tests, zero tests and signs are computed as the
+/// field elements 0 or 1 with exponentiations by public exponents, and `cmov` selects with field arithmetic, so
+/// nothing branches on the message.
+#[derive(Debug)]
+pub struct TwistHasher<T> {
+    z: Fp2Elem<T>,
+    c1: Fp2Elem<T>,
+    c2: Fp2Elem<T>,
+    c3: Fp2Elem<T>,
+    c4: Fp2Elem<T>,
+    element_bytes: usize,
+    p_minus_r: T,
+    r: T,
+}
+
+impl<T: ExtensionField + SquareRoot> TwistHasher<T> {
+    /// `z`             - the Z of the SvdW map, an element of the base field
+    /// `element_bytes` - the L of `hash_to_field`: the number of bytes reduced to each coordinate
+    /// `p_minus_r`     - the field prime minus the curve order; the cofactor is `2 * p_minus_r + r`
+    /// `r`             - the curve order
+    pub fn new(z: T, element_bytes: usize, p_minus_r: T, r: T) -> TwistHasher<T> {
+        let z = Fp2Elem {
+            elem1: Zero::zero(),
+            elem2: z,
+        };
+        let g_z = z.pow(3) + T::twisted_curve_const_coeff();
+        let three_z_squared = z.square() * 3;
+        let c3 = (-g_z * three_z_squared)
+            .sqrt()
+            .expect("Developer error: Z doesn't meet the requirements of the SvdW map");
+        TwistHasher {
+            z,
+            c1: g_z,
+            c2: -z / (Fp2Elem::one() * 2),
+            c3: if c3.sgn0() { -c3 } else { c3 },
+            c4: -(g_z * 4) / three_z_squared,
+            element_bytes,
+            p_minus_r,
+            r,
+        }
+    }
+}
+
+impl<T> TwistHasher<T>
+where
+    T: ExtensionField + Hashable + BitRepr + ConstantSwap,
+{
+    /// Hash `msg` to a point of order r on the twisted curve, with the domain separation tag `dst`.
+    pub fn hash_to_curve<H: Sha256Hashing>(
+        &self,
+        msg: &[u8],
+        dst: &[u8],
+        sha256: &H,
+    ) -> TwistedHPoint<T> {
+        let [u0, u1] = self.hash_to_field(msg, dst, sha256);
+        self.clear_cofactor(self.map_to_curve(u0) + self.map_to_curve(u1))
+    }
+
+    /// Two uniformly random elements of `Fp2Elem<T>` from `msg`.
+    pub fn hash_to_field<H: Sha256Hashing>(
+        &self,
+        msg: &[u8],
+        dst: &[u8],
+        sha256: &H,
+    ) -> [Fp2Elem<T>; 2] {
+        let uniform_bytes = expand_message_xmd(sha256, msg, dst, 4 * self.element_bytes);
+        let element = |bytes: &[u8]| {
+            // the constant term comes first
+            let (constant, u) = bytes.split_at(self.element_bytes);
+            Fp2Elem {
+                elem1: from_be_bytes(u),
+                elem2: from_be_bytes(constant),
+            }
+        };
+        let (first, second) = uniform_bytes.split_at(2 * self.element_bytes);
+        [element(first), element(second)]
+    }
+
+    /// The Shallue--van de Woestijne map of `u` to the twisted curve.
+    pub fn map_to_curve(&self, u: Fp2Elem<T>) -> TwistedHPoint<T> {
+        let one = Fp2Elem::one();
+        let curve_rhs = |x: Fp2Elem<T>| x.pow(3) + T::twisted_curve_const_coeff();
+        let tv1 = u.square() * self.c1;
+        let tv2 = one + tv1;
+        let tv1 = one - tv1;
+        let tv3 = inv0(tv1 * tv2);
+        let tv4 = u * tv1 * tv3 * self.c3;
+        let x1 = self.c2 - tv4;
+        let e1 = is_square(curve_rhs(x1));
+        let x2 = self.c2 + tv4;
+        let e2 = is_square(curve_rhs(x2)) * (T::one() - e1);
+        let x3 = (tv2.square() * tv3).square() * self.c4 + self.z;
+        let x = cmov(cmov(x3, x1, e1), x2, e2);
+        let y = sqrt_of_square(curve_rhs(x));
+        let sign_difference = sgn0(u) - sgn0(y);
+        let y = cmov(-y, y, T::one() - sign_difference.square());
+        TwistedHPoint::new(x, y)
+    }
+
+    /// Multiply `point` by the cofactor `2p - r`, as `(p - r) * point` doubled plus `r * point`, since both
+    /// scalars are smaller than p.
+    pub fn clear_cofactor(&self, point: TwistedHPoint<T>) -> TwistedHPoint<T> {
+        (point * self.p_minus_r).double() + point * self.r
+    }
+}
+
+/// `expand_message_xmd` of RFC 9380 (section 5.3.1) with SHA-256: `len` pseudorandom bytes from `msg` and `dst`.
+fn expand_message_xmd<H: Sha256Hashing>(
+    sha256: &H,
+    msg: &[u8],
+    dst: &[u8],
+    len: usize,
+) -> ByteVector {
+    let blocks = len.div_ceil(32);
+    assert!(
+        blocks <= 255 && dst.len() <= 255,
+        "Developer error: expand_message_xmd is limited to 255 blocks and a 255 byte tag"
+    );
+    let dst_prime = [dst, &[dst.len() as u8][..]].concat();
+    let b_0 = sha256.hash(
+        &[
+            &[0u8; 64][..],
+            msg,
+            &(len as u16).to_be_bytes()[..],
+            &[0u8][..],
+            &dst_prime,
+        ]
+        .concat(),
+    );
+    let mut b_i = sha256.hash(&[&b_0[..], &[1u8][..], &dst_prime].concat());
+    let mut uniform_bytes = b_i.to_vec();
+    for i in 2..=blocks {
+        let chained: ByteVector = b_0.iter().zip(b_i.iter()).map(|(a, b)| a ^ b).collect();
+        b_i = sha256.hash(&[&chained[..], &[i as u8][..], &dst_prime].concat());
+        uniform_bytes.extend_from_slice(&b_i);
+    }
+    uniform_bytes.truncate(len);
+    uniform_bytes
+}
+
+/// The big-endian integer `bytes`, reduced mod p.
+fn from_be_bytes<T: ExtensionField>(bytes: &[u8]) -> T {
+    bytes
+        .iter()
+        .fold(T::zero(), |acc, byte| acc * 256 + T::from(*byte))
+}
+
+/// `a` if `c` is 0 and `b` if `c` is 1.
+fn cmov<T: Field>(a: Fp2Elem<T>, b: Fp2Elem<T>, c: T) -> Fp2Elem<T> {
+    Fp2Elem {
+        elem1: a.elem1 + (b.elem1 - a.elem1) * c,
+        elem2: a.elem2 + (b.elem2 - a.elem2) * c,
+    }
+}
+
+/// 1 if `a` is zero and 0 otherwise, by Fermat's little theorem.
+fn is_zero<T: ExtensionField>(a: T) -> T {
+    T::one() - pow_vartime(a, T::p_minus_1_div_2()).square()
+}
+
+/// 1 if `a` is a square (including zero) and 0 otherwise. `a` is a square exactly when its norm is a square in the
+/// base field, and the Legendre symbol `l` of the norm is mapped to 1 - l(l - 1)/2.
+fn is_square<T: ExtensionField>(a: Fp2Elem<T>) -> T {
+    let legendre = pow_vartime(a.elem1.square() + a.elem2.square(), T::p_minus_1_div_2());
+    T::one() - (legendre.square() - legendre) / T::from(2u8)
+}
+
+/// `sgn0` of RFC 9380 as 0 or 1; see `SquareRoot::sgn0`.
+fn sgn0<T: ExtensionField + Hashable>(a: Fp2Elem<T>) -> T {
+    let parity = |t: T| T::from(t.to_bytes().last().map_or(0, |byte| byte & 1));
+    let sign_0 = parity(a.elem2);
+    let sign_1 = parity(a.elem1) * is_zero(a.elem2);
+    sign_0 + sign_1 - sign_0 * sign_1
+}
+
+/// The inverse of `a`, or zero if `a` is zero. The inverse of the norm is `n^(p-2) = (n^((p-3)/4))^4 * n`.
+fn inv0<T: ExtensionField>(a: Fp2Elem<T>) -> Fp2Elem<T> {
+    let norm = a.elem1.square() + a.elem2.square();
+    let norm_inv = pow_vartime(norm, T::p_minus_3_div_4()).pow(4) * norm;
+    Fp2Elem {
+        elem1: -a.elem1 * norm_inv,
+        elem2: a.elem2 * norm_inv,
+    }
+}
+
+/// A square root of `a`, which must be a square. This is `SquareRoot::sqrt` with its branches replaced by `cmov`.
+fn sqrt_of_square<T: ExtensionField>(a: Fp2Elem<T>) -> Fp2Elem<T> {
+    let one = Fp2Elem::one();
+    let a1 = pow_vartime(a, T::p_minus_3_div_4());
+    let alpha = a1 * (a1 * a);
+    let x0 = a1 * a;
+    // u * x0
+    let u_x0 = Fp2Elem {
+        elem1: x0.elem2,
+        elem2: -x0.elem1,
+    };
+    let alpha_is_minus_one = is_zero((alpha + one).elem1) * is_zero((alpha + one).elem2);
+    cmov(
+        pow_vartime(alpha + one, T::p_minus_1_div_2()) * x0,
+        u_x0,
+        alpha_is_minus_one,
+    )
+}
+
 // Since the formulas are complete, there is no need for make a special for zero.
 //See double for details on the formula
 fn add<T, U>(x1: T, y1: T, z1: T, x2: T, y2: T, z2: T, three_b: U) -> (T, T, T)
@@ -456,7 +760,9 @@ where
 pub mod test {
     use super::*;
     use crate::internal::curve::{FP_256_CURVE_POINTS, FP_480_CURVE_POINTS};
-    use crate::internal::fp::fp256_unsafe_from;
+    use crate::internal::fp::{fp256_unsafe_from, fp480_unsafe_from};
+    use crate::internal::fp2elem::test::arb_fp2;
+    use crate::internal::sha256::Sha256;
     use crate::internal::test::{arb_fp256, arb_fp480};
     use gridiron::fp_256::Monty as Monty256;
     use gridiron::fp_480::Monty as Monty480;
@@ -600,6 +906,37 @@ pub mod test {
         assert_eq!(hashed_value_bytes, hpoint.to_bytes())
     }
 
+    #[test]
+    fn compressed_generator_has_sec1_prefix() {
+        let generator = FP_256_CURVE_POINTS.generator;
+        let compressed = generator.to_compressed().unwrap();
+        let (x, y) = generator.normalize().unwrap();
+        assert_eq!(compressed[0], if y.sgn0() { 0x03 } else { 0x02 });
+        assert_eq!(&compressed[1..], &x.to_bytes()[..]);
+        assert_eq!(HomogeneousPoint::<Monty256>::zero().to_compressed(), None);
+    }
+
+    #[test]
+    fn from_compressed_rejects_bad_input() {
+        let mut compressed = FP_256_CURVE_POINTS.g1.to_compressed().unwrap();
+        assert!(TwistedHPoint::<Monty256>::from_compressed(&compressed[1..]).is_err());
+        compressed[0] = 0x04;
+        assert!(TwistedHPoint::<Monty256>::from_compressed(&compressed).is_err());
+
+        // x = 0 would need y^2 = 3, which has no root since 3 is not a square mod p
+        let mut zero_x = vec![0x02];
+        zero_x.extend(Monty256::zero().to_bytes());
+        assert!(HomogeneousPoint::<Monty256>::from_compressed(&zero_x).is_err());
+
+        // x = p is the non-canonical encoding of 0
+        let mut unreduced = vec![0x02];
+        unreduced.extend(
+            hex::decode("8fb501e34aa387f9aa6fecb86184dc21ee5b88d120b5b59e185cac6c5e089667")
+                .unwrap(),
+        );
+        assert!(HomogeneousPoint::<Monty256>::from_compressed(&unreduced).is_err());
+    }
+
     #[test]
     fn double_zero_is_zero() {
         let zero_fp256 = HomogeneousPoint::<Monty256>::zero();
@@ -617,6 +954,216 @@ pub mod test {
         assert_eq!(result, double_result);
     }
 
+    fn fp2_256(constant: &str, u: &str) -> Fp2Elem<Monty256> {
+        Fp2Elem {
+            elem1: fp256_unsafe_from(u).to_monty(),
+            elem2: fp256_unsafe_from(constant).to_monty(),
+        }
+    }
+
+    fn fp2_480(constant: &str, u: &str) -> Fp2Elem<Monty480> {
+        Fp2Elem {
+            elem1: fp480_unsafe_from(u).to_monty(),
+            elem2: fp480_unsafe_from(constant).to_monty(),
+        }
+    }
+
+    fn order_480() -> Monty480 {
+        fp480_unsafe_from("fffc66640e249d9ec75ad5290b81a85d415797b931258da0d78b58a21c425cdf7cfda2886d3d45c4fd22b1444c47f1427183f46b21df15f4921652f1")
+            .to_monty()
+    }
+
+    #[test]
+    fn expand_message_xmd_matches_rfc_9380() {
+        // the expand_message_xmd(SHA-256) vectors of appendix K.1
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        assert_eq!(
+            hex::encode(expand_message_xmd(&Sha256, b"", dst, 0x20)),
+            "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"
+        );
+        assert_eq!(
+            hex::encode(expand_message_xmd(&Sha256, b"abc", dst, 0x20)),
+            "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"
+        );
+    }
+
+    #[test]
+    fn hash_to_curve_256_matches_independent_vectors() {
+        // generated with a separate, variable time implementation of RFC 9380 over the same curve
+        // (u0 and u1 from hash_to_field, Q0 = map_to_curve(u0) and the final point)
+        let hasher = &FP_256_CURVE_POINTS.twist_hasher;
+        let dst = b"RECRYPT-V01-CS01-with-BN256G2_XMD:SHA-256_SVDW_RO_";
+        let cases = [
+            (
+                &b""[..],
+                [
+                    fp2_256(
+                        "4383b67acd24f0dc1df0919384f7d37836338cde122315044072a17a94467f63",
+                        "0d27b02ed0b0aaf081ef943ca9c61474794a1fab4459732e4e8c49c6178ac972",
+                    ),
+                    fp2_256(
+                        "05fe4e3a855ffa5a626bf9401e2e363bd7140e5d3baad540e3aa915e5a5c3b18",
+                        "6110b50e5b8a051f21d8dbe5903434cf7c73017254b190533db5fc9dfa390c89",
+                    ),
+                ],
+                (
+                    fp2_256(
+                        "21ff52be0e2ce3ec434966a02bbdfbfaa4620724728c5404603d7ae4b95fae58",
+                        "0f71ca87d66df48db746a1d8a5359ecc14a84525d5c056bf1e425b19c7931b7c",
+                    ),
+                    fp2_256(
+                        "3a31cfd84317fd5b045e6907861776d55b7348a5a8e9ca79d33446c6bdeb804f",
+                        "43e15fd038840900ba24a2908e0e9ffbbdd76a322a0dfcea1acace2c627c22f3",
+                    ),
+                ),
+                (
+                    fp2_256(
+                        "7a21f9936505b884be843f41267e45bf8e8cc8b31b12df82d3a0854c21ee5805",
+                        "5bdbbdb1fa012d89c144b4dfdd7cc310a05ef452261a4890eb6ea35d3ac2fe85",
+                    ),
+                    fp2_256(
+                        "88611121b146f504421e18f3d81948b8d4ae316f90e689598568e7cfa40a28b7",
+                        "5983a76b9708a4c8aa117b9cee601d63cae024dd26c7f16fb0be1b778f6ff2ad",
+                    ),
+                ),
+            ),
+            (
+                &b"abc"[..],
+                [
+                    fp2_256(
+                        "0bb09105a83dbf690a18348c8056ca685eb32208d4ab4863c2407ff87e0f7d9f",
+                        "7c079b6851a7122a59547692d9da44788e20d13cc0a98346f3c15dd8d457bc4b",
+                    ),
+                    fp2_256(
+                        "3d255681b9bd6692ef7bd9990898459ca82c56a202c81295b981d7ecc6215627",
+                        "44a37a1bdee40c0d650d92df42e597f2edfcd7c048d98f179925620eff739d0d",
+                    ),
+                ],
+                (
+                    fp2_256(
+                        "15a4b133896dc3f0a6a6362be516038d4cff260ee7631912b87e79febd5b89e7",
+                        "7b6a1a600e442fc70e1bc8e6aeb78d3929896c547638f9fca3b42c46216cf969",
+                    ),
+                    fp2_256(
+                        "69bed61c129ccb0dde8fa862508721c0e570e33d87aea22ed3db0a72b5b8c1ff",
+                        "0a4437a182a5d5e26c7da58dc61a7ed9035b626b1c90cf4c28d654bc996a2d13",
+                    ),
+                ),
+                (
+                    fp2_256(
+                        "68459d35af48ff5bf21b6097a93e658305ffcf52ba487c44de1cf4a4d6903c4f",
+                        "60d5a295afb58f9a7b0e9d11e0372371320d63684b219388c0003187ca1dda7b",
+                    ),
+                    fp2_256(
+                        "0ab251cde718cf2e26e4adc6330023a3777ee7dedddeef18932f1a35986397c4",
+                        "7207f0261a3b3eee55f2ebcd61028e4f509548b37a38fdb03f35aa9eb0ad1f03",
+                    ),
+                ),
+            ),
+        ];
+        for (msg, u, q0, expected) in cases {
+            assert_eq!(hasher.hash_to_field(msg, dst, &Sha256), u);
+            assert_eq!(hasher.map_to_curve(u[0]), TwistedHPoint::new(q0.0, q0.1));
+            let point = hasher.hash_to_curve(msg, dst, &Sha256);
+            assert_eq!(point, TwistedHPoint::new(expected.0, expected.1));
+            assert_eq!(point * order(), Zero::zero());
+        }
+    }
+
+    #[test]
+    fn hash_to_curve_480_matches_independent_vectors() {
+        // generated with a separate, variable time implementation of RFC 9380 over the same curve
+        // (u0 and u1 from hash_to_field, Q0 = map_to_curve(u0) and the final point)
+        let hasher = &FP_480_CURVE_POINTS.twist_hasher;
+        let dst = b"RECRYPT-V01-CS01-with-BN480G2_XMD:SHA-256_SVDW_RO_";
+        let cases = [
+            (
+                &b""[..],
+                [
+                    fp2_480(
+                        "f8fa9a6e7b4cc22b48ff1275b9113502976ba77be200cefeb4a51d15b8284131ce002c3f4ce4cd6633154fc539098aa3ea48d2e40b6f25c221173d30",
+                        "2a1ebafa30d45c93fff461dc63632dd6b5f1bc24696b3067d3c0b0f619bc7b54e00d63eabce17d044bf61ea300022bad7305ac3a2c70a624d91fd189",
+                    ),
+                    fp2_480(
+                        "a3d648695f97232524b94ee9f393303cfaf8ec8fc5668a0ee74a73de1774d110b74223f08bcba299bd9afbceab1e5d1c4fa7c4634874d7a67a55845d",
+                        "23c73706403249bf0d409eea38aa340848c4db29b04cd26395ab90058afcda5d502bcc53d34f0e1ce77af06a46a350e5abb7db13859b9081766c992d",
+                    ),
+                ],
+                (
+                    fp2_480(
+                        "154a0c6f99468ea8089c6474c25fa752eb39c140fb0f23b39bdaf8f743b7d2e92663a3744c9142d3c15c5b073dff1b1800b0d7ab26a5b07d7f95e6fb",
+                        "ad73b188a28474f697a381fbbbfd79489f733e4f8783dff3a499a663420d74982f475f9d936250caaf765393e470c366e7fb34c4cf99de21e709aa7f",
+                    ),
+                    fp2_480(
+                        "20b7ddfb69d698fb6e7b78981ddada1c11c440fd4907ccca2f7451682f81f9eba89a548b35872e022bd1345db9d7cb64a4f56eb1df9a2c1f375c0026",
+                        "fb09cc818ac654e570d32bb4b81ad74e1a17346a1e8005edeb910e743239c4e09c14206bb367831b2dda139d6c6c3654a48c34faa47b2f54fbad1e7f",
+                    ),
+                ),
+                (
+                    fp2_480(
+                        "da96f729fdebe86424a88ce0e90ffd9e372c2837548f6ed7699140ce8a592f46d46b0f7f5f434c04ac9069b672e79a7801143697c85b3c25bd0a0d45",
+                        "69b39f01297b728b77d598b5903ac0ab4a6e4da61fb2f36edbdf1bf8504de046b27ef3439ceff6ba35abaa604949b05dc1f204283d507ab202212d0a",
+                    ),
+                    fp2_480(
+                        "942fab9b9ee3f4cdaad02ae66cff7f6b765e19944772081f5cc5f332528744a39fdd0e9ae9b931625eaa4e60d7e46da93022f12ea6d28073190c80b6",
+                        "0f0da6a60c096e269b89d2bd78bd9c36fb79fc1011713f2f9f6389a5515b4c50cf0847ac210b192c85f33ba0ba8fa19d9191253057948bb664178c68",
+                    ),
+                ),
+            ),
+            (
+                &b"abc"[..],
+                [
+                    fp2_480(
+                        "4debf6d6d53facd17609ec5b2092581a5f49dc5804d60b531e88bd1588b232848f5b0188d766c070c74ec7421b39886d289e2f26f1ba99ae145160dd",
+                        "988dec17b4d01989b5092a91ac8959a45bfd446448d235daa7fea0e335ca9dd5005510675c2e9b4a59b5a335ae472a780805108773dcfd4096c2d90f",
+                    ),
+                    fp2_480(
+                        "8110e29087c1df3826e647f9db63c1c1535cb1c1064ffb9af31371acf40b9bf8c89b91f363fd9bc8a99444741618c32251fbbf91508ce0ae91683c97",
+                        "5d97491f6df99b8fd75d98c267fdfcafcbdd778dbe1d1f8e2d953098983f0fa803aa774559202e4d090e6b81a1c71ebf845b304fc0739ae3bb0678c5",
+                    ),
+                ],
+                (
+                    fp2_480(
+                        "99ef4c9ff28f9f7d2bbce0ba9edb3936fc24f463a0801e0422310b9da46712f7206ce040c438ebbfefcf6266054250fa5249ca98970a23a80800ea69",
+                        "0289f663c721d0f8caff64f1a65179d5dfe5527f64ba17f4d1614f4c8e3e586d372aa4ee40a147854c10d1eb57d79661ff4c6e0cf36b031203902a79",
+                    ),
+                    fp2_480(
+                        "1dee5c3f47962c79750280174debd099003e3d51bd08b2c442b5735ba679596632f810d7cfcdc4c5db39c2451c63975e0b674216ed9e0042be00aaf5",
+                        "761e2c34d2bcaf32a5759cec0929ba9a19fffd103468026ad8a65bd02e1cf00ac3f67280778f219e84cce2bb5abbfc01a628db960c7d2e871d9f1546",
+                    ),
+                ),
+                (
+                    fp2_480(
+                        "1fef4a49e495a4cad2644ecdde51123868012c557110521723edd96e0e7a0850f72bb975884087f702d03d947856028a7b63854794f396aaa33900d6",
+                        "fa1c94f39a90aca1a022f40798b980c5b42d94de68d0e6b69d61a0067f6ef0f498091641c875bf1e1ebcce60410a92111f8bd92002ff86fb4c233b1c",
+                    ),
+                    fp2_480(
+                        "bcc9ae9c6cda7298061489f484f363c5c9576dc47bcc03549af562cd783775cb2cc484eee3f014d1f262ac165df6f934baa010c392eb2c2c007fe061",
+                        "43ed42bebbf2afc4e81b6b85932e0bc6137640bf570adeee809cfd940c1c9fffad17cef176a7ea6e5b0a3a14cafdeba939eaab44b23a23c84e2656da",
+                    ),
+                ),
+            ),
+        ];
+        for (msg, u, q0, expected) in cases {
+            assert_eq!(hasher.hash_to_field(msg, dst, &Sha256), u);
+            assert_eq!(hasher.map_to_curve(u[0]), TwistedHPoint::new(q0.0, q0.1));
+            let point = hasher.hash_to_curve(msg, dst, &Sha256);
+            assert_eq!(point, TwistedHPoint::new(expected.0, expected.1));
+            assert_eq!(point * order_480(), Zero::zero());
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn constant_time_helpers_match_variable_time_ones(a in arb_fp2()) {
+            prop_assert_eq!(is_square(a), Monty256::from(u8::from(a.sqrt().is_some())));
+            prop_assert_eq!(sgn0(a), Monty256::from(u8::from(a.sgn0())));
+            let square = a.square();
+            prop_assert_eq!(sqrt_of_square(square).square(), square);
+            prop_assert_eq!(inv0(a) * a, Fp2Elem::one());
+        }
+    }
+
     // macro to produce property-based tests for each FP type
     macro_rules! fp_proptest {
         ($fp:ident, $arb_fp:ident, $arb_homogeneous:ident, $arb_homogeneous_fp2:ident, $m:ident) => {
@@ -721,6 +1268,24 @@ pub mod test {
                 assert_eq!(arb_tw_hpoint, hpoint)
             }
 
+            #[test]
+            fn roundtrip_compressed(arb_hpoint in $arb_homogeneous()) {
+                prop_assume!(arb_hpoint != zero());
+                let compressed = arb_hpoint.to_compressed().unwrap();
+                prop_assert_eq!(compressed.len(), $fp::ENCODED_SIZE_BYTES + 1);
+                prop_assert_eq!(HomogeneousPoint::<$fp>::from_compressed(&compressed).unwrap(), arb_hpoint);
+                prop_assert_eq!(HomogeneousPoint::<$fp>::from_compressed(&(-arb_hpoint).to_compressed().unwrap()).unwrap(), -arb_hpoint);
+            }
+
+            #[test]
+            fn twisted_roundtrip_compressed(arb_tw_hpoint in $arb_homogeneous_fp2()) {
+                prop_assume!(arb_tw_hpoint != zero());
+                let compressed = arb_tw_hpoint.to_compressed().unwrap();
+                prop_assert_eq!(compressed.len(), Fp2Elem::<$fp>::ENCODED_SIZE_BYTES + 1);
+                prop_assert_eq!(TwistedHPoint::<$fp>::from_compressed(&compressed).unwrap(), arb_tw_hpoint);
+                prop_assert_eq!(TwistedHPoint::<$fp>::from_compressed(&(-arb_tw_hpoint).to_compressed().unwrap()).unwrap(), -arb_tw_hpoint);
+            }
+
             #[test]
             fn double_is_mul_2_fp256(arb_hpoint in $arb_homogeneous()) {
                 prop_assert_eq!(arb_hpoint.double(), arb_hpoint * $fp::from(2u8));
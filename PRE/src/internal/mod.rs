@@ -1,7 +1,7 @@
 use crate::internal::bit_repr::BitRepr;
 use crate::internal::curve::CurvePoints;
 use crate::internal::ed25519::{
-    Ed25519Signature, Ed25519Signing, PublicSigningKey, SigningKeypair,
+    Ed25519Signature, Ed25519Signing, Ed25519SigningKey, PublicSigningKey,
 };
 use crate::internal::field::ExtensionField;
 use crate::internal::field::Field;
@@ -12,7 +12,7 @@ use crate::internal::hashable::{Hashable, Hashable32, Hashable60};
 use crate::internal::homogeneouspoint::{HomogeneousPoint, PointErr, TwistedHPoint};
 use crate::internal::pairing::Pairing;
 use crate::internal::pairing::PairingConfig;
-use crate::internal::sha256::Sha256Hashing;
+use crate::internal::sha256::{HashDomain, ProtocolVersion, Sha256Hashing};
 use crate::nonemptyvec::NonEmptyVec;
 use clear_on_drop::clear::Clear;
 use gridiron::digits::constant_bool::ConstantBool;
@@ -32,6 +32,7 @@ pub mod bytedecoder;
 pub mod curve;
 pub mod ed25519;
 pub mod field;
+pub mod fixedbase;
 pub mod fp;
 pub mod fp12elem;
 pub mod fp2elem;
@@ -43,6 +44,8 @@ pub mod pairing;
 pub mod rand_bytes;
 pub mod schnorr;
 pub mod sha256;
+pub mod threshold;
+pub mod wire;
 
 use crate::api;
 use crate::api_480;
@@ -61,6 +64,7 @@ quick_error! {
             from()
         }
         CorruptReencryptionKey {}
+        ProtocolVersionMismatch {}
     }
 }
 
@@ -210,9 +214,13 @@ impl Hashable for AuthHash {
 }
 
 impl AuthHash {
-    pub fn create<A: Hashable, F: Sha256Hashing>(hash_func: &F, a: &A) -> AuthHash {
+    pub fn create<A: Hashable, F: Sha256Hashing>(
+        hash_func: &F,
+        version: ProtocolVersion,
+        a: &A,
+    ) -> AuthHash {
         AuthHash {
-            bytes: hash_func.hash(a),
+            bytes: version.hash(hash_func, HashDomain::AuthHash, a),
         }
     }
 }
@@ -232,6 +240,7 @@ pub enum EncryptedValue<T: Field + Hashable> {
 /// encryptedMessage - the encrypted value.
 /// authHash - Authentication hash for the plaintext.
 /// encryptionBlocks - A vector of blocks which describes how to transform the encrypted data to be decrypted by another party.
+/// version - protocol version the value was encrypted (and transformed) with.
 #[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct ReencryptedValue<T: Field> {
@@ -239,6 +248,7 @@ pub struct ReencryptedValue<T: Field> {
     pub encrypted_message: Fp12Elem<T>,
     pub auth_hash: AuthHash,
     pub encryption_blocks: NonEmptyVec<ReencryptionBlock<T>>,
+    pub version: ProtocolVersion,
 }
 
 impl<FP: Field + Hashable> ReencryptedValue<FP> {
@@ -251,6 +261,7 @@ impl<FP: Field + Hashable> ReencryptedValue<FP> {
             encrypted_message: encrypted_value.encrypted_message,
             auth_hash: encrypted_value.auth_hash,
             encryption_blocks: re_blocks,
+            version: encrypted_value.version,
         }
     }
 
@@ -263,6 +274,7 @@ impl<FP: Field + Hashable> ReencryptedValue<FP> {
             encrypted_message: self.encrypted_message,
             auth_hash: self.auth_hash,
             encryption_blocks: new_blocks,
+            version: self.version,
         }
     }
 }
@@ -306,12 +318,14 @@ impl<FP: Field + Hashable> Hashable for ReencryptionBlock<FP> {
 /// `ephemeral_public_key`  - public key of the private key that was used to encrypt
 /// `encrypted_message`     - the encrypted value.
 /// `auth_hash`             - Authentication hash for the plaintext.
+/// `version`               - protocol version the value was encrypted with.
 #[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct EncryptedOnceValue<T: Field> {
     pub ephemeral_public_key: PublicKey<T>,
     pub encrypted_message: Fp12Elem<T>,
     pub auth_hash: AuthHash,
+    pub version: ProtocolVersion,
 }
 
 impl<T: Field + Hashable> Hashable for EncryptedValue<T> {
@@ -321,7 +335,9 @@ impl<T: Field + Hashable> Hashable for EncryptedValue<T> {
                 ephemeral_public_key,
                 encrypted_message,
                 auth_hash,
+                version,
             }) => vec![
+                version.to_bytes(),
                 ephemeral_public_key.to_bytes(),
                 encrypted_message.to_bytes(),
                 auth_hash.to_bytes(),
@@ -332,7 +348,9 @@ impl<T: Field + Hashable> Hashable for EncryptedValue<T> {
                 encrypted_message,
                 auth_hash,
                 encryption_blocks,
+                version,
             }) => vec![
+                version.to_bytes(),
                 ephemeral_public_key.to_bytes(),
                 encrypted_message.to_bytes(),
                 auth_hash.to_bytes(),
@@ -415,6 +433,46 @@ fn pow_for_square<T: One + Mul<T, Output = T> + Copy + Square>(t: T, exp: u32) -
     }
 }
 
+/// Invert every element of `fs` with a single inversion (Montgomery's trick).
+/// None of the elements may be zero.
+fn batch_inv<T: Field>(fs: &[T]) -> Vec<T> {
+    // prefixes[i] is the product of fs[0..i]
+    let mut prefixes = Vec::with_capacity(fs.len());
+    let product = fs.iter().fold(T::one(), |acc, f| {
+        prefixes.push(acc);
+        acc * *f
+    });
+    // walk backwards, peeling one element off the inverse of the product at a time
+    let mut product_inv = product.inv();
+    let mut result: Vec<T> = fs
+        .iter()
+        .zip(prefixes)
+        .rev()
+        .map(|(f, prefix)| {
+            let f_inv = product_inv * prefix;
+            product_inv = product_inv * *f;
+            f_inv
+        })
+        .collect();
+    result.reverse();
+    result
+}
+
+/// Raise t to the big-endian exponent `exp` using square and multiply.
+/// This is not constant time in the exponent, so it must only be used with public exponents.
+pub fn pow_vartime<T: One + Mul<T, Output = T> + Copy + Square>(t: T, exp: &[u8]) -> T {
+    let mut result = T::one();
+    for byte in exp {
+        for i in (0..8).rev() {
+            result = result.square();
+            if (byte >> i) & 1 == 1 {
+                result = result * t;
+            }
+        }
+    }
+    result
+}
+
 pub fn array_concat_32<T: Copy + Zero>(one: &[T; 32], two: &[T; 32]) -> [T; 64] {
     let mut result: [T; 64] = [T::zero(); 64];
     result[0..32].copy_from_slice(&one[..]);
@@ -453,13 +511,13 @@ where
     pairing.final_exp(fp12_elem)
 }
 
-///Generate a public key using the private key and generator point.
-pub fn public_keygen<T>(private_key: PrivateKey<T>, generator: HomogeneousPoint<T>) -> PublicKey<T>
+///Generate a public key using the private key and the generator point of `curve_points`.
+pub fn public_keygen<T>(private_key: PrivateKey<T>, curve_points: &CurvePoints<T>) -> PublicKey<T>
 where
     T: Field + BitRepr + ConstantSwap,
 {
     PublicKey {
-        value: generator * private_key.value,
+        value: curve_points.generator_table.mul(private_key.value),
     }
 }
 
@@ -474,7 +532,7 @@ where
 /// SignedValue<T> - contains the value `payload`, the public signingkey, and the computed signature
 fn sign_value<T, F: Ed25519Signing>(
     payload: T,
-    signing_keypair: &SigningKeypair,
+    signing_keypair: &F::Key,
     ed25519: &F,
 ) -> SignedValue<T>
 where
@@ -489,6 +547,31 @@ where
     }
 }
 
+/// The curve, pairing and the hashing and signing implementations that `encrypt`, `generate_reencryption_key` and
+/// `reencrypt` work with. The protocol version isn't part of it, since reencrypting takes the version of the value.
+pub struct Suite<'a, FP, H, S> {
+    pub curve_points: &'a CurvePoints<FP>,
+    pub pairing: &'a Pairing<FP>,
+    pub sha256: &'a H,
+    pub ed25519: &'a S,
+}
+
+impl<'a, FP, H, S> Suite<'a, FP, H, S> {
+    pub fn new(
+        curve_points: &'a CurvePoints<FP>,
+        pairing: &'a Pairing<FP>,
+        sha256: &'a H,
+        ed25519: &'a S,
+    ) -> Suite<'a, FP, H, S> {
+        Suite {
+            curve_points,
+            pairing,
+            sha256,
+            ed25519,
+        }
+    }
+}
+
 /// Encrypt plaintext to publicKey. This public key encryption is not meant to encrypt arbitrary
 /// data; instead, you should generate a random plaintext value (an element of G_T), apply a
 /// SHA256 hash to it to generate a 32-bit number, and use that as a key for a symmetric algorithm
@@ -505,10 +588,8 @@ where
 /// `plaintext`             - the value to encrypt - must be an element of G_T
 /// `encrypting_key`        - a random private key value chosen just for this plaintext
 /// `signing_keypair`       - the public portion of the encrypter's signing key pair
-/// `pairing`               - Optimal Ate Pairing
-/// `curve_points`          - IronCore's curve
-/// `hash`                  - Sha256Hashing instance
-/// `sign`                  - Ed25519Signing instance
+/// `suite`                 - IronCore's curve, the pairing, and the Sha256Hashing and Ed25519Signing instances
+/// `version`               - protocol version to encrypt with
 ///
 /// # Return
 /// SignedValue[EncryptedValue] - the plaintext encrypted to the specified public key,
@@ -517,26 +598,33 @@ pub fn encrypt<T: Clone, F: Sha256Hashing, G: Ed25519Signing>(
     to_public_key: PublicKey<T>,
     plaintext: Fp12Elem<T>, // @clintfred can this be any fp12? or an rth root?
     encrypting_key: PrivateKey<T>,
-    signing_keypair: &SigningKeypair,
-    pairing: &Pairing<T>,
-    curve_points: &CurvePoints<T>,
-    hash: &F,
-    sign: &G,
+    signing_keypair: &G::Key,
+    suite: &Suite<T, F, G>,
+    version: ProtocolVersion,
 ) -> ErrorOr<SignedValue<EncryptedValue<T>>>
 where
     T: ExtensionField + PairingConfig + BitRepr + Hashable + ConstantSwap,
 {
+    let Suite {
+        curve_points,
+        pairing,
+        sha256: hash,
+        ed25519: sign,
+    } = *suite;
     let ephem_pub_key = PublicKey {
-        value: curve_points.generator * encrypting_key,
+        value: curve_points.generator_table.mul(encrypting_key),
     };
-    let encrypted_message =
-        pairing.pair(to_public_key.value * encrypting_key, curve_points.g1)? * plaintext;
-    let auth_hash = AuthHash::create(hash, &(&ephem_pub_key, &plaintext));
+    let encrypted_message = pairing.pair_prepared(
+        to_public_key.value * encrypting_key,
+        &curve_points.g1_prepared,
+    )? * plaintext;
+    let auth_hash = AuthHash::create(hash, version, &(&ephem_pub_key, &plaintext));
     Ok(sign_value(
         EncryptedValue::EncryptedOnce(EncryptedOnceValue {
             ephemeral_public_key: ephem_pub_key,
             encrypted_message,
             auth_hash,
+            version,
         }),
         signing_keypair,
         sign,
@@ -590,6 +678,7 @@ where
                     encrypted_once_value.ephemeral_public_key,
                     unverified_plaintext,
                     hash,
+                    encrypted_once_value.version,
                 )
             }
             EncryptedValue::Reencrypted(re_value) => {
@@ -600,22 +689,135 @@ where
                     re_value.ephemeral_public_key,
                     unverified_plaintext,
                     hash,
+                    re_value.version,
                 )
             }
         },
     )
 }
 
+/// Decrypt many signed encrypted values with the same private key. The result for each value is
+/// the same as `decrypt` would return for it, but the Ed25519 signatures are checked together with
+/// `Ed25519Signing::verify_batch` and all of the pairings share one `Pairing::final_exp_batch`.
+///
+/// # Arguments
+/// `private_key`               - private key of the recipient of the messages
+/// `signed_encrypted_values`   - outputs of encrypt() or reencrypt()
+/// `pairing`                   - Optimal Ate Pairing
+/// `curve_points`              - IronCore's curve
+/// `hash`                      - Sha256Hashing instance
+/// `signing`                   - Ed25519Signing instance
+///
+/// # Return
+/// one ErrorOr[FP12Elem] per value, in the same order as `signed_encrypted_values`
+pub fn decrypt_batch<T, H: Sha256Hashing, G: Ed25519Signing>(
+    private_key: PrivateKey<T>,
+    signed_encrypted_values: Vec<SignedValue<EncryptedValue<T>>>,
+    pairing: &Pairing<T>,
+    curve_points: &CurvePoints<T>,
+    hash: &H,
+    signing: &G,
+) -> Vec<ErrorOr<Fp12Elem<T>>>
+where
+    T: ExtensionField
+        + PairingConfig
+        + BitRepr
+        + Hashable
+        + From<[u8; 64]>
+        + Default
+        + ConstantSwap,
+{
+    let signatures_valid = signing.verify_batch(
+        &signed_encrypted_values
+            .iter()
+            .map(|value| (value, &value.signature, &value.public_signing_key))
+            .collect::<Vec<_>>(),
+    );
+    let miller_loop = |point: HomogeneousPoint<T>| {
+        pairing.miller_loop_product(&[(-(point * private_key.value), &curve_points.g1_prepared)])
+    };
+    // run the Miller loops of every value whose signature is valid, leaving the final exponentiations for later
+    let pending: Vec<ErrorOr<_>> = signed_encrypted_values
+        .into_iter()
+        .zip(signatures_valid)
+        .map(|(signed_value, signature_valid)| {
+            if !signature_valid {
+                return Err(InternalError::InvalidEncryptedMessageSignature);
+            }
+            let loops = match &signed_value.payload {
+                EncryptedValue::EncryptedOnce(encrypted_once_value) => {
+                    vec![miller_loop(
+                        encrypted_once_value.ephemeral_public_key.value,
+                    )?]
+                }
+                EncryptedValue::Reencrypted(re_value) => {
+                    let re_blocks_last = re_value.encryption_blocks.last();
+                    vec![
+                        miller_loop(re_blocks_last.public_key.value)?,
+                        miller_loop(re_blocks_last.rand_re_public_key.value)?,
+                    ]
+                }
+            };
+            Ok((signed_value.payload, loops))
+        })
+        .collect();
+    let loops: Vec<Fp12Elem<T>> = pending
+        .iter()
+        .flatten()
+        .flat_map(|(_, loops)| loops.iter().copied())
+        .collect();
+    let mut pairs = pairing.final_exp_batch(&loops).into_iter();
+    let mut next_pair = || {
+        pairs
+            .next()
+            .expect("Developer error: one final exponentiation per Miller loop")
+    };
+    pending
+        .into_iter()
+        .map(|pending_value| match pending_value?.0 {
+            EncryptedValue::EncryptedOnce(encrypted_once_value) => compute_and_compare_auth_hash(
+                encrypted_once_value.auth_hash,
+                encrypted_once_value.ephemeral_public_key,
+                encrypted_once_value.encrypted_message * next_pair(),
+                hash,
+                encrypted_once_value.version,
+            ),
+            EncryptedValue::Reencrypted(re_value) => {
+                let re_blocks_last = re_value.encryption_blocks.last();
+                let sec_to_last_k = KValue(re_blocks_last.encrypted_temp_key * next_pair());
+                let sec_to_last_rand_re_k =
+                    KValue(re_blocks_last.encrypted_rand_re_temp_key * next_pair());
+                let unverified_plaintext = unwrap_reencryption_blocks(
+                    &re_value,
+                    sec_to_last_k,
+                    sec_to_last_rand_re_k,
+                    curve_points,
+                    pairing,
+                    hash,
+                )?;
+                compute_and_compare_auth_hash(
+                    re_value.auth_hash,
+                    re_value.ephemeral_public_key,
+                    unverified_plaintext,
+                    hash,
+                    re_value.version,
+                )
+            }
+        })
+        .collect()
+}
+
 fn compute_and_compare_auth_hash<FP, H: Sha256Hashing>(
     candidate_auth_hash: AuthHash,
     public_key: PublicKey<FP>,
     unverified_plaintext: Fp12Elem<FP>,
     hash: &H,
+    version: ProtocolVersion,
 ) -> ErrorOr<Fp12Elem<FP>>
 where
     FP: ExtensionField + PairingConfig + BitRepr + Hashable,
 {
-    let computed_auth_hash = AuthHash::create(hash, &(&public_key, &unverified_plaintext));
+    let computed_auth_hash = AuthHash::create(hash, version, &(&public_key, &unverified_plaintext));
 
     if candidate_auth_hash != computed_auth_hash {
         Result::Err(InternalError::AuthHashMatchFailed)
@@ -643,7 +845,6 @@ fn decrypt_encrypted_once<T>(
 where
     T: ExtensionField + PairingConfig + BitRepr + ConstantSwap,
 {
-    let g1 = curve_points.g1;
     let EncryptedOnceValue {
         ephemeral_public_key,
         encrypted_message,
@@ -652,7 +853,11 @@ where
 
     //This is because:
     // m*pair(P,Q)*pair(P,-Q) = m*pair(P,Q)*pair(P,Q)^(-1) = m
-    Ok(*encrypted_message * pairing.pair(-(ephemeral_public_key.value * private_key.value), g1)?)
+    Ok(*encrypted_message
+        * pairing.pair_prepared(
+            -(ephemeral_public_key.value * private_key.value),
+            &curve_points.g1_prepared,
+        )?)
 }
 /// Decrypt a reencryptedValue using the provided privateKey.
 ///
@@ -691,15 +896,58 @@ where
 
     let sec_to_last_k = KValue(
         *encrypted_k_last
-            * pairing.pair(-re_pub_key_last.value * private_key.value, curve_points.g1)?,
+            * pairing.pair_prepared(
+                -re_pub_key_last.value * private_key.value,
+                &curve_points.g1_prepared,
+            )?,
     );
     let sec_to_last_rand_re_k = KValue(
         *enc_rand_re_k_last
-            * pairing.pair(
+            * pairing.pair_prepared(
                 -rand_re_pub_key_last.value * private_key.value,
-                curve_points.g1,
+                &curve_points.g1_prepared,
             )?,
     );
+    unwrap_reencryption_blocks(
+        reencrypted_value,
+        sec_to_last_k,
+        sec_to_last_rand_re_k,
+        curve_points,
+        pairing,
+        sha256,
+    )
+}
+
+/// Finish decrypting a reencryptedValue once the temp keys of its last reencryption block have
+/// been recovered with the recipient's private key.
+///
+/// # Arguments
+/// `reencrypted_value`     - reencrypted value to decrypt.
+/// `sec_to_last_k`         - decrypted `encrypted_temp_key` of the last reencryption block
+/// `sec_to_last_rand_re_k` - decrypted `encrypted_rand_re_temp_key` of the last reencryption block
+///
+/// # Return
+///  decrypted value as FP12 element
+fn unwrap_reencryption_blocks<FP, H>(
+    reencrypted_value: &ReencryptedValue<FP>,
+    sec_to_last_k: KValue<FP>,
+    sec_to_last_rand_re_k: KValue<FP>,
+    curve_points: &CurvePoints<FP>,
+    pairing: &Pairing<FP>,
+    sha256: &H,
+) -> ErrorOr<Fp12Elem<FP>>
+where
+    FP: Hashable
+        + ExtensionField
+        + PairingConfig
+        + BitRepr
+        + From<[u8; 64]>
+        + Default
+        + ConstantSwap,
+    H: Sha256Hashing,
+{
+    let re_blocks = &reencrypted_value.encryption_blocks;
+    let version = reencrypted_value.version;
     //We're going through the list backwards because we unravel the reencryption blocks from last to first, the last one is special so it's done first.
     let (first_k, first_rand_re_k) = re_blocks.to_vec().iter().rev().skip(1).try_fold(
         (sec_to_last_k, sec_to_last_rand_re_k),
@@ -710,13 +958,13 @@ where
                 rand_re_public_key: next_rand_re_pub_key,
                 encrypted_rand_re_temp_key: next_enc_rand_re_k,
             } = curr_re_block;
-            let curr_k_hash = hash2(curr_k, curve_points, sha256);
+            let curr_k_hash = hash2(curr_k, curve_points, sha256, version);
             let new_k = KValue(*next_enc_k * pairing.pair(-next_re_pub_key.value, curr_k_hash)?);
             let new_rand_re_k = KValue(
                 *next_enc_rand_re_k
                     * pairing.pair(
                         -next_rand_re_pub_key.value,
-                        hash2(curr_rand_re_k, curve_points, sha256) + curr_k_hash,
+                        hash2(curr_rand_re_k, curve_points, sha256, version) + curr_k_hash,
                     )?,
             );
             Ok((new_k, new_rand_re_k))
@@ -725,7 +973,8 @@ where
     Ok(reencrypted_value.encrypted_message
         * pairing.pair(
             reencrypted_value.ephemeral_public_key.value.neg(),
-            hash2(first_k, curve_points, sha256) + hash2(first_rand_re_k, curve_points, sha256),
+            hash2(first_k, curve_points, sha256, version)
+                + hash2(first_rand_re_k, curve_points, sha256, version),
         )?)
 }
 
@@ -738,7 +987,7 @@ where
 /// # Return
 /// Some around the payload if the signature was valid, or None otherwise
 ///
-fn verify_signed_value<T: Hashable + Clone, G: Ed25519Signing>(
+pub fn verify_signed_value<T: Hashable + Clone, G: Ed25519Signing>(
     signed_value: SignedValue<T>,
     sign: &G,
 ) -> Option<T> {
@@ -763,9 +1012,8 @@ fn verify_signed_value<T: Hashable + Clone, G: Ed25519Signing>(
 /// `reencryption_private_key`  - a random private key
 /// `new_k`                     - a random FP12 element
 /// `signing_keypair`           - Ed25519 keypair to use to sign reencryption key
-/// `curve_points`              - IronCore's curve
-/// `sha256`                    - Sha256 instance
-/// `ed25519`                   - Ed25519Signing instance
+/// `suite`                     - IronCore's curve, the pairing, and the Sha256 and Ed25519Signing instances
+/// `version`                   - protocol version of the values the key will transform
 ///
 /// # Return
 ///  reencryption key, along with an Ed25519 public signing key and Ed25519 signature
@@ -776,11 +1024,9 @@ pub fn generate_reencryption_key<FP, H, S>(
     to_public_key: PublicKey<FP>,
     reencryption_private_key: PrivateKey<FP>,
     new_k: KValue<FP>,
-    signing_keypair: &SigningKeypair,
-    curve_points: &CurvePoints<FP>,
-    pairing: &Pairing<FP>,
-    sha256: &H,
-    ed25519: &S,
+    signing_keypair: &S::Key,
+    suite: &Suite<FP, H, S>,
+    version: ProtocolVersion,
 ) -> ErrorOr<SignedValue<ReencryptionKey<FP>>>
 where
     FP: ExtensionField
@@ -793,17 +1039,23 @@ where
     H: Sha256Hashing,
     S: Ed25519Signing,
 {
-    let g1 = curve_points.g1;
-
-    let re_public_key = public_keygen(reencryption_private_key, curve_points.generator);
+    let Suite {
+        curve_points,
+        pairing,
+        sha256,
+        ed25519,
+    } = *suite;
+    let re_public_key = public_keygen(reencryption_private_key, curve_points);
     let p = to_public_key.value * reencryption_private_key.value;
-    let encrypted_k = pairing.pair(p, g1)? * new_k.0;
-    let hashed_k = hash2(new_k, curve_points, sha256) + (g1.neg() * from_private_key);
+    let encrypted_k = pairing.pair_prepared(p, &curve_points.g1_prepared)? * new_k.0;
+    let hashed_k =
+        hash2(new_k, curve_points, sha256, version) - curve_points.g1_table.mul(from_private_key);
     let reencryption_key = ReencryptionKey {
         re_public_key,
         to_public_key,
         encrypted_k,
         hashed_k,
+        version,
     };
 
     Ok(sign_value(reencryption_key, signing_keypair, ed25519))
@@ -846,25 +1098,36 @@ impl From<api_480::Plaintext> for KValue<fp_480::Monty> {
 /// `k_value`       - Fp12 element to use in the hash
 /// `curve_points`  - IronCore's curve
 /// `sha256`        - Sha256 implementation
+/// `version`       - protocol version of the value or key being hashed for
 ///
 fn hash2<FP, H>(
     k_value: KValue<FP>,
     curve_points: &CurvePoints<FP>,
     sha256: &H,
+    version: ProtocolVersion,
 ) -> TwistedHPoint<FP>
 where
     FP: Hashable + From<[u8; 64]> + BitRepr + Default + ExtensionField + ConstantSwap,
     H: Sha256Hashing,
 {
-    let hash_element = curve_points.hash_element;
-    //Produce a 512 bit byte vector, which ensures we have a big enough value for 480 and Fp
-    //We use a constant value combined with the entire fp12 element so we don't leak information about the fp12 structure.
-    let bytes = array_concat_32(
-        &sha256.hash(&(&0u8, &k_value)),
-        &sha256.hash(&(&1u8, &k_value)),
-    );
-    let fp = FP::from(bytes);
-    hash_element * fp
+    match version {
+        ProtocolVersion::Legacy => {
+            //Produce a 512 bit byte vector, which ensures we have a big enough value for 480 and Fp
+            //We use a constant value combined with the entire fp12 element so we don't leak information about the fp12 structure.
+            let bytes = array_concat_32(
+                &sha256.hash(&(&0u8, &k_value)),
+                &sha256.hash(&(&1u8, &k_value)),
+            );
+            let fp = FP::from(bytes);
+            curve_points.hash_element_table.mul(fp)
+        }
+        // A multiple of `hash_element` has a known discrete log, so V2 hashes to the curve instead.
+        ProtocolVersion::V2 => curve_points.twist_hasher.hash_to_curve(
+            &k_value.to_bytes(),
+            &HashDomain::TransformKey.tag(),
+            sha256,
+        ),
+    }
 }
 
 /// A reencryption key allows a message encrypted to one public key (the key of the delegator)
@@ -877,6 +1140,7 @@ where
 ///                   successive levels of multi-hop transform encryption
 /// `hashed_k`      - a combination of the hash of K and the secret key of the delegator,
 ///                   used to recover `K` from `encrypted_k`
+/// `version`       - protocol version of `hashed_k`, and of the values the key can transform
 #[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct ReencryptionKey<FP: ExtensionField> {
@@ -884,11 +1148,18 @@ pub struct ReencryptionKey<FP: ExtensionField> {
     pub to_public_key: PublicKey<FP>,
     pub encrypted_k: Fp12Elem<FP>,
     pub hashed_k: TwistedHPoint<FP>,
+    pub version: ProtocolVersion,
 }
 
 impl<FP: Hashable + ExtensionField> Hashable for ReencryptionKey<FP> {
     fn to_bytes(&self) -> ByteVector {
-        (&self.re_public_key, &self.to_public_key, &self.encrypted_k).to_bytes()
+        (
+            &self.version,
+            &self.re_public_key,
+            &self.to_public_key,
+            &self.encrypted_k,
+        )
+            .to_bytes()
     }
 }
 
@@ -898,11 +1169,11 @@ impl<FP: BitRepr + ExtensionField + ConstantSwap> ReencryptionKey<FP> {
     pub fn augment(
         &self,
         priv_key: &PrivateKey<FP>,
-        g1: &TwistedHPoint<FP>,
+        curve_points: &CurvePoints<FP>,
     ) -> ReencryptionKey<FP> {
         //Note that because priv_key is an Fp negating it would not work, we have to negate the point or the result of
         //the multiplication.
-        let new_hashed_k = -(*g1 * priv_key.value) + self.hashed_k;
+        let new_hashed_k = -curve_points.g1_table.mul(priv_key.value) + self.hashed_k;
         ReencryptionKey {
             hashed_k: new_hashed_k,
             ..*self
@@ -918,8 +1189,8 @@ impl<FP: BitRepr + ExtensionField + ConstantSwap> ReencryptionKey<FP> {
 /// `signed_encrypted_value`  - A signed version of the encrypted value, which is encrypted to the delegating user.
 /// `rand_re_priv_key`            - A new random private key, which will be used to encrypt the rand_re_k.
 /// `rand_re_k`                 - A new random integer which is used to ensure that the reencryption block cannot be reused.
-/// `private_signing_key`     - The ED25519 private key to sign the reencryption block.
-/// `public_signing_key`      - The ED25519 public key matching the private_signing_key.
+/// `signing_keypair`         - The ED25519 keypair to sign the reencryption block.
+/// `suite`                   - IronCore's curve, the pairing, and the Sha256 and Ed25519Signing instances
 ///
 /// # Return
 /// Ok(ReencryptedValue) - if the value could be successfully reencrypted
@@ -929,11 +1200,8 @@ pub fn reencrypt<FP, S, H>(
     signed_encrypted_value: SignedValue<EncryptedValue<FP>>,
     rand_re_priv_key: PrivateKey<FP>,
     rand_re_k: KValue<FP>,
-    signing_keypair: &SigningKeypair,
-    ed25519: &S,
-    sha256: &H,
-    curve_points: &CurvePoints<FP>,
-    pairing: &Pairing<FP>,
+    signing_keypair: &S::Key,
+    suite: &Suite<FP, H, S>,
 ) -> ErrorOr<SignedValue<EncryptedValue<FP>>>
 where
     FP: Hashable
@@ -947,41 +1215,87 @@ where
     S: Ed25519Signing,
 {
     match (
-        verify_signed_value(signed_encrypted_value, ed25519),
-        verify_signed_value(signed_reencryption_key, ed25519),
+        verify_signed_value(signed_encrypted_value, suite.ed25519),
+        verify_signed_value(signed_reencryption_key, suite.ed25519),
     ) {
-        (Some(EncryptedValue::EncryptedOnce(encrypted_val)), Some(re_key)) => Ok(sign_value(
-            EncryptedValue::Reencrypted(reencrypt_encrypted_once(
-                re_key,
-                encrypted_val,
-                rand_re_priv_key,
-                rand_re_k,
-                curve_points,
-                pairing,
-                sha256,
-            )?),
-            signing_keypair,
-            ed25519,
-        )),
-
-        (Some(EncryptedValue::Reencrypted(encrypted_val)), Some(re_key)) => Ok(sign_value(
-            EncryptedValue::Reencrypted(reencrypt_reencrypted_value(
-                re_key,
-                &encrypted_val,
-                rand_re_priv_key,
-                rand_re_k,
-                curve_points,
-                pairing,
-                sha256,
-            )?),
+        (Some(encrypted_value), Some(re_key)) => reencrypt_verified(
+            re_key,
+            encrypted_value,
+            rand_re_priv_key,
+            rand_re_k,
             signing_keypair,
-            ed25519,
-        )),
+            suite,
+        ),
         (None, _) => Err(InternalError::InvalidEncryptedMessageSignature),
         (_, None) => Err(InternalError::CorruptReencryptionKey),
     }
 }
 
+/// Reencrypt an EncryptedValue whose signature, like that of the reencryption key, has already been verified.
+/// This is the part of `reencrypt` that is done once per value when transforming many values with the same key.
+///
+/// # Return
+/// Ok(ReencryptedValue) signed with `signing_keypair` if the value could be successfully reencrypted
+/// - Err(ProtocolVersionMismatch) - if the value and the reencryption key have different protocol versions.
+pub fn reencrypt_verified<FP, S, H>(
+    re_key: ReencryptionKey<FP>,
+    encrypted_value: EncryptedValue<FP>,
+    rand_re_priv_key: PrivateKey<FP>,
+    rand_re_k: KValue<FP>,
+    signing_keypair: &S::Key,
+    suite: &Suite<FP, H, S>,
+) -> ErrorOr<SignedValue<EncryptedValue<FP>>>
+where
+    FP: Hashable
+        + ExtensionField
+        + PairingConfig
+        + BitRepr
+        + From<[u8; 64]>
+        + Default
+        + ConstantSwap,
+    H: Sha256Hashing,
+    S: Ed25519Signing,
+{
+    let Suite {
+        curve_points,
+        pairing,
+        sha256,
+        ed25519,
+    } = *suite;
+    let value_version = match &encrypted_value {
+        EncryptedValue::EncryptedOnce(encrypted_val) => encrypted_val.version,
+        EncryptedValue::Reencrypted(encrypted_val) => encrypted_val.version,
+    };
+    if value_version != re_key.version {
+        return Err(InternalError::ProtocolVersionMismatch);
+    }
+    let reencrypted_value = match encrypted_value {
+        EncryptedValue::EncryptedOnce(encrypted_val) => reencrypt_encrypted_once(
+            re_key,
+            encrypted_val,
+            rand_re_priv_key,
+            rand_re_k,
+            curve_points,
+            pairing,
+            sha256,
+        )?,
+        EncryptedValue::Reencrypted(encrypted_val) => reencrypt_reencrypted_value(
+            re_key,
+            &encrypted_val,
+            rand_re_priv_key,
+            rand_re_k,
+            curve_points,
+            pairing,
+            sha256,
+        )?,
+    };
+    Ok(sign_value(
+        EncryptedValue::Reencrypted(reencrypted_value),
+        signing_keypair,
+        ed25519,
+    ))
+}
+
 /**
  * Reencrypt an EncryptedValue to a new key.
  * `reencryption_key` - The reencryption key, which allows a transform from a delegating user to another user
@@ -996,11 +1310,13 @@ fn reencrypt_encrypted_once<FP, H>(
         to_public_key,
         encrypted_k,
         hashed_k,
+        version,
     }: ReencryptionKey<FP>,
     EncryptedOnceValue {
         ephemeral_public_key,
         encrypted_message,
         auth_hash,
+        ..
     }: EncryptedOnceValue<FP>,
     rand_re_priv_key: PrivateKey<FP>,
     rand_re_temp_key: KValue<FP>,
@@ -1019,20 +1335,23 @@ where
     H: Sha256Hashing,
 {
     // encrypt and product auth hashes for the rand_re_temp_key
-    let rand_re_public_key = public_keygen(rand_re_priv_key, curve_points.generator);
+    let rand_re_public_key = public_keygen(rand_re_priv_key, curve_points);
 
-    let encrypted_rand_re_temp_key =
-        pairing.pair(to_public_key.value * rand_re_priv_key, curve_points.g1)? * rand_re_temp_key.0;
+    let encrypted_rand_re_temp_key = pairing.pair_prepared(
+        to_public_key.value * rand_re_priv_key,
+        &curve_points.g1_prepared,
+    )? * rand_re_temp_key.0;
     // Because this is the first reencryption, modify the encrypted_message using rand_re_temp_key
     // Note that this can be decrypted using the reencryption key
     let encrypted_msg_prime = pairing.pair(
         ephemeral_public_key.value,
-        hashed_k + hash2(rand_re_temp_key, curve_points, sha256),
+        hashed_k + hash2(rand_re_temp_key, curve_points, sha256, version),
     )? * encrypted_message;
     let new_encypted_data = EncryptedOnceValue {
         ephemeral_public_key,
         encrypted_message: encrypted_msg_prime,
         auth_hash,
+        version,
     };
     let reencryption_block = ReencryptionBlock {
         public_key: re_public_key,
@@ -1059,6 +1378,7 @@ fn reencrypt_reencrypted_value<FP, H>(
         to_public_key,
         encrypted_k,
         hashed_k,
+        version,
     }: ReencryptionKey<FP>,
     reencrypted_value: &ReencryptedValue<FP>,
     rand_re_priv_key: PrivateKey<FP>,
@@ -1088,14 +1408,16 @@ where
     } = re_blocks_last;
     let encrypted_k_prime_last =
         *encrypted_k_last * pairing.pair(re_pub_key_last.value, hashed_k)?; // re-encrypted K
-    let rand_re_pub_key = public_keygen(rand_re_priv_key, curve_points.generator);
-    let enc_rand_re_temp_key =
-        pairing.pair(to_public_key.value * rand_re_priv_key, curve_points.g1)? * rand_re_temp_key.0;
+    let rand_re_pub_key = public_keygen(rand_re_priv_key, curve_points);
+    let enc_rand_re_temp_key = pairing.pair_prepared(
+        to_public_key.value * rand_re_priv_key,
+        &curve_points.g1_prepared,
+    )? * rand_re_temp_key.0;
     // Modify the enc_rand_re_temp_key of the last block with the new random reencryption K
     let rand_re_k_last_prime = *enc_rand_re_k_last
         * pairing.pair(
             rand_re_pub_key_last.value,
-            hash2(rand_re_temp_key, curve_points, sha256) + hashed_k,
+            hash2(rand_re_temp_key, curve_points, sha256, version) + hashed_k,
         )?;
     let re_block_last_prime =
         re_blocks_last.with_temp_key(encrypted_k_prime_last, rand_re_k_last_prime);
@@ -1145,7 +1467,7 @@ pub(crate) fn take_lock<T>(m: &Mutex<T>) -> MutexGuard<T> {
 mod test {
     use super::*;
     use crate::api::test::DummyRandomBytes;
-    use crate::internal::ed25519::Ed25519;
+    use crate::internal::ed25519::{Ed25519, SigningKeypair};
     use crate::internal::fp::fp256_unsafe_from;
     use crate::internal::fp12elem::test::arb_fp12;
     use crate::internal::homogeneouspoint::test::arb_homogeneous_256;
@@ -1181,6 +1503,8 @@ mod test {
     struct Mocks;
 
     impl Ed25519Signing for Mocks {
+        type Key = SigningKeypair;
+
         fn sign<T: Hashable>(&self, _t: &T, _signing_keypair: &SigningKeypair) -> Ed25519Signature {
             Ed25519Signature::new([0; 64])
         }
@@ -1204,6 +1528,8 @@ mod test {
     struct AlwaysFailVerifyEd25519Signing;
 
     impl Ed25519Signing for AlwaysFailVerifyEd25519Signing {
+        type Key = SigningKeypair;
+
         fn sign<T: Hashable>(&self, _t: &T, _signing_keypair: &SigningKeypair) -> Ed25519Signature {
             Ed25519Signature::new([0; 64])
         }
@@ -1327,10 +1653,8 @@ mod test {
                 re_private_key,
                 salt,
                 &signing_keypair,
-                curve_points,
-                &pairing,
-                sha256,
-                ed25519).unwrap().payload;
+                &Suite::new(curve_points, &pairing, sha256, ed25519),
+                ProtocolVersion::Legacy).unwrap().payload;
 
 
             let good_encrypted_k = Fp12Elem::create_from_t(
@@ -1455,17 +1779,15 @@ mod test {
             fp256_unsafe_from("613430d6b5ffee80cb971c85f2ea779d2dd0c020dcdd31a93c46e56c5b2f3ca2")
                 .to_monty(),
         );
-        let pub_key = public_keygen(priv_key, curve_points.generator);
+        let pub_key = public_keygen(priv_key, curve_points);
         let plaintext = pt_fp12;
         let encrypt_result = encrypt(
             pub_key,
             plaintext,
             ephem_priv_key,
             &signing_keypair,
-            &pairing,
-            curve_points,
-            sha256,
-            ed25519,
+            &Suite::new(curve_points, &pairing, sha256, ed25519),
+            ProtocolVersion::Legacy,
         )
         .unwrap();
         let rand_re_priv_key = PrivateKey::from_fp256(
@@ -1480,10 +1802,8 @@ mod test {
             re_private,
             salt,
             &signing_keypair,
-            curve_points,
-            &pairing,
-            sha256,
-            ed25519,
+            &Suite::new(curve_points, &pairing, sha256, ed25519),
+            ProtocolVersion::Legacy,
         )
         .unwrap();
 
@@ -1493,10 +1813,7 @@ mod test {
             rand_re_priv_key,
             rand_re_k,
             &signing_keypair,
-            ed25519,
-            sha256,
-            curve_points,
-            &pairing,
+            &Suite::new(curve_points, &pairing, sha256, ed25519),
         )
         .unwrap();
 
@@ -1529,16 +1846,19 @@ mod test {
             fp256_unsafe_from("c478b0b05e9d5cc4a7aaa3e5f991f6f452fd26a72f5415a93c46e56c5b2f3d88")
                 .to_monty(),
         );
-        let pub_key = public_keygen(priv_key, curve_points.generator);
+        let pub_key = public_keygen(priv_key, curve_points);
         let encrypted_value = encrypt(
             pub_key,
             salt1,
             ephem_priv_key,
             &signing_keypair,
-            &pairing,
-            curve_points,
-            sha256,
-            &AlwaysFailVerifyEd25519Signing,
+            &Suite::new(
+                curve_points,
+                &pairing,
+                sha256,
+                &AlwaysFailVerifyEd25519Signing,
+            ),
+            ProtocolVersion::Legacy,
         )
         .unwrap();
         let decrypt_result = decrypt(
@@ -1573,16 +1893,14 @@ mod test {
             fp256_unsafe_from("c478b0b05e9d5cc4a7aaa3e5f991f6f452fd26a72f5415a93c46e56c5b2f3d88")
                 .to_monty(),
         );
-        let pub_key = public_keygen(priv_key, curve_points.generator);
+        let pub_key = public_keygen(priv_key, curve_points);
         let encrypted_value = encrypt(
             pub_key,
             salt1,
             ephem_priv_key,
             &signing_keypair,
-            &pairing,
-            curve_points,
-            sha256,
-            &Mocks,
+            &Suite::new(curve_points, &pairing, sha256, &Mocks),
+            ProtocolVersion::Legacy,
         )
         .unwrap();
 
@@ -1702,19 +2020,19 @@ mod test {
             fp256_unsafe_from("613430d6b5ffee80cb971c85f2ea779d2dd0c020dcdd31a93c46e56c5b2f3ca2")
                 .to_monty(),
         );
-        let pub_key = public_keygen(priv_key, curve_points.generator);
+        let pub_key = public_keygen(priv_key, curve_points);
         let priv_key2 = PrivateKey::from_fp256(
             //22266559432365357341903140497410248873090149633601160471165130153973144042608
             fp256_unsafe_from("313a6d10030318ffa481b32fa104b4a77d6ad640a87bade5ee9e4ddc5b2f3c70")
                 .to_monty(),
         );
-        let pub_key2 = public_keygen(priv_key2, curve_points.generator);
+        let pub_key2 = public_keygen(priv_key2, curve_points);
         let priv_key3 = PrivateKey::from_fp256(
             //33333359432365357341903140497410248873090149633601160471165130153973144042608
             fp256_unsafe_from("49b2034a4bc9614d95bdac29251fb567b2ed2b41b0d25be5ee9e4ddc5b2f3c70")
                 .to_monty(),
         );
-        let pub_key3 = public_keygen(priv_key3, curve_points.generator);
+        let pub_key3 = public_keygen(priv_key3, curve_points);
 
         let plaintext = gen_rth_root(&pairing, pt_fp12);
 
@@ -1724,10 +2042,8 @@ mod test {
             plaintext,
             ephem_priv_key,
             &signing_keypair,
-            &pairing,
-            curve_points,
-            sha256,
-            ed25519,
+            &Suite::new(curve_points, &pairing, sha256, ed25519),
+            ProtocolVersion::Legacy,
         )
         .unwrap();
         let rand_re_priv_key = PrivateKey::from_fp256(
@@ -1742,10 +2058,8 @@ mod test {
             re_private,
             salt1,
             &signing_keypair,
-            curve_points,
-            &pairing,
-            sha256,
-            ed25519,
+            &Suite::new(curve_points, &pairing, sha256, ed25519),
+            ProtocolVersion::Legacy,
         )
         .unwrap();
 
@@ -1756,10 +2070,7 @@ mod test {
             rand_re_priv_key,
             rand_re_k,
             &signing_keypair,
-            ed25519,
-            sha256,
-            curve_points,
-            &pairing,
+            &Suite::new(curve_points, &pairing, sha256, ed25519),
         )
         .unwrap();
 
@@ -1782,10 +2093,8 @@ mod test {
             re_priv_2,
             salt2,
             &signing_keypair,
-            curve_points,
-            &pairing,
-            sha256,
-            ed25519,
+            &Suite::new(curve_points, &pairing, sha256, ed25519),
+            ProtocolVersion::Legacy,
         )
         .unwrap();
 
@@ -1795,10 +2104,7 @@ mod test {
             rand_re_priv_key_2,
             rand_re_k_2,
             &signing_keypair,
-            ed25519,
-            sha256,
-            curve_points,
-            &pairing,
+            &Suite::new(curve_points, &pairing, sha256, ed25519),
         )
         .unwrap();
 
@@ -1853,7 +2159,7 @@ mod test {
         }
         #[test]
         fn encrypt_decrypt_roundtrip(priv_key in arb_priv_key(), plaintext in arb_fp12().prop_filter("", |a| !(*a == Fp12Elem::<fp_256::Monty>::zero()))) {
-            let pub_key = public_keygen(priv_key, curve::FP_256_CURVE_POINTS.generator);
+            let pub_key = public_keygen(priv_key, &curve::FP_256_CURVE_POINTS);
             let ephem_secret_key = PrivateKey::from_fp256(fp_256::Monty::from(42u8));
             let priv_signing_key = good_signing_keys();
             let pairing = pairing::Pairing::new();
@@ -1863,10 +2169,8 @@ mod test {
                 plaintext,
                 ephem_secret_key,
                 &priv_signing_key,
-                &pairing,
-                curve_points,
-                &Sha256,
-                &Ed25519
+                &Suite::new(curve_points, &pairing, &Sha256, &Ed25519),
+                ProtocolVersion::Legacy
             ).unwrap();
             let decrypt_result = decrypt(
                 priv_key,
@@ -1905,8 +2209,8 @@ mod test {
                 re_public_key: re_key.re_public_key,
                 to_public_key: re_key.to_public_key,
                 encrypted_k: re_key.encrypted_k,
-                hashed_k: new_hashed_k // replaced hashed_k!
-
+                hashed_k: new_hashed_k, // replaced hashed_k!
+                version: re_key.version,
             };
 
             prop_assert_eq!(signed_re_key.payload.to_bytes(), re_key_copy.to_bytes())
@@ -1941,10 +2245,8 @@ mod test {
                 *reencryption_private_key,
                 KValue(*new_k),
                 &ed25519::test::good_signing_keypair(),
-                &curve_points,
-                &pairing,
-                &Mocks,
-                &Mocks
+                &Suite::new(&curve_points, &pairing, &Mocks, &Mocks),
+                ProtocolVersion::Legacy
             ).unwrap()
         }
     }
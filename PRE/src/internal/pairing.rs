@@ -1,12 +1,12 @@
-use crate::internal::Square;
 use crate::internal::field::ExtensionField;
-use crate::internal::fp2elem::{Fp2Elem, Xi};
+use crate::internal::fp2elem::Fp2Elem;
 use crate::internal::fp6elem::Fp6Elem;
-use crate::internal::fp12elem::Fp12Elem;
+use crate::internal::fp12elem::{CompressedFp12Elem, Fp12Elem};
 use crate::internal::homogeneouspoint::Double;
 use crate::internal::homogeneouspoint::HomogeneousPoint;
 use crate::internal::homogeneouspoint::PointErr;
 use crate::internal::homogeneouspoint::TwistedHPoint;
+use crate::internal::{Square, batch_inv};
 use gridiron::fp_256;
 use gridiron::fp_480;
 use num_traits::{Inv, One, Zero};
@@ -44,123 +44,113 @@ where
         point_p: HomogeneousPoint<T>,
         point_q: TwistedHPoint<T>,
     ) -> Result<Fp12Elem<T>, PointErr> {
-        point_p
-            .normalize()
-            .map_or(Err(PointErr::ZeroPoint), |(px, py)| {
-                let mut f1: Fp12Elem<T> = One::one();
-                let mut f2: Fp2Elem<T> = One::one();
-                let neg_q = -point_q;
-                let point_result: TwistedHPoint<T> = <T as PairingConfig>::naf_for_loop()
-                    .iter()
-                    .fold(point_q, |acc, naf_value| {
-                        let mut point_r = acc;
-                        let mut point_s = point_r.double();
-                        let (ell1, ell2) = self.double_line_eval(px, py, point_r);
-                        f1 = ell1 * f1.square();
-                        f2 = ell2 * f2.square();
-                        point_r = point_s;
-                        if *naf_value == -1 {
-                            point_s = neg_q + point_r;
-                            let (ell1, ell2) = self.add_line_eval(px, py, neg_q, point_r);
-                            f1 = f1 * ell1;
-                            f2 = f2 * ell2;
-                            point_r = point_s;
-                        } else if *naf_value == 1 {
-                            point_s = point_q + point_r;
-                            let (ell1, ell2) = self.add_line_eval(px, py, point_q, point_r);
-                            f1 = f1 * ell1;
-                            f2 = f2 * ell2;
-                            point_r = point_s;
-                        }
-                        point_r
-                    });
-                let point_q1 = self.frobenius(point_q);
-                let point_q2 = self.frobenius(point_q1);
-                let point_s = point_q1 + point_result;
-                let (ell1, ell2) = self.add_line_eval(px, py, point_q1, point_result);
-                f1 = f1 * ell1;
-                f2 = f2 * ell2;
-                let point_r = point_s;
-                let (ell3, ell4) = self.add_line_eval(px, py, -point_q2, point_r);
-                f1 = f1 * ell3;
-                f2 = f2 * ell4;
-                let f = f1
-                    * Fp12Elem {
-                        elem1: Zero::zero(),
-                        elem2: Fp6Elem {
-                            elem1: Zero::zero(),
-                            elem2: Zero::zero(),
-                            elem3: f2.inv(),
-                        },
-                    };
-                Ok(self.final_exp(f))
-            })
+        self.pair_prepared(point_p, &self.prepare(point_q))
     }
 
-    /// Returns the value at p of the function whose zero-set is the line through q and r.
-    /// Script l with addition in the denominator from Miller's Algorithm
-    /// Used in step 6 or 8 of Algorithm 1 in High-Speed Software Implementation of
-    /// the Optimal Ate Pairing over Barreto–Naehrig Curves
-    fn add_line_eval(
+    /// `pair` with a `point_q` that was prepared ahead of time with `prepare`.
+    pub fn pair_prepared(
         &self,
-        px: T,
-        py: T,
-        q: TwistedHPoint<T>,
-        r: TwistedHPoint<T>,
-    ) -> (Fp12Elem<T>, Fp2Elem<T>) {
-        let TwistedHPoint {
-            x: qx,
-            y: qy,
-            z: qz,
-        } = q;
-        let TwistedHPoint {
-            x: rx,
-            y: ry,
-            z: rz,
-        } = r;
-        let numerator = ry * qz - qy * rz;
-        let denominator = rx * qz - qx * rz;
-        self.finalize_eval(q, px, py, numerator, denominator)
+        point_p: HomogeneousPoint<T>,
+        prepared_q: &PreparedG2<T>,
+    ) -> Result<Fp12Elem<T>, PointErr> {
+        self.pair_product(&[(point_p, prepared_q)])
     }
 
-    /// returns the value at P of the function whose zero-set is the line through Q and R.
-    /// Script l with multiplication in the denominator from Miller's Algorithm
-    /// Used in step 4 of Algorithm 1 in High-Speed Software Implementation of
-    /// the Optimal Ate Pairing over Barreto–Naehrig Curves
-    fn double_line_eval(&self, px: T, py: T, r: TwistedHPoint<T>) -> (Fp12Elem<T>, Fp2Elem<T>) {
-        let TwistedHPoint { x, y, z } = r;
-        let numerator = x.square() * 3;
-        let denominator = y * z * 2;
-        self.finalize_eval(r, px, py, numerator, denominator)
+    /// The product of the pairings of all the `pairs`. The Miller loops of the pairs run side by side, so
+    /// they share their squarings, and there is only one final exponentiation.
+    pub fn pair_product(
+        &self,
+        pairs: &[(HomogeneousPoint<T>, &PreparedG2<T>)],
+    ) -> Result<Fp12Elem<T>, PointErr> {
+        self.miller_loop_product(pairs).map(|f| self.final_exp(f))
     }
 
-    /// last step for double_line_eval or add_line_eval
-    fn finalize_eval(
+    /// The Miller loop of `pair`, which is `final_exp` of this value.
+    /// Computing it separately allows the final exponentiations of many pairings to be done with `final_exp_batch`.
+    #[cfg(test)]
+    pub fn miller_loop(
         &self,
-        q: TwistedHPoint<T>,
-        px: T,
-        py: T,
-        numerator: Fp2Elem<T>,
-        denominator: Fp2Elem<T>,
-    ) -> (Fp12Elem<T>, Fp2Elem<T>) {
-        let TwistedHPoint { x, y, z } = q;
-        let new_numerator = Fp12Elem::create(
-            Zero::zero(),
-            x * numerator - y * denominator,
-            -z * numerator
-                * Fp2Elem {
-                    elem1: Zero::zero(),
-                    elem2: px,
-                },
-            Zero::zero(),
-            Zero::zero(),
-            z * denominator
-                * Fp2Elem {
+        point_p: HomogeneousPoint<T>,
+        point_q: TwistedHPoint<T>,
+    ) -> Result<Fp12Elem<T>, PointErr> {
+        self.miller_loop_product(&[(point_p, &self.prepare(point_q))])
+    }
+
+    /// The Miller loop of `pair_product`, which is `final_exp` of this value.
+    pub fn miller_loop_product(
+        &self,
+        pairs: &[(HomogeneousPoint<T>, &PreparedG2<T>)],
+    ) -> Result<Fp12Elem<T>, PointErr> {
+        let evals = pairs
+            .iter()
+            .map(|(point_p, prepared_q)| {
+                point_p
+                    .normalize()
+                    .map(|(px, py)| (px, py, *prepared_q))
+                    .ok_or(PointErr::ZeroPoint)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut f1: Fp12Elem<T> = One::one();
+        let mut f2: Fp2Elem<T> = One::one();
+        let mul_line = |f1: &mut Fp12Elem<T>, f2: &mut Fp2Elem<T>, line: &Line<T>, px: T, py: T| {
+            let (ell1, ell2) = line.eval(px, py);
+            *f1 = *f1 * ell1;
+            *f2 = *f2 * ell2;
+        };
+        let step_count = <T as PairingConfig>::naf_for_loop().len();
+        for step in 0..step_count {
+            f1 = f1.square();
+            f2 = f2.square();
+            for (px, py, prepared_q) in evals.iter() {
+                let (double_line, add_line) = &prepared_q.steps[step];
+                mul_line(&mut f1, &mut f2, double_line, *px, *py);
+                if let Some(add_line) = add_line {
+                    mul_line(&mut f1, &mut f2, add_line, *px, *py);
+                }
+            }
+        }
+        for (px, py, prepared_q) in evals.iter() {
+            for line in prepared_q.final_lines.iter() {
+                mul_line(&mut f1, &mut f2, line, *px, *py);
+            }
+        }
+        Ok(f1
+            * Fp12Elem {
+                elem1: Zero::zero(),
+                elem2: Fp6Elem {
                     elem1: Zero::zero(),
-                    elem2: py,
+                    elem2: Zero::zero(),
+                    elem3: f2.inv(),
                 },
-        );
-        (new_numerator, z * denominator)
+            })
+    }
+
+    /// Compute the lines of the Miller loop for `point_q`, which only depend on `point_q`, so that it can be
+    /// paired with many points with `pair_prepared` or `pair_product`.
+    pub fn prepare(&self, point_q: TwistedHPoint<T>) -> PreparedG2<T> {
+        let neg_q = -point_q;
+        let naf = <T as PairingConfig>::naf_for_loop();
+        let mut steps = Vec::with_capacity(naf.len());
+        let point_result = naf.iter().fold(point_q, |point_r, naf_value| {
+            let double_line = Line::tangent(point_r);
+            let point_r = point_r.double();
+            let (point_r, add_line) = if *naf_value == -1 {
+                (neg_q + point_r, Some(Line::through(neg_q, point_r)))
+            } else if *naf_value == 1 {
+                (point_q + point_r, Some(Line::through(point_q, point_r)))
+            } else {
+                (point_r, None)
+            };
+            steps.push((double_line, add_line));
+            point_r
+        });
+        let point_q1 = self.frobenius(point_q);
+        let point_q2 = self.frobenius(point_q1);
+        let final_lines = [
+            Line::through(point_q1, point_result),
+            Line::through(-point_q2, point_q1 + point_result),
+        ];
+        PreparedG2 { steps, final_lines }
     }
 
     /// Final exponentiation: compute the value f^((p^12 - 1) / r). This maps f to one of the rth roots of unity.
@@ -168,11 +158,31 @@ where
     ///    The easy part, which consists entirely of calls to frobenius and inverse.
     ///    The hard part, which involves expressing the exponent as a polynomial in x = 1868033.
     pub fn final_exp(&self, initial_f: Fp12Elem<T>) -> Fp12Elem<T> {
+        self.final_exp_with_inv(initial_f, initial_f.inv())
+    }
+
+    /// `final_exp` of each element of `fs`.
+    /// The inversion in the easy part is the most expensive step after the hard part, so it is shared between all the
+    /// elements using Montgomery's trick. None of the elements may be zero, which holds for the output of a Miller loop.
+    pub fn final_exp_batch(&self, fs: &[Fp12Elem<T>]) -> Vec<Fp12Elem<T>> {
+        batch_inv(fs)
+            .into_iter()
+            .zip(fs)
+            .map(|(f_inv, f)| self.final_exp_with_inv(*f, f_inv))
+            .collect()
+    }
+
+    /// `final_exp` given the inverse of `initial_f`.
+    fn final_exp_with_inv(
+        &self,
+        initial_f: Fp12Elem<T>,
+        initial_f_inv: Fp12Elem<T>,
+    ) -> Fp12Elem<T> {
         let mut f = initial_f;
         //Easy part
         //"Computing f^((p^6-1)(p^2+1))..."
         let mut g = (0..6).fold(f, |acc, _| acc.frobenius());
-        f = g * f.inv(); //  f = f^(p^6-1)
+        f = g * initial_f_inv; //  f = f^(p^6-1)
         f = f.frobenius().frobenius() * f; //  f = f^(p^2+1)
 
         //Hard part: compute f = f^((p^4 - p^2 + 1)/r) - Section 7 of Devegili Scott Dahab Pairings over BN curves - Algorithm 3
@@ -181,20 +191,21 @@ where
         //We also express the exponent as a polynomial in x=1868033.  See Section 7 and in particular Algorithm 3 in Devegili--Scott--Dahab "Implementing Pairings over Barreto--Naehrig Curves"
         //(p^4 - p^2 + 1)/r = p^3 + p^2(6t^2+1) + p(-36t^3-18t^2-12t+1) + (-36t^3-30t^2-18t-2), where t is the BNParam
         let f_inv = f.conjugate(); // f is unitary (See explanation in Beuchet--Gonzalez-Diaz--Mitsunari et. al. bottom of page 4), so f^(-1) == \overline{f}
-        g = Pairing::square(f_inv); //g = f^(-2)
-        g = Pairing::square(g) * g; //g = g^3
+        //f is also in the cyclotomic subgroup, so all of the squarings below can use the cyclotomic squarings of Fp12Elem
+        g = f_inv.cyclotomic_square(); //g = f^(-2)
+        g = g.cyclotomic_square() * g; //g = g^3
         g = PairingConfig::bn_pow(g); //g = g^x, where x = 1868033 = cube root of BNParam
         g = PairingConfig::bn_pow(g); //g = g^x
         g = PairingConfig::bn_pow(g); //g = g^x = f^(-6x^3)
-        let a = g * Pairing::square(Pairing::square(f_inv)) * f_inv; //a = f^-(6*x^3-5)
+        let a = g * f_inv.cyclotomic_square().cyclotomic_square() * f_inv; //a = f^-(6*x^3-5)
         let mut b = a.frobenius(); //b = a^p
         b = a * b; //b = a^(p+1)
         let g1 = f.frobenius(); //g1 = f^p
         let g2 = g1.frobenius(); //g2 = g2^p
         let g3 = g2.frobenius(); //g3 = g2^p = f^(p^3)
-        let g4 = b * Pairing::square(g1) * g2; //g4 = b*g1^2*g2
-        let mut g5 = Pairing::square(g4); //g5 = g4^2
-        g5 = Pairing::square(g5) * g5; //g5 = g5^3 = g4^6
+        let g4 = b * g1.cyclotomic_square() * g2; //g4 = b*g1^2*g2
+        let mut g5 = g4.cyclotomic_square(); //g5 = g4^2
+        g5 = g5.cyclotomic_square() * g5; //g5 = g5^3 = g4^6
         g5 = PairingConfig::bn_pow(g5); //g5^x
         g5 = PairingConfig::bn_pow(g5); //g5^x
         g5 = PairingConfig::bn_pow(g5); //g5^x
@@ -205,32 +216,12 @@ where
         g3 * g5
             * g4
             * b
-            * Pairing::square(Pairing::square(Pairing::square(g6)))
+            * g6.cyclotomic_square()
+                .cyclotomic_square()
+                .cyclotomic_square()
             * g6
             * a
-            * Pairing::square(Pairing::square(f))
-    }
-
-    /// Squaring for use in final exponentiation.  Shortcut taken from Section 3 of Granger--Scott
-    /// "Faster Squaring in the Cyclomatic Subgroup of Sixth Degree Extensions"
-    fn square(fp12: Fp12Elem<T>) -> Fp12Elem<T> {
-        let Fp12Elem { elem1: b, elem2: a } = fp12;
-        let a2 = a * b * 2;
-        let b2 = b.square() * 2;
-        let Fp6Elem {
-            elem1: z,
-            elem2: y,
-            elem3: x,
-        } = b2;
-        let z2 = z * Xi + One::one();
-        Fp12Elem {
-            elem1: a2,
-            elem2: Fp6Elem {
-                elem1: y,
-                elem2: x,
-                elem3: z2,
-            },
-        }
+            * f.cyclotomic_square().cyclotomic_square()
     }
 
     fn frobenius(&self, point: TwistedHPoint<T>) -> TwistedHPoint<T> {
@@ -244,33 +235,136 @@ where
         }
     }
 }
+
+/// The Miller loop lines for a fixed point of G2, computed by `Pairing::prepare`.
+#[derive(Clone, Debug)]
+pub struct PreparedG2<T> {
+    /// The tangent line of every step of the loop, followed by the line of the addition if the step has one.
+    steps: Vec<(Line<T>, Option<Line<T>>)>,
+    /// The lines through the frobenius images of the point that finish the loop.
+    final_lines: [Line<T>; 2],
+}
+
+/// A line function used by the Miller loop. The coefficients only depend on the points of G2 the line goes
+/// through, so they can be computed once and evaluated at many points of G1.
+#[derive(Clone, Copy, Debug)]
+struct Line<T> {
+    constant: Fp2Elem<T>,
+    px_coeff: Fp2Elem<T>,
+    py_coeff: Fp2Elem<T>,
+}
+
+impl<T: ExtensionField> Line<T> {
+    /// The line through q and r.
+    /// Script l with addition in the denominator from Miller's Algorithm
+    /// Used in step 6 or 8 of Algorithm 1 in High-Speed Software Implementation of
+    /// the Optimal Ate Pairing over Barreto–Naehrig Curves
+    fn through(q: TwistedHPoint<T>, r: TwistedHPoint<T>) -> Line<T> {
+        let TwistedHPoint {
+            x: qx,
+            y: qy,
+            z: qz,
+        } = q;
+        let TwistedHPoint {
+            x: rx,
+            y: ry,
+            z: rz,
+        } = r;
+        let numerator = ry * qz - qy * rz;
+        let denominator = rx * qz - qx * rz;
+        Line::new(q, numerator, denominator)
+    }
+
+    /// The tangent line at r.
+    /// Script l with multiplication in the denominator from Miller's Algorithm
+    /// Used in step 4 of Algorithm 1 in High-Speed Software Implementation of
+    /// the Optimal Ate Pairing over Barreto–Naehrig Curves
+    fn tangent(r: TwistedHPoint<T>) -> Line<T> {
+        let TwistedHPoint { x, y, z } = r;
+        let numerator = x.square() * 3;
+        let denominator = y * z * 2;
+        Line::new(r, numerator, denominator)
+    }
+
+    /// last step for through or tangent
+    fn new(q: TwistedHPoint<T>, numerator: Fp2Elem<T>, denominator: Fp2Elem<T>) -> Line<T> {
+        let TwistedHPoint { x, y, z } = q;
+        Line {
+            constant: x * numerator - y * denominator,
+            px_coeff: -z * numerator,
+            py_coeff: z * denominator,
+        }
+    }
+
+    /// Returns the value at p of the line's function, as a numerator and a denominator.
+    fn eval(&self, px: T, py: T) -> (Fp12Elem<T>, Fp2Elem<T>) {
+        let new_numerator = Fp12Elem::create(
+            Zero::zero(),
+            self.constant,
+            self.px_coeff
+                * Fp2Elem {
+                    elem1: Zero::zero(),
+                    elem2: px,
+                },
+            Zero::zero(),
+            Zero::zero(),
+            self.py_coeff
+                * Fp2Elem {
+                    elem1: Zero::zero(),
+                    elem2: py,
+                },
+        );
+        (new_numerator, self.py_coeff)
+    }
+}
+
 ///This type represents the information to configure the pairing for a particular FpType.
 pub trait PairingConfig
 where
     Self: Sized,
 {
-    /// This is based on the BNParam. It's the cuberoot of BNParam. It should always be called
-    /// in multiples of 3.
-    fn bn_pow(fp12: Fp12Elem<Self>) -> Fp12Elem<Self>;
+    /// The square and multiply chain for x, the cube root of the BNParam. Starting from the element itself, each
+    /// entry is the number of squarings to do next and whether the result is multiplied in inverted (conjugated),
+    /// which lets the chain follow a signed representation of x.
+    const BN_POW_CHAIN: &'static [(usize, bool)];
+
+    /// Raise an element of the cyclotomic subgroup to the power x, the cube root of the BNParam. It should always be
+    /// called in multiples of 3.
+    ///
+    /// The squarings are done on the compressed form of the element, and all of the powers that get multiplied in are
+    /// decompressed together at the end.
+    fn bn_pow(fp12: Fp12Elem<Self>) -> Fp12Elem<Self>
+    where
+        Self: ExtensionField,
+    {
+        let mut x = fp12.compress();
+        let powers: Vec<CompressedFp12Elem<Self>> = Self::BN_POW_CHAIN
+            .iter()
+            .map(|(squarings, _)| {
+                (0..*squarings).for_each(|_| x = x.square());
+                x
+            })
+            .collect();
+        CompressedFp12Elem::decompress_batch(&powers)
+            .into_iter()
+            .zip(Self::BN_POW_CHAIN)
+            .fold(fp12, |res, (power, (_, inverted))| {
+                if *inverted {
+                    res * power.conjugate()
+                } else {
+                    res * power
+                }
+            })
+    }
 
     ///The naf which is used for the miller loop. In both our cases it's the NAF of 6*BNParam + 2 reversed with the last 2 dropped off.
     fn naf_for_loop() -> Vec<i8>;
 }
 
 impl PairingConfig for fp_256::Monty {
-    fn bn_pow(fp12: Fp12Elem<Self>) -> Fp12Elem<Self> {
-        //This is a hardcode of the square and multiply for bnPow
-        let mut x = fp12;
-        let mut res = x;
-        (0..8).for_each(|_| x = x.square());
-        res = res * x;
-        (0..7).for_each(|_| x = x.square());
-        res = res * x;
-        (0..3).for_each(|_| x = x.square());
-        res = res * x.conjugate();
-        (0..3).for_each(|_| x = x.square());
-        res * x
-    }
+    //x = 1868033 = 2^0 + 2^8 + 2^15 - 2^18 + 2^21
+    const BN_POW_CHAIN: &'static [(usize, bool)] = &[(8, false), (7, false), (3, true), (3, false)];
+
     //NAF of 6*BNParam + 2
     fn naf_for_loop() -> Vec<i8> {
         // if comparing to recrypt-scala, the last two elements were left off manually
@@ -285,35 +379,21 @@ impl PairingConfig for fp_256::Monty {
 }
 
 impl PairingConfig for fp_480::Monty {
-    fn bn_pow(fp12: Fp12Elem<Self>) -> Fp12Elem<Self> {
-        //This is a hardcode of the square and multiply for bnPow
-        let mut x = fp12;
-        let mut res = x;
-        (0..3).for_each(|_| x = x.square());
-        res = res * x;
-        (0..4).for_each(|_| x = x.square());
-        res = res * x;
-        (0..2).for_each(|_| x = x.square());
-        res = res * x;
-        (0..4).for_each(|_| x = x.square());
-        res = res * x;
-        (0..5).for_each(|_| x = x.square());
-        res = res * x;
-        (0..4).for_each(|_| x = x.square());
-        res = res * x.conjugate();
-        (0..2).for_each(|_| x = x.square());
-        res = res * x;
-        (0..3).for_each(|_| x = x.square());
-        res = res * x;
-        (0..2).for_each(|_| x = x.square());
-        res = res * x.conjugate();
-        (0..4).for_each(|_| x = x.square());
-        res = res * x.conjugate();
-        (0..5).for_each(|_| x = x.square());
-        res = res * x.conjugate();
-        (0..2).for_each(|_| x = x.square());
-        res * x
-    }
+    //x = 815653986953 = 2^0 + 2^3 + 2^7 + 2^9 + 2^13 + 2^18 - 2^22 + 2^24 + 2^27 - 2^29 - 2^33 - 2^38 + 2^40
+    const BN_POW_CHAIN: &'static [(usize, bool)] = &[
+        (3, false),
+        (4, false),
+        (2, false),
+        (4, false),
+        (5, false),
+        (4, true),
+        (2, false),
+        (3, false),
+        (2, true),
+        (4, true),
+        (5, true),
+        (2, false),
+    ];
 
     fn naf_for_loop() -> Vec<i8> {
         // if comparing to recrypt-scala, the last two elements were removed manually instead of at runtime
@@ -334,7 +414,9 @@ mod test {
     use super::*;
     use crate::internal::curve::{FP_256_CURVE_POINTS, FP_480_CURVE_POINTS};
     use crate::internal::fp::fp256_unsafe_from;
+    use crate::internal::fp12elem::test::{arb_fp12, arb_fp12_480};
     use crate::internal::homogeneouspoint::Double;
+    use crate::internal::pow_vartime;
     use lazy_static::lazy_static;
     use num_traits::Pow;
     use proptest::prelude::*;
@@ -524,12 +606,11 @@ mod test {
                 .to_monty(),
             },
         );
-        let (result_num, result_denom) = Pairing::new().add_line_eval(
-            BASE_POINT_X.clone(),
-            BASE_POINT_Y.clone(),
+        let (result_num, result_denom) = Line::through(
             GOOD_TWISTED_HPOINT_MONTY.clone(),
             GOOD_TWISTED_HPOINT_MONTY.double(),
-        );
+        )
+        .eval(BASE_POINT_X.clone(), BASE_POINT_Y.clone());
         //Normalize the values so that we don't have to care about _how_ we got to the result. Different * and double algorithms will
         //give different values here, but when you normalize it should come out to the same.
         let result = result_num * result_denom.inv();
@@ -591,12 +672,11 @@ mod test {
             .double()
             .normalize()
             .expect("normalize failed");
-        let (result_num, result_denom) = Pairing::new().add_line_eval(
-            point_x,
-            point_y,
+        let (result_num, result_denom) = Line::through(
             GOOD_TWISTED_HPOINT_MONTY.double(),
             GOOD_TWISTED_HPOINT_MONTY.double().double(),
-        );
+        )
+        .eval(point_x, point_y);
         //Normalize the values so that we don't have to care about _how_ we got to the result. Different * and double algorithms will
         //give different values here, but when you normalize it should come out to the same.
         let result = result_num * result_denom.inv();
@@ -654,11 +734,8 @@ mod test {
             .map(&|fp| fp.to_monty()),
         );
 
-        let result = Pairing::new().double_line_eval(
-            BASE_POINT_X.clone(),
-            BASE_POINT_Y.clone(),
-            GOOD_TWISTED_HPOINT_MONTY.clone(),
-        );
+        let result = Line::tangent(GOOD_TWISTED_HPOINT_MONTY.clone())
+            .eval(BASE_POINT_X.clone(), BASE_POINT_Y.clone());
         assert_eq!(expected_good_result, result);
     }
 
@@ -747,7 +824,148 @@ mod test {
         assert_eq!(expected_result, result);
     }
 
+    #[test]
+    fn pair_prepared_matches_pair() {
+        let pairing: Pairing<fp_256::Monty> = Pairing::new();
+        let p = FP_256_CURVE_POINTS.generator * fp_256::Monty::from(5u32);
+        let q = *GOOD_TWISTED_HPOINT_MONTY;
+        let prepared_q = pairing.prepare(q);
+        assert_eq!(
+            pairing.pair_prepared(p, &prepared_q).unwrap(),
+            pairing.pair(p, q).unwrap()
+        );
+        assert_eq!(
+            pairing
+                .pair_prepared(
+                    FP_256_CURVE_POINTS.generator,
+                    &FP_256_CURVE_POINTS.g1_prepared
+                )
+                .unwrap(),
+            pairing
+                .pair(FP_256_CURVE_POINTS.generator, FP_256_CURVE_POINTS.g1)
+                .unwrap()
+        );
+        assert_eq!(
+            pairing.pair_prepared(Zero::zero(), &prepared_q),
+            Err(PointErr::ZeroPoint)
+        );
+    }
+
+    #[test]
+    fn pair_product_matches_product_of_pairs() {
+        let pairing: Pairing<fp_480::Monty> = Pairing::new();
+        let p1 = FP_480_CURVE_POINTS.generator * fp_480::Monty::from(3u32);
+        let p2 = FP_480_CURVE_POINTS.generator * fp_480::Monty::from(7u32);
+        let q1 = FP_480_CURVE_POINTS.g1;
+        let q2 = FP_480_CURVE_POINTS.hash_element;
+        let prepared_q2 = pairing.prepare(q2);
+        let expected = pairing.pair(p1, q1).unwrap() * pairing.pair(p2, q2).unwrap();
+        assert_eq!(
+            pairing
+                .pair_product(&[(p1, &FP_480_CURVE_POINTS.g1_prepared), (p2, &prepared_q2)])
+                .unwrap(),
+            expected
+        );
+        assert_eq!(pairing.pair_product(&[]).unwrap(), Fp12Elem::one());
+    }
+
+    #[test]
+    fn final_exp_batch_matches_final_exp() {
+        let pairing: Pairing<fp_256::Monty> = Pairing::new();
+        let p = FP_256_CURVE_POINTS.generator;
+        let q = FP_256_CURVE_POINTS.g1;
+        let fs: Vec<Fp12Elem<fp_256::Monty>> = [1u32, 2, 3, 4]
+            .iter()
+            .map(|a| pairing.miller_loop(p * fp_256::Monty::from(*a), q).unwrap())
+            .collect();
+        let expected: Vec<Fp12Elem<fp_256::Monty>> =
+            fs.iter().map(|f| pairing.final_exp(*f)).collect();
+
+        assert_eq!(pairing.final_exp_batch(&fs), expected);
+        assert_eq!(pairing.final_exp_batch(&fs[..1]), expected[..1].to_vec());
+        assert!(pairing.final_exp_batch(&[]).is_empty());
+    }
+
+    //x, the cube root of the BNParam, for each curve
+    const FP256_X: u64 = 1868033;
+    const FP480_X: u64 = 815653986953;
+
+    /// The final exponentiation as it was computed before the cyclotomic squarings were added: generic squaring,
+    /// and a square and multiply by x in place of `bn_pow`.
+    fn reference_final_exp<T: ExtensionField>(initial_f: Fp12Elem<T>, x: u64) -> Fp12Elem<T> {
+        let pow_x = |f: Fp12Elem<T>| pow_vartime(f, &x.to_be_bytes());
+        let g = (0..6).fold(initial_f, |acc, _| acc.frobenius());
+        let mut f = g * initial_f.inv();
+        f = f.frobenius().frobenius() * f;
+        let f_inv = f.conjugate();
+        let mut g = f_inv.square();
+        g = g.square() * g;
+        g = pow_x(pow_x(pow_x(g)));
+        let a = g * f_inv.square().square() * f_inv;
+        let b = a * a.frobenius();
+        let g1 = f.frobenius();
+        let g2 = g1.frobenius();
+        let g3 = g2.frobenius();
+        let g4 = b * g1.square() * g2;
+        let mut g5 = g4.square();
+        g5 = g5.square() * g5;
+        g5 = (0..6).fold(g5, |acc, _| pow_x(acc));
+        let g6 = g1 * f;
+        g3 * g5 * g4 * b * g6.square().square().square() * g6 * a * f.square().square()
+    }
+
+    #[test]
+    fn bn_pow_matches_pow_by_x() {
+        let f256 = Pairing::new()
+            .pair(FP_256_CURVE_POINTS.generator, FP_256_CURVE_POINTS.g1)
+            .unwrap();
+        assert_eq!(
+            PairingConfig::bn_pow(f256),
+            pow_vartime(f256, &FP256_X.to_be_bytes())
+        );
+        let f480 = Pairing::new()
+            .pair(FP_480_CURVE_POINTS.generator, FP_480_CURVE_POINTS.g1)
+            .unwrap();
+        assert_eq!(
+            PairingConfig::bn_pow(f480),
+            pow_vartime(f480, &FP480_X.to_be_bytes())
+        );
+        assert_eq!(
+            PairingConfig::bn_pow(Fp12Elem::<fp_480::Monty>::one()),
+            Fp12Elem::one()
+        );
+    }
+
+    #[test]
+    fn final_exp_of_miller_loop_matches_reference() {
+        let pairing: Pairing<fp_256::Monty> = Pairing::new();
+        let f = pairing
+            .miller_loop(FP_256_CURVE_POINTS.generator, *GOOD_TWISTED_HPOINT_MONTY)
+            .unwrap();
+        assert_eq!(pairing.final_exp(f), reference_final_exp(f, FP256_X));
+
+        let pairing: Pairing<fp_480::Monty> = Pairing::new();
+        let f = pairing
+            .miller_loop(FP_480_CURVE_POINTS.generator, FP_480_CURVE_POINTS.g1)
+            .unwrap();
+        assert_eq!(pairing.final_exp(f), reference_final_exp(f, FP480_X));
+    }
+
     proptest! {
+      #[test]
+      fn fp256_final_exp_matches_reference(f in arb_fp12()) {
+        prop_assume!(!f.is_zero());
+        let pairing: Pairing<fp_256::Monty> = Pairing::new();
+        prop_assert_eq!(pairing.final_exp(f), reference_final_exp(f, FP256_X));
+      }
+
+      #[test]
+      fn fp480_final_exp_matches_reference(f in arb_fp12_480()) {
+        prop_assume!(!f.is_zero());
+        let pairing: Pairing<fp_480::Monty> = Pairing::new();
+        prop_assert_eq!(pairing.final_exp(f), reference_final_exp(f, FP480_X));
+      }
+
       //"follow the law pair(a * P, a * Q) == pair(a^2 * P, Q) == pair(P,a^2 * Q)"
       //"follow the law pair(a * P, a * Q) == pair(P, Q) ^ (a^2)"
       #[test]
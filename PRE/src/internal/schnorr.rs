@@ -5,8 +5,7 @@ use crate::internal::fp::fr_256::Fr256;
 use crate::internal::fp::fr_480::Fr480;
 use crate::internal::hashable::Hashable;
 use crate::internal::homogeneouspoint::HomogeneousPoint;
-use crate::internal::sha256::Sha256;
-use crate::internal::sha256::Sha256Hashing;
+use crate::internal::sha256::{HashDomain, ProtocolVersion, Sha256Hashing};
 use crate::internal::{PrivateKey, PublicKey, field};
 use gridiron::digits::constant_time_primitives::ConstantSwap;
 use gridiron::fp_256::Monty as Monty256;
@@ -37,28 +36,51 @@ impl<T> SchnorrSignature<T> {
     }
 }
 
+/// Signs and verifies with the hashing of `version`. Verification only falls back to the hashing of
+/// `ProtocolVersion::Legacy` when `with_legacy_verification` was used.
 #[derive(Debug)]
 pub struct SchnorrSign<FP, FR, H> {
     sha256: H,
     g: HomogeneousPoint<FP>,
+    version: ProtocolVersion,
+    accept_legacy: bool,
     phantom: PhantomData<FR>,
 }
 
-impl SchnorrSign<Monty256, Fr256, Sha256> {
-    pub fn new_256() -> SchnorrSign<Monty256, Fr256, Sha256> {
+impl<FP, FR, H> SchnorrSign<FP, FR, H> {
+    /// Sign and verify with the hashing of `version` instead of `ProtocolVersion::Legacy`.
+    pub fn with_version(self, version: ProtocolVersion) -> SchnorrSign<FP, FR, H> {
+        SchnorrSign { version, ..self }
+    }
+
+    /// Also accept signatures made with the hashing of `ProtocolVersion::Legacy`, which isn't domain separated.
+    pub fn with_legacy_verification(self) -> SchnorrSign<FP, FR, H> {
         SchnorrSign {
-            sha256: Sha256,
+            accept_legacy: true,
+            ..self
+        }
+    }
+}
+
+impl<H> SchnorrSign<Monty256, Fr256, H> {
+    pub fn new_256(sha256: H) -> SchnorrSign<Monty256, Fr256, H> {
+        SchnorrSign {
+            sha256,
             g: FP_256_CURVE_POINTS.generator,
+            version: ProtocolVersion::Legacy,
+            accept_legacy: false,
             phantom: PhantomData::<Fr256>,
         }
     }
 }
 
-impl SchnorrSign<Monty480, Fr480, Sha256> {
-    pub fn new_480() -> SchnorrSign<Monty480, Fr480, Sha256> {
+impl<H> SchnorrSign<Monty480, Fr480, H> {
+    pub fn new_480(sha256: H) -> SchnorrSign<Monty480, Fr480, H> {
         SchnorrSign {
-            sha256: Sha256,
+            sha256,
             g: FP_480_CURVE_POINTS.generator,
+            version: ProtocolVersion::Legacy,
+            accept_legacy: false,
             phantom: PhantomData::<Fr480>,
         }
     }
@@ -66,10 +88,14 @@ impl SchnorrSign<Monty480, Fr480, Sha256> {
 
 fn compute_double_hash<A: Hashable, B: Hashable, H: Sha256Hashing>(
     sha256: &H,
+    version: ProtocolVersion,
     a: &A,
     b: &B,
 ) -> [u8; 64] {
-    array_concat_32(&sha256.hash(a), &sha256.hash(b))
+    array_concat_32(
+        &version.hash(sha256, HashDomain::SchnorrChallenge, a),
+        &version.hash(sha256, HashDomain::SchnorrChallenge, b),
+    )
 }
 
 impl<FP, FR, H> SchnorrSigning<FP, FR> for SchnorrSign<FP, FR, H>
@@ -92,6 +118,7 @@ where
                 let r = FR::from(x);
                 let dh = compute_double_hash(
                     &self.sha256,
+                    self.version,
                     &(&r, &pub_key, message),
                     &(&pub_key, message, &r),
                 );
@@ -110,20 +137,30 @@ where
         signature: SchnorrSignature<FR>,
     ) -> bool {
         use num_traits::Zero;
-        let h = FR::from(FP::from(compute_double_hash(
-            &self.sha256,
-            &(&signature.r, &pub_key, message),
-            &(&pub_key, message, &signature.r),
-        )));
         let augmenting_pub_key = augmenting_key
             .map(|key| self.g * key.value)
             .unwrap_or_else(HomogeneousPoint::zero);
         let unaugmented_key = pub_key.value - augmenting_pub_key;
-        let v = self.g * signature.s + unaugmented_key * h;
-        let normalized = v.normalize();
-        normalized
-            .map(|(x, _)| FR::from(x) == signature.r) // `x` and `signature.r` revealed, but they are not secret
-            .unwrap_or_else(|| false)
+        // the signature doesn't record the version it was made with, so a legacy signature is only tried when the
+        // caller opted in
+        let versions: &[ProtocolVersion] = if self.accept_legacy {
+            &[self.version, ProtocolVersion::Legacy]
+        } else {
+            &[self.version]
+        };
+        versions.iter().any(|version| {
+            let h = FR::from(FP::from(compute_double_hash(
+                &self.sha256,
+                *version,
+                &(&signature.r, &pub_key, message),
+                &(&pub_key, message, &signature.r),
+            )));
+            let v = self.g * signature.s + unaugmented_key * h;
+            let normalized = v.normalize();
+            normalized
+                .map(|(x, _)| FR::from(x) == signature.r) // `x` and `signature.r` revealed, but they are not secret
+                .unwrap_or_else(|| false)
+        })
     }
 }
 
@@ -157,11 +194,12 @@ pub trait SchnorrSigning<T: field::Field, U> {
 }
 
 #[cfg(test)]
-mod test {
+pub mod test {
     use super::*;
     use crate::internal::PublicKey;
     use crate::internal::fp::fp256_unsafe_from;
     use crate::internal::fp::fr_256::Fr256;
+    use crate::internal::sha256::Sha256;
     use crate::internal::test::arb_priv_key;
     use num_traits::{One, Pow, Zero};
     use proptest::arbitrary::any;
@@ -187,7 +225,7 @@ mod test {
             aug_priv_key in arb_priv_key().prop_filter("", |a| !a.value.is_zero())) {
             let g = FP_256_CURVE_POINTS.generator;
             let message = 1u8;
-            let signing = SchnorrSign::new_256();
+            let signing = SchnorrSign::new_256(Sha256);
             let aug_pub_key = PublicKey::new(g * aug_priv_key);
             let pub_key = PublicKey::new(g * priv_key.value + aug_pub_key.value);
             let sig = signing.sign(priv_key,pub_key, &message, fr).unwrap();
@@ -238,9 +276,35 @@ mod test {
             ]),
         };
 
-        let result = SchnorrSign::new_256()
+        let result = SchnorrSign::new_256(Sha256)
             .sign(priv_key, pub_key, &message, k)
             .unwrap();
         assert_eq!(result, expected_result);
     }
+
+    #[test]
+    fn schnorr_verifies_only_signatures_of_its_version() {
+        let priv_key = PrivateKey::from_fp256(
+            fp256_unsafe_from("8fb501e34aa387f9aa6fecb86184dc21ee5b88d120b5b59e185cac6c5e089666")
+                .to_monty(),
+        );
+        let pub_key = PublicKey::new(FP_256_CURVE_POINTS.generator * priv_key.value);
+        let k = Fr256::from(12345u32);
+        let message = 1u8;
+        let legacy = SchnorrSign::new_256(Sha256);
+        let v2 = SchnorrSign::new_256(Sha256).with_version(ProtocolVersion::V2);
+        let legacy_sig = legacy.sign(priv_key, pub_key, &message, k).unwrap();
+        let v2_sig = v2.sign(priv_key, pub_key, &message, k).unwrap();
+        assert_ne!(legacy_sig, v2_sig);
+        let copy = |sig: &SchnorrSignature<Fr256>| SchnorrSignature::new(sig.r, sig.s);
+        assert!(legacy.verify(pub_key, None, &message, copy(&legacy_sig)));
+        assert!(!legacy.verify(pub_key, None, &message, copy(&v2_sig)));
+        assert!(v2.verify(pub_key, None, &message, copy(&v2_sig)));
+        assert!(!v2.verify(pub_key, None, &message, copy(&legacy_sig)));
+        assert!(!v2.verify(pub_key, None, &2u8, copy(&v2_sig)));
+
+        let v2_or_legacy = v2.with_legacy_verification();
+        assert!(v2_or_legacy.verify(pub_key, None, &message, copy(&legacy_sig)));
+        assert!(v2_or_legacy.verify(pub_key, None, &message, copy(&v2_sig)));
+    }
 }
@@ -1,13 +1,103 @@
+use crate::internal::ByteVector;
 use crate::internal::hashable::Hashable;
 use sha2;
 use sha2::Digest;
+use sha2::digest::consts::U32;
+use std::fmt;
+use std::marker::PhantomData;
 
+/// The hash used for auth hashes, transform keys and Schnorr signatures. Despite the name it can be any hash
+/// function with a 32 byte output, see `DigestHash`.
 pub trait Sha256Hashing {
     fn hash<T: Hashable>(&self, t: &T) -> [u8; 32];
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct Sha256;
 
+/// Identifies the ciphersuite of `ProtocolVersion::V2`: transform encryption over the BN pairing curves, with the
+/// hashes of each use separated by a `HashDomain`. The curve itself is implied by the length of the hashed values.
+pub const CIPHERSUITE_ID: &[u8] = b"RECRYPT-BN-PRE-V2";
+
+/// The protocol version of an encrypted value or transform key, which decides how its hashes are computed.
+///
+/// Values and keys of every version can be decrypted and transformed, but a transform key can only transform values
+/// of its own version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ProtocolVersion {
+    /// The hashes of recrypt 0.14 and earlier, over the `Hashable` bytes of their inputs alone.
+    #[default]
+    Legacy,
+    /// Every hash is prefixed with `CIPHERSUITE_ID` and the label of its `HashDomain`.
+    V2,
+}
+
+impl ProtocolVersion {
+    /// Hash `t` for the use `domain`.
+    pub fn hash<H: Sha256Hashing, T: Hashable>(
+        self,
+        sha256: &H,
+        domain: HashDomain,
+        t: &T,
+    ) -> [u8; 32] {
+        match self {
+            ProtocolVersion::Legacy => sha256.hash(t),
+            ProtocolVersion::V2 => sha256.hash(&(&domain.tag(), t)),
+        }
+    }
+}
+
+/// The bytes signed along with a value or key. `Legacy` has none, so legacy values sign the same bytes as before,
+/// and a `V2` value can't be passed off as a legacy one without breaking its signature.
+impl Hashable for ProtocolVersion {
+    fn to_bytes(&self) -> ByteVector {
+        match self {
+            ProtocolVersion::Legacy => vec![],
+            ProtocolVersion::V2 => vec![2],
+        }
+    }
+}
+
+/// The uses of the hash, each of which gets its own label in `ProtocolVersion::V2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashDomain {
+    /// The auth hash of an encrypted value
+    AuthHash,
+    /// Hashing temp keys to points for transform keys and transform blocks (`hash2`)
+    TransformKey,
+    /// The challenge of a Schnorr signature
+    SchnorrChallenge,
+    /// Deriving a symmetric key from a `Plaintext`
+    SymmetricKey,
+    /// Deriving a private key from a `Plaintext`
+    PrivateKey,
+}
+
+impl HashDomain {
+    fn label(self) -> &'static [u8] {
+        match self {
+            HashDomain::AuthHash => b"auth-hash",
+            HashDomain::TransformKey => b"transform-key",
+            HashDomain::SchnorrChallenge => b"schnorr-challenge",
+            HashDomain::SymmetricKey => b"symmetric-key",
+            HashDomain::PrivateKey => b"private-key",
+        }
+    }
+
+    /// The prefix of every hash for this use: `CIPHERSUITE_ID` and the label, each preceded by its length so that
+    /// no tag is a prefix of another.
+    pub fn tag(self) -> ByteVector {
+        let label = self.label();
+        [
+            &[CIPHERSUITE_ID.len() as u8][..],
+            CIPHERSUITE_ID,
+            &[label.len() as u8][..],
+            label,
+        ]
+        .concat()
+    }
+}
+
 impl Sha256Hashing for Sha256 {
     fn hash<T: Hashable>(&self, t: &T) -> [u8; 32] {
         let mut hasher = sha2::Sha256::default();
@@ -22,6 +112,75 @@ impl Sha256Hashing for Sha256 {
     }
 }
 
+/// A `Sha256Hashing` backed by any hash from the RustCrypto `digest` traits with a 32 byte output, such as
+/// SHA3-256 or BLAKE2s-256. Hashes with any other output size are rejected at compile time.
+///
+/// A non-empty domain separation tag is prefixed, along with its length, to everything that is hashed, so a suite
+/// with its own tag never produces the same hashes as another suite using the same function. Without a tag the
+/// hashes are those of the plain function, so `DigestHash::<sha2::Sha256>::new()` hashes the same as `Sha256`.
+pub struct DigestHash<D> {
+    domain: Vec<u8>,
+    phantom: PhantomData<fn() -> D>,
+}
+
+impl<D> DigestHash<D> {
+    pub fn new() -> DigestHash<D> {
+        DigestHash::with_domain(&[])
+    }
+
+    /// Construct a hash that separates its outputs with `domain`, which can be at most 255 bytes.
+    pub fn with_domain(domain: &[u8]) -> DigestHash<D> {
+        assert!(
+            domain.len() <= u8::MAX as usize,
+            "Developer error: the domain separation tag must be at most 255 bytes"
+        );
+        DigestHash {
+            domain: domain.to_vec(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn domain(&self) -> &[u8] {
+        &self.domain
+    }
+}
+
+impl<D> Default for DigestHash<D> {
+    fn default() -> Self {
+        DigestHash::new()
+    }
+}
+
+// implemented by hand so that `D` doesn't need to be `Clone` or `Debug`
+impl<D> Clone for DigestHash<D> {
+    fn clone(&self) -> Self {
+        DigestHash::with_domain(&self.domain)
+    }
+}
+
+impl<D> fmt::Debug for DigestHash<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DigestHash")
+            .field("domain", &self.domain)
+            .finish()
+    }
+}
+
+impl<D: Digest<OutputSize = U32>> Sha256Hashing for DigestHash<D> {
+    fn hash<T: Hashable>(&self, t: &T) -> [u8; 32] {
+        let mut hasher = D::new();
+        if !self.domain.is_empty() {
+            hasher.update([self.domain.len() as u8]);
+            hasher.update(&self.domain);
+        }
+        hasher.update(t.to_bytes().as_slice());
+        let hash_result = hasher.finalize();
+        let mut result: [u8; 32] = [0; 32];
+        result.copy_from_slice(&hash_result);
+        result
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::internal::sha256::*;
@@ -35,4 +194,61 @@ mod test {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn legacy_version_hashes_without_domain() {
+        for domain in [HashDomain::AuthHash, HashDomain::SchnorrChallenge] {
+            assert_eq!(
+                ProtocolVersion::Legacy.hash(&Sha256, domain, &1u8),
+                Sha256.hash(&1u8)
+            );
+        }
+    }
+
+    #[test]
+    fn v2_hashes_are_separated_by_domain() {
+        let domains = [
+            HashDomain::AuthHash,
+            HashDomain::TransformKey,
+            HashDomain::SchnorrChallenge,
+            HashDomain::SymmetricKey,
+            HashDomain::PrivateKey,
+        ];
+        let hashes: Vec<[u8; 32]> = domains
+            .iter()
+            .map(|domain| ProtocolVersion::V2.hash(&Sha256, *domain, &1u8))
+            .collect();
+        for (i, hash) in hashes.iter().enumerate() {
+            assert_ne!(*hash, Sha256.hash(&1u8));
+            assert!(hashes[i + 1..].iter().all(|other| other != hash));
+        }
+        assert_eq!(
+            ProtocolVersion::V2.hash(&Sha256, HashDomain::AuthHash, &1u8),
+            Sha256.hash(&vec![HashDomain::AuthHash.tag(), 1u8.to_bytes()])
+        );
+    }
+
+    #[test]
+    fn digest_hash_without_domain_matches_sha256() {
+        let hash = DigestHash::<sha2::Sha256>::new();
+        assert_eq!(hash.hash(&1u8), Sha256.hash(&1u8));
+        assert_eq!(hash.hash(&vec![7u8; 32]), Sha256.hash(&vec![7u8; 32]));
+    }
+
+    #[test]
+    fn digest_hash_domains_separate_hashes() {
+        let suite_a = DigestHash::<sha2::Sha512_256>::with_domain(b"suite-a");
+        let suite_b = DigestHash::<sha2::Sha512_256>::with_domain(b"suite-b");
+        assert_ne!(suite_a.hash(&1u8), suite_b.hash(&1u8));
+        assert_ne!(
+            suite_a.hash(&1u8),
+            DigestHash::<sha2::Sha512_256>::new().hash(&1u8)
+        );
+        assert_eq!(suite_a.hash(&1u8), suite_a.clone().hash(&1u8));
+        // the length of the tag keeps a tag from running into the bytes being hashed
+        assert_ne!(
+            DigestHash::<sha2::Sha512_256>::with_domain(b"ab").hash(&b'c'),
+            DigestHash::<sha2::Sha512_256>::with_domain(b"a").hash(&(&b'b', &b'c'))
+        );
+    }
 }
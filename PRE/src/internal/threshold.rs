@@ -0,0 +1,245 @@
+//! Shamir t-of-n sharing of private keys, and decryption from the partial decryptions of the shares.
+//!
+//! A private key `k` is split by choosing a random polynomial `f` of degree `t - 1` over Fr with `f(0) = k`, and
+//! share `i` is `f(i)`. Decryption only multiplies a few points of the encrypted value by `k` (see `key_points`),
+//! so each shareholder multiplies those points by its share instead. Point multiplication is linear in the scalar,
+//! so interpolating the partial decryptions of any `t` shares at zero gives the points multiplied by `k`, and the
+//! rest of the decryption goes on as usual without `k` being reconstructed.
+use crate::internal::bit_repr::BitRepr;
+use crate::internal::curve::CurvePoints;
+use crate::internal::ed25519::Ed25519Signing;
+use crate::internal::field::{ExtensionField, Field};
+use crate::internal::fp12elem::Fp12Elem;
+use crate::internal::hashable::Hashable;
+use crate::internal::homogeneouspoint::HomogeneousPoint;
+use crate::internal::pairing::{Pairing, PairingConfig};
+use crate::internal::sha256::Sha256Hashing;
+use crate::internal::{
+    EncryptedValue, ErrorOr, InternalError, KValue, PrivateKey, SignedValue,
+    compute_and_compare_auth_hash, unwrap_reencryption_blocks, verify_signed_value,
+};
+use gridiron::digits::constant_time_primitives::ConstantSwap;
+use num_traits::Zero;
+
+/// Split `secret` into `share_count` shares, any `threshold` of which determine it. `random` supplies the other
+/// coefficients of the polynomial.
+///
+/// # Return
+/// The shares as `(i, f(i))` for `i` in `1..=share_count`.
+pub fn split_secret<FR, R>(
+    secret: FR,
+    threshold: u32,
+    share_count: u32,
+    mut random: R,
+) -> Vec<(u32, FR)>
+where
+    FR: Field + From<u32>,
+    R: FnMut() -> FR,
+{
+    assert!(
+        (1..=share_count).contains(&threshold),
+        "Developer error: the threshold must be between 1 and the number of shares"
+    );
+    let coefficients: Vec<FR> = std::iter::once(secret)
+        .chain((1..threshold).map(|_| random()))
+        .collect();
+    (1..=share_count)
+        .map(|i| {
+            let x = FR::from(i);
+            // Horner's rule, starting from the highest coefficient
+            let y = coefficients
+                .iter()
+                .rev()
+                .fold(FR::zero(), |acc, coefficient| acc * x + *coefficient);
+            (i, y)
+        })
+        .collect()
+}
+
+/// The Lagrange coefficients for interpolating at zero from the shares at `indices`, which must be distinct.
+pub fn lagrange_coefficients<FR>(indices: &[u32]) -> Vec<FR>
+where
+    FR: Field + From<u32>,
+{
+    indices
+        .iter()
+        .map(|i| {
+            let x_i = FR::from(*i);
+            let (numerator, denominator) = indices.iter().filter(|j| *j != i).fold(
+                (FR::one(), FR::one()),
+                |(numerator, denominator), j| {
+                    let x_j = FR::from(*j);
+                    (numerator * x_j, denominator * (x_j - x_i))
+                },
+            );
+            numerator / denominator
+        })
+        .collect()
+}
+
+/// The points of `encrypted_value` that decryption multiplies by the private key: the ephemeral public key of an
+/// `EncryptedOnceValue`, or the public key and random reencryption public key of the last reencryption block of a
+/// `ReencryptedValue`.
+pub fn key_points<FP: Field + Hashable>(
+    encrypted_value: &EncryptedValue<FP>,
+) -> Vec<HomogeneousPoint<FP>> {
+    match encrypted_value {
+        EncryptedValue::EncryptedOnce(encrypted_once_value) => {
+            vec![encrypted_once_value.ephemeral_public_key.value]
+        }
+        EncryptedValue::Reencrypted(re_value) => {
+            let re_block_last = re_value.encryption_blocks.last();
+            vec![
+                re_block_last.public_key.value,
+                re_block_last.rand_re_public_key.value,
+            ]
+        }
+    }
+}
+
+/// Partially decrypt a signed encrypted value with one share of a private key.
+///
+/// # Return
+/// The `key_points` of the value multiplied by the share, or an error if the signature of the value is invalid.
+pub fn decrypt_partial<FP, G>(
+    private_key_share: PrivateKey<FP>,
+    signed_encrypted_value: SignedValue<EncryptedValue<FP>>,
+    signing: &G,
+) -> ErrorOr<Vec<HomogeneousPoint<FP>>>
+where
+    FP: Field + Hashable + BitRepr + ConstantSwap,
+    G: Ed25519Signing,
+{
+    let encrypted_value = verify_signed_value(signed_encrypted_value, signing)
+        .ok_or(InternalError::InvalidEncryptedMessageSignature)?;
+    Ok(key_points(&encrypted_value)
+        .into_iter()
+        .map(|point| point * private_key_share.value)
+        .collect())
+}
+
+/// Decrypt a signed encrypted value from the partial decryptions of at least `threshold` distinct shares.
+///
+/// # Arguments
+/// `signed_encrypted_value`  - output of encrypt() or reencrypt()
+/// `partial_decryptions`     - the share index and output of `decrypt_partial` for each share. The indices must be
+///                             distinct and each output must have one point for each of the `key_points` of the value.
+///
+/// # Return
+/// The decrypted value. If there are fewer partial decryptions than the threshold the interpolated points are wrong,
+/// so the auth hash doesn't match and the result is `AuthHashMatchFailed`.
+pub fn decrypt_combined<FP, FR, H, G>(
+    signed_encrypted_value: SignedValue<EncryptedValue<FP>>,
+    partial_decryptions: &[(u32, Vec<HomogeneousPoint<FP>>)],
+    pairing: &Pairing<FP>,
+    curve_points: &CurvePoints<FP>,
+    hash: &H,
+    signing: &G,
+) -> ErrorOr<Fp12Elem<FP>>
+where
+    FP: ExtensionField
+        + PairingConfig
+        + BitRepr
+        + Hashable
+        + From<[u8; 64]>
+        + Default
+        + ConstantSwap,
+    FR: Field + From<u32> + BitRepr,
+    H: Sha256Hashing,
+    G: Ed25519Signing,
+{
+    let encrypted_value = verify_signed_value(signed_encrypted_value, signing)
+        .ok_or(InternalError::InvalidEncryptedMessageSignature)?;
+    let indices: Vec<u32> = partial_decryptions.iter().map(|(i, _)| *i).collect();
+    let coefficients: Vec<FR> = lagrange_coefficients(&indices);
+    // each of the key points multiplied by the private key, interpolated from the partial decryptions
+    let key_products: Vec<HomogeneousPoint<FP>> = (0..key_points(&encrypted_value).len())
+        .map(|n| {
+            partial_decryptions.iter().zip(&coefficients).fold(
+                HomogeneousPoint::zero(),
+                |acc, ((_, points), coefficient)| acc + points[n] * *coefficient,
+            )
+        })
+        .collect();
+    match encrypted_value {
+        EncryptedValue::EncryptedOnce(encrypted_once_value) => {
+            let unverified_plaintext = encrypted_once_value.encrypted_message
+                * pairing.pair_prepared(-key_products[0], &curve_points.g1_prepared)?;
+            compute_and_compare_auth_hash(
+                encrypted_once_value.auth_hash,
+                encrypted_once_value.ephemeral_public_key,
+                unverified_plaintext,
+                hash,
+                encrypted_once_value.version,
+            )
+        }
+        EncryptedValue::Reencrypted(re_value) => {
+            let re_block_last = re_value.encryption_blocks.last();
+            let sec_to_last_k = KValue(
+                re_block_last.encrypted_temp_key
+                    * pairing.pair_prepared(-key_products[0], &curve_points.g1_prepared)?,
+            );
+            let sec_to_last_rand_re_k = KValue(
+                re_block_last.encrypted_rand_re_temp_key
+                    * pairing.pair_prepared(-key_products[1], &curve_points.g1_prepared)?,
+            );
+            let unverified_plaintext = unwrap_reencryption_blocks(
+                &re_value,
+                sec_to_last_k,
+                sec_to_last_rand_re_k,
+                curve_points,
+                pairing,
+                hash,
+            )?;
+            compute_and_compare_auth_hash(
+                re_value.auth_hash,
+                re_value.ephemeral_public_key,
+                unverified_plaintext,
+                hash,
+                re_value.version,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internal::fp::fr_256::Fr256;
+    use crate::internal::schnorr::test::arb_fr256;
+    use num_traits::One;
+    use proptest::prelude::*;
+
+    #[test]
+    fn lagrange_coefficients_sum_to_one() {
+        // interpolating the constant polynomial 1 at zero
+        let coefficients: Vec<Fr256> = lagrange_coefficients(&[2, 5, 7]);
+        assert_eq!(
+            coefficients
+                .into_iter()
+                .fold(Fr256::zero(), |acc, c| acc + c),
+            Fr256::one()
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn any_threshold_shares_recover_secret(
+            secret in arb_fr256(),
+            coefficient_seeds in prop::collection::vec(any::<u32>(), 2),
+        ) {
+            let mut seeds = coefficient_seeds.into_iter();
+            let shares = split_secret(secret, 3, 5, || Fr256::from(seeds.next().unwrap()));
+            prop_assert_eq!(shares.len(), 5);
+            for chosen in [[0, 1, 2], [0, 3, 4], [4, 2, 1]] {
+                let indices: Vec<u32> = chosen.iter().map(|c| shares[*c].0).collect();
+                let coefficients: Vec<Fr256> = lagrange_coefficients(&indices);
+                let recovered = chosen
+                    .iter()
+                    .zip(coefficients)
+                    .fold(Fr256::zero(), |acc, (c, coefficient)| acc + shares[*c].1 * coefficient);
+                prop_assert_eq!(recovered, secret);
+            }
+        }
+    }
+}
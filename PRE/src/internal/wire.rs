@@ -0,0 +1,320 @@
+//! Canonical binary encoding shared by the composite public API types.
+//!
+//! Every encoding starts with a two byte header: the format version followed by a tag that names
+//! the encoded type (and, for curve dependent types, the curve). Each field after the header is a
+//! big-endian `u32` length followed by that many bytes. Decoding is strict: unknown versions,
+//! unexpected tags, wrong field lengths and trailing bytes are all rejected.
+//!
+//! Types that contain curve points also have a compact variant, marked by the `COMPACT` flag in the
+//! tag, in which the points are stored compressed. The fields are otherwise the same.
+//!
+//! Encrypted values and transform keys of `ProtocolVersion::V2` have the `PROTOCOL_V2` flag set in
+//! their tag. Legacy values and keys are encoded exactly as before the protocol version was added.
+use crate::api_common::RecryptErr;
+use crate::internal::ByteVector;
+use crate::internal::bytedecoder::DecodeErr;
+use crate::internal::sha256::ProtocolVersion;
+use std::convert::TryInto;
+
+/// Current version of the wire format
+pub const WIRE_VERSION: u8 = 1;
+
+const HEADER_LEN: usize = 2;
+const LENGTH_PREFIX_LEN: usize = 4;
+
+pub const TAG_ENCRYPTED_ONCE_VALUE_256: u8 = 0x01;
+pub const TAG_TRANSFORMED_VALUE_256: u8 = 0x02;
+pub const TAG_TRANSFORM_BLOCK_256: u8 = 0x03;
+pub const TAG_TRANSFORM_KEY_256: u8 = 0x04;
+pub const TAG_ENCRYPTED_ONCE_VALUE_480: u8 = 0x11;
+pub const TAG_TRANSFORMED_VALUE_480: u8 = 0x12;
+pub const TAG_TRANSFORM_BLOCK_480: u8 = 0x13;
+pub const TAG_TRANSFORM_KEY_480: u8 = 0x14;
+pub const TAG_SIGNING_KEYPAIR: u8 = 0x20;
+
+/// Flag set in the tag of a compact encoding
+pub const COMPACT: u8 = 0x40;
+
+/// `tag`, with the `COMPACT` flag set if `compact` is true.
+pub fn tag(tag: u8, compact: bool) -> u8 {
+    if compact { tag | COMPACT } else { tag }
+}
+
+/// Whether `tag` names a compact encoding.
+pub fn is_compact(tag: u8) -> bool {
+    tag & COMPACT == COMPACT
+}
+
+/// Flag set in the tag of an encrypted value or transform key of `ProtocolVersion::V2`
+pub const PROTOCOL_V2: u8 = 0x80;
+
+/// `tag`, with the `COMPACT` flag set if `compact` is true and the `PROTOCOL_V2` flag set for
+/// `ProtocolVersion::V2`.
+pub fn versioned_tag(tag: u8, compact: bool, version: ProtocolVersion) -> u8 {
+    match version {
+        ProtocolVersion::Legacy => self::tag(tag, compact),
+        ProtocolVersion::V2 => self::tag(tag, compact) | PROTOCOL_V2,
+    }
+}
+
+/// The protocol version named by `tag`.
+pub fn protocol_version(tag: u8) -> ProtocolVersion {
+    if tag & PROTOCOL_V2 == PROTOCOL_V2 {
+        ProtocolVersion::V2
+    } else {
+        ProtocolVersion::Legacy
+    }
+}
+
+/// `tag` without its `COMPACT` and `PROTOCOL_V2` flags.
+pub fn base_tag(tag: u8) -> u8 {
+    tag & !(COMPACT | PROTOCOL_V2)
+}
+
+/// Every form of the versioned `tag`: plain and compact, for each protocol version.
+pub fn versioned_tags(tag: u8) -> [u8; 4] {
+    [
+        versioned_tag(tag, false, ProtocolVersion::Legacy),
+        versioned_tag(tag, true, ProtocolVersion::Legacy),
+        versioned_tag(tag, false, ProtocolVersion::V2),
+        versioned_tag(tag, true, ProtocolVersion::V2),
+    ]
+}
+
+/// Builds a canonical encoding one field at a time.
+pub struct WireWriter {
+    bytes: ByteVector,
+}
+
+impl WireWriter {
+    pub fn new(tag: u8) -> WireWriter {
+        WireWriter {
+            bytes: vec![WIRE_VERSION, tag],
+        }
+    }
+
+    /// Append a length-prefixed field.
+    pub fn field(mut self, field: &[u8]) -> WireWriter {
+        self.put_len(field.len());
+        self.bytes.extend_from_slice(field);
+        self
+    }
+
+    /// Append a field made of two halves, such as the x and y coordinates of a public key.
+    pub fn field_pair(mut self, first: &[u8], second: &[u8]) -> WireWriter {
+        self.put_len(first.len() + second.len());
+        self.bytes.extend_from_slice(first);
+        self.bytes.extend_from_slice(second);
+        self
+    }
+
+    /// Append the number of elements of a repeated field.
+    pub fn count(mut self, count: usize) -> WireWriter {
+        self.put_len(count);
+        self
+    }
+
+    pub fn finish(self) -> ByteVector {
+        self.bytes
+    }
+
+    fn put_len(&mut self, n: usize) {
+        let n: u32 = n
+            .try_into()
+            .expect("Developer error: wire fields are never larger than u32::MAX bytes");
+        self.bytes.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+/// Reads a canonical encoding produced by `WireWriter`.
+pub struct WireReader<'a> {
+    all: &'a [u8],
+    rest: &'a [u8],
+}
+
+impl<'a> WireReader<'a> {
+    /// Check the header of `bytes` and return a reader positioned at the first field.
+    pub fn new(bytes: &'a [u8], expected_tags: &[u8]) -> Result<(u8, WireReader<'a>), DecodeErr> {
+        let reader = WireReader {
+            all: bytes,
+            rest: bytes,
+        };
+        match bytes {
+            [version, tag, rest @ ..] if *version == WIRE_VERSION => {
+                if expected_tags.contains(tag) {
+                    Ok((*tag, WireReader { all: bytes, rest }))
+                } else {
+                    Err(reader.invalid(format!("unexpected type tag {:#04x}", tag)))
+                }
+            }
+            [version, _, ..] => Err(reader.invalid(format!("unsupported version {}", version))),
+            _ => Err(DecodeErr::BytesNotCorrectLength {
+                required_length: HEADER_LEN,
+                bad_bytes: bytes.to_vec(),
+            }),
+        }
+    }
+
+    /// Read the next length-prefixed field.
+    pub fn field(&mut self) -> Result<&'a [u8], DecodeErr> {
+        let len = self.count()?;
+        if self.rest.len() < len {
+            return Err(self.invalid(format!(
+                "field of {} bytes extends past the end of the input",
+                len
+            )));
+        }
+        let (field, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Ok(field)
+    }
+
+    /// Read the next field and split it into two halves of `half_len` bytes each.
+    pub fn field_pair(&mut self, half_len: usize) -> Result<(&'a [u8], &'a [u8]), DecodeErr> {
+        let field = self.field()?;
+        if field.len() != half_len * 2 {
+            return Err(DecodeErr::BytesNotCorrectLength {
+                required_length: half_len * 2,
+                bad_bytes: field.to_vec(),
+            });
+        }
+        Ok(field.split_at(half_len))
+    }
+
+    /// Read the next field and construct a value from it with `new_from_slice`.
+    pub fn read<T, F>(&mut self, new_from_slice: F) -> Result<T, DecodeErr>
+    where
+        F: FnOnce(&'a [u8]) -> Result<T, RecryptErr>,
+    {
+        let field = self.field()?;
+        new_from_slice(field).map_err(field_err(field))
+    }
+
+    /// Read the next two-part field and construct a value from its halves with `new_from_slice`.
+    pub fn read_pair<T, F>(&mut self, half_len: usize, new_from_slice: F) -> Result<T, DecodeErr>
+    where
+        F: FnOnce((&'a [u8], &'a [u8])) -> Result<T, RecryptErr>,
+    {
+        let (first, second) = self.field_pair(half_len)?;
+        new_from_slice((first, second)).map_err(|err| {
+            let mut field = first.to_vec();
+            field.extend_from_slice(second);
+            field_err(&field)(err)
+        })
+    }
+
+    /// Read the number of elements of a repeated field.
+    pub fn count(&mut self) -> Result<usize, DecodeErr> {
+        if self.rest.len() < LENGTH_PREFIX_LEN {
+            return Err(self.invalid("input ended inside a length prefix"));
+        }
+        let (prefix, rest) = self.rest.split_at(LENGTH_PREFIX_LEN);
+        self.rest = rest;
+        let prefix: [u8; LENGTH_PREFIX_LEN] = prefix.try_into().expect("split at prefix length");
+        Ok(u32::from_be_bytes(prefix) as usize)
+    }
+
+    /// Ensure the whole input was consumed.
+    pub fn finish(self) -> Result<(), DecodeErr> {
+        if self.rest.is_empty() {
+            Ok(())
+        } else {
+            Err(self.invalid(format!("{} trailing bytes", self.rest.len())))
+        }
+    }
+
+    /// Error describing why the input being read is invalid.
+    pub fn invalid<S: Into<String>>(&self, message: S) -> DecodeErr {
+        DecodeErr::BytesInvalid {
+            message: message.into(),
+            bad_bytes: self.all.to_vec(),
+        }
+    }
+}
+
+/// Convert the error produced while constructing a decoded field into a `DecodeErr`.
+fn field_err(field: &[u8]) -> impl FnOnce(RecryptErr) -> DecodeErr + '_ {
+    move |err| match err {
+        RecryptErr::InputWrongSize(_, required_length) => DecodeErr::BytesNotCorrectLength {
+            required_length,
+            bad_bytes: field.to_vec(),
+        },
+        RecryptErr::DecodeFailure(err) => err,
+        other => DecodeErr::BytesInvalid {
+            message: other.to_string(),
+            bad_bytes: field.to_vec(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip_fields() {
+        let bytes = WireWriter::new(TAG_SIGNING_KEYPAIR)
+            .field(&[1, 2, 3])
+            .count(2)
+            .field_pair(&[4], &[5])
+            .finish();
+        assert_eq!(
+            bytes,
+            vec![1, 0x20, 0, 0, 0, 3, 1, 2, 3, 0, 0, 0, 2, 0, 0, 0, 2, 4, 5]
+        );
+
+        let (tag, mut reader) = WireReader::new(&bytes, &[TAG_SIGNING_KEYPAIR]).unwrap();
+        assert_eq!(tag, TAG_SIGNING_KEYPAIR);
+        assert_eq!(reader.field().unwrap(), &[1, 2, 3]);
+        assert_eq!(reader.count().unwrap(), 2);
+        assert_eq!(reader.field_pair(1).unwrap(), (&[4u8][..], &[5u8][..]));
+        assert!(reader.finish().is_ok());
+    }
+
+    #[test]
+    fn compact_flag() {
+        let compact = tag(TAG_TRANSFORM_BLOCK_480, true);
+        assert_eq!(compact, 0x53);
+        assert!(is_compact(compact));
+        assert!(!is_compact(tag(TAG_TRANSFORM_BLOCK_480, false)));
+    }
+
+    #[test]
+    fn protocol_version_flag() {
+        let v2 = versioned_tag(TAG_TRANSFORMED_VALUE_256, true, ProtocolVersion::V2);
+        assert_eq!(v2, 0xc2);
+        assert!(is_compact(v2));
+        assert_eq!(protocol_version(v2), ProtocolVersion::V2);
+        assert_eq!(base_tag(v2), TAG_TRANSFORMED_VALUE_256);
+        let legacy = versioned_tag(TAG_TRANSFORMED_VALUE_256, false, ProtocolVersion::Legacy);
+        assert_eq!(legacy, TAG_TRANSFORMED_VALUE_256);
+        assert_eq!(protocol_version(legacy), ProtocolVersion::Legacy);
+    }
+
+    #[test]
+    fn rejects_bad_header() {
+        assert!(WireReader::new(&[WIRE_VERSION], &[TAG_SIGNING_KEYPAIR]).is_err());
+        assert!(WireReader::new(&[2, TAG_SIGNING_KEYPAIR], &[TAG_SIGNING_KEYPAIR]).is_err());
+        assert!(WireReader::new(&[WIRE_VERSION, 0x7f], &[TAG_SIGNING_KEYPAIR]).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_and_trailing_bytes() {
+        let bytes = WireWriter::new(TAG_SIGNING_KEYPAIR)
+            .field(&[1, 2, 3])
+            .finish();
+
+        let (_, mut reader) =
+            WireReader::new(&bytes[..bytes.len() - 1], &[TAG_SIGNING_KEYPAIR]).unwrap();
+        assert!(reader.field().is_err());
+
+        let (_, mut reader) = WireReader::new(&bytes[..4], &[TAG_SIGNING_KEYPAIR]).unwrap();
+        assert!(reader.field().is_err());
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        let (_, mut reader) = WireReader::new(&trailing, &[TAG_SIGNING_KEYPAIR]).unwrap();
+        reader.field().unwrap();
+        assert!(reader.finish().is_err());
+    }
+}
@@ -128,6 +128,32 @@
 //! let from_bytes_json_y_as_tuple: &[u8] = &from_bytes_json_y;
 //! let pub_key_from_bytes_json = PublicKey::new_from_slice((from_bytes_json_x_as_tuple, from_bytes_json_y_as_tuple)).unwrap();
 //! ```
+//!
+//! ## serde support
+//!
+//! With the `serde` feature enabled, `PublicKey`, `PrivateKey`, `Plaintext`, `SchnorrSignature`, `EncryptedValue` and
+//! `TransformKey` (from both `api` and `api_480`) implement `Serialize` and `Deserialize`. Human-readable formats such as
+//! JSON get a hex string, binary formats get the raw bytes. `EncryptedValue` and `TransformKey` use the canonical wire
+//! format (see `EncryptedValue::to_wire_bytes`). Public keys are checked to be valid points when they are deserialized.
+//! ```rust
+//! # #[cfg(feature = "serde")]
+//! # {
+//! use recrypt::prelude::*;
+//! use recrypt::api::{EncryptedValue, PublicKey};
+//! let recrypt = Recrypt::new();
+//! let (priv_key, pub_key) = recrypt.generate_key_pair().unwrap();
+//! let signing_keypair = recrypt.generate_ed25519_key_pair();
+//!
+//! let pub_key_json = serde_json::to_string(&pub_key).unwrap();
+//! let pub_key: PublicKey = serde_json::from_str(&pub_key_json).unwrap();
+//!
+//! let pt = recrypt.gen_plaintext();
+//! let encrypted_val = recrypt.encrypt(&pt, &pub_key, &signing_keypair).unwrap();
+//! let encrypted_json = serde_json::to_string(&encrypted_val).unwrap();
+//! let encrypted_val: EncryptedValue = serde_json::from_str(&encrypted_json).unwrap();
+//! assert_eq!(recrypt.decrypt(encrypted_val, &priv_key).unwrap(), pt);
+//! # }
+//! ```
 
 pub mod prelude;
 #[macro_use] // this is still required in Rust 2018
@@ -135,4 +161,6 @@ mod internal; // this needs to come before `api` as api relies on macros defined
 pub mod api;
 pub mod api_480;
 mod api_common;
+#[cfg(feature = "serde")]
+mod api_serde;
 pub mod nonemptyvec;
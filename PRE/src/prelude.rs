@@ -16,4 +16,7 @@ pub use crate::api::Sha256;
 pub use crate::api::CryptoOps;
 pub use crate::api::Ed25519Ops;
 pub use crate::api::KeyGenOps;
+pub use crate::api::KeyRotationOps;
 pub use crate::api::SchnorrOps;
+pub use crate::api::SenderAuthOps;
+pub use crate::api::ThresholdOps;